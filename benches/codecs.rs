@@ -0,0 +1,67 @@
+use binius_core::linear_code::LinearCode;
+use binius_core::reed_solomon::reed_solomon::ReedSolomonCode;
+use binius_ntt::NTTOptions;
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use rand::rngs::StdRng;
+use rand::{CryptoRng, RngCore, SeedableRng};
+use zoda_rs::codecs::{Codec, Rs8Codec, SimdCodec};
+use zoda_rs::tree::Felt;
+
+const SHARE_SIZE: usize = 512;
+// fixed so runs encode the same bytes and stay comparable
+const SEED: u64 = 0x5eed;
+
+fn random_shares(rng: &mut (impl RngCore + CryptoRng), k: usize) -> Vec<Vec<u8>> {
+    (0..k)
+        .map(|_| {
+            let mut share = vec![0u8; SHARE_SIZE];
+            rng.fill_bytes(&mut share);
+            share
+        })
+        .collect()
+}
+
+// Encodes one axis of k shares with each backend. The binius path encodes the
+// same bytes as SHARE_SIZE / 16 codewords of k felts each.
+fn bench_encode_axis(c: &mut Criterion) {
+    let mut group = c.benchmark_group("encode_axis");
+    let mut rng = StdRng::seed_from_u64(SEED);
+    for k in [16usize, 64, 128] {
+        let shares = random_shares(&mut rng, k);
+
+        group.bench_with_input(BenchmarkId::new("rs-8", k), &shares, |b, shares| {
+            b.iter(|| Rs8Codec.encode(shares.clone()).unwrap())
+        });
+
+        group.bench_with_input(BenchmarkId::new("rs-simd", k), &shares, |b, shares| {
+            b.iter(|| SimdCodec.encode(shares.clone()).unwrap())
+        });
+
+        let encoder =
+            ReedSolomonCode::<Felt>::new(k.trailing_zeros() as usize, 1, NTTOptions::default())
+                .unwrap();
+        let lanes: Vec<Vec<Felt>> = (0..SHARE_SIZE / 16)
+            .map(|lane| {
+                shares
+                    .iter()
+                    .map(|share| {
+                        let chunk = &share[lane * 16..(lane + 1) * 16];
+                        Felt::new(u128::from_be_bytes(chunk.try_into().unwrap()))
+                    })
+                    .collect()
+            })
+            .collect();
+
+        group.bench_with_input(BenchmarkId::new("binius-rs-128", k), &lanes, |b, lanes| {
+            b.iter(|| {
+                for lane in lanes {
+                    encoder.encode(lane.clone()).unwrap();
+                }
+            })
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_encode_axis);
+criterion_main!(benches);
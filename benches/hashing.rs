@@ -0,0 +1,32 @@
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use zoda_rs::hashers::sha256_backend;
+use zoda_rs::tree::{axis_tree, leaf_hash, Felt};
+
+// Set ZODA_REPORT_BACKEND=1 to print which SHA-256 path was picked
+fn report_backend() {
+    if std::env::var_os("ZODA_REPORT_BACKEND").is_some() {
+        println!("sha256 backend: {}", sha256_backend().as_str());
+    }
+}
+
+fn bench_leaf_hashing(c: &mut Criterion) {
+    report_backend();
+
+    let mut group = c.benchmark_group("leaf_hashing");
+    for width in [64usize, 256, 1024] {
+        let axis: Vec<Felt> = (0..width as u128).map(Felt::new).collect();
+        group.throughput(Throughput::Elements(width as u64));
+
+        group.bench_with_input(BenchmarkId::new("leaves", width), &axis, |b, axis| {
+            b.iter(|| axis.iter().map(leaf_hash).collect::<Vec<_>>())
+        });
+
+        group.bench_with_input(BenchmarkId::new("axis_tree", width), &axis, |b, axis| {
+            b.iter(|| axis_tree(axis))
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_leaf_hashing);
+criterion_main!(benches);
@@ -0,0 +1,60 @@
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use zoda_rs::tree::{transpose, DataSquare, Felt, LeafStrategy};
+
+const STRATEGIES: [LeafStrategy; 2] = [LeafStrategy::PerFelt, LeafStrategy::PerShare];
+
+fn square(width: usize) -> Vec<Vec<Felt>> {
+    (0..width)
+        .map(|c| {
+            (0..width)
+                .map(|r| Felt::new(((c * width + r) as u128 + 1) * 0x9e37_79b9_7f4a_7c15))
+                .collect()
+        })
+        .collect()
+}
+
+// Set ZODA_LEAF_REPORT=1 to print tree and proof sizes next to the timings
+fn report_sizes() {
+    if std::env::var_os("ZODA_LEAF_REPORT").is_none() {
+        return;
+    }
+    println!("width strategy leaves tree_bytes felt_opening share_opening");
+    for width in [32usize, 128, 512] {
+        for strategy in STRATEGIES {
+            let report = strategy.report(width);
+            println!(
+                "{} {:?} {} {} {} {}",
+                width,
+                strategy,
+                report.leaves,
+                report.tree_bytes,
+                report.felt_opening_bytes,
+                report.share_opening_bytes
+            );
+        }
+    }
+}
+
+fn bench_commitment_trees(c: &mut Criterion) {
+    report_sizes();
+
+    let mut group = c.benchmark_group("commitment_tree");
+    for width in [32usize, 128, 512] {
+        let cols = square(width);
+        let rows = transpose(&cols);
+        for strategy in STRATEGIES {
+            let square = DataSquare::new(cols.clone())
+                .unwrap()
+                .with_leaf_strategy(strategy);
+            group.bench_with_input(
+                BenchmarkId::new(format!("{:?}", strategy), width),
+                &rows,
+                |b, rows| b.iter(|| square.create_tree(rows, rows).unwrap()),
+            );
+        }
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_commitment_trees);
+criterion_main!(benches);
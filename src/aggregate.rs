@@ -0,0 +1,130 @@
+// Combined column openings. Every column of the extended square is a codeword,
+// so any linear combination of columns is one too. Instead of opening each
+// sampled column, the prover opens a single verifier-weighted combination; the
+// verifier checks it is a codeword and spot checks it against a few committed
+// rows of the original columns.
+use anyhow::{bail, Result};
+
+use crate::proof::{Axis, CellProof};
+use crate::tree::{derive_dr, new_encoder, ExtendedDataSquare, Felt};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CombinedOpening {
+    // sampled columns, in the order of the coefficients
+    pub cols: Vec<usize>,
+    // sum of coeffs[k] * column cols[k]
+    pub combined: Vec<Felt>,
+    pub spot_checks: Vec<SpotCheck>,
+}
+
+// The cells of one row across every sampled column, each opened against its
+// column root
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SpotCheck {
+    pub row: usize,
+    pub cells: Vec<Felt>,
+    pub proofs: Vec<CellProof>,
+}
+
+// Verifier coefficients expanded from a seed, so they can be chosen after the
+// columns are fixed without a round trip
+pub fn coefficients_from_seed(seed: &[u8; 32], n: usize) -> Vec<Felt> {
+    derive_dr(seed, n)
+}
+
+impl ExtendedDataSquare {
+    pub fn open_combined(
+        &self,
+        cols: &[usize],
+        coeffs: &[Felt],
+        spot_rows: &[usize],
+    ) -> Result<CombinedOpening> {
+        let width = self.width();
+        if cols.len() != coeffs.len() {
+            bail!("{} columns but {} coefficients", cols.len(), coeffs.len());
+        }
+        if let Some(col) = cols.iter().find(|&&col| col >= width) {
+            bail!("column {} out of range for width {}", col, width);
+        }
+
+        let mut combined = vec![Felt::default(); width];
+        for (&col, &coeff) in cols.iter().zip(coeffs) {
+            for (acc, cell) in combined.iter_mut().zip(self.col(col)) {
+                *acc += coeff * *cell;
+            }
+        }
+
+        let spot_checks = spot_rows
+            .iter()
+            .map(|&row| {
+                let proofs = cols
+                    .iter()
+                    .map(|&col| self.prove_cell_in_col(row, col))
+                    .collect::<Result<Vec<_>>>()?;
+                Ok(SpotCheck {
+                    row,
+                    cells: cols.iter().map(|&col| self.cell(row, col)).collect(),
+                    proofs,
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(CombinedOpening {
+            cols: cols.to_vec(),
+            combined,
+            spot_checks,
+        })
+    }
+}
+
+impl CombinedOpening {
+    // `col_roots[k]` is the (already authenticated) root of column `cols[k]`
+    pub fn verify(&self, coeffs: &[Felt], col_roots: &[[u8; 32]]) -> Result<()> {
+        if coeffs.len() != self.cols.len() || col_roots.len() != self.cols.len() {
+            bail!(
+                "{} columns, {} coefficients and {} column roots",
+                self.cols.len(),
+                coeffs.len(),
+                col_roots.len()
+            );
+        }
+
+        let width = self.combined.len();
+        if width < 2 || !width.is_power_of_two() {
+            bail!("combined column has invalid length {}", width);
+        }
+        let half = width / 2;
+        if new_encoder(half)?.encode(self.combined[..half].to_vec())? != self.combined[half..] {
+            bail!("combined column is not a codeword");
+        }
+
+        for check in &self.spot_checks {
+            if check.row >= width
+                || check.cells.len() != self.cols.len()
+                || check.proofs.len() != self.cols.len()
+            {
+                bail!("malformed spot check at row {}", check.row);
+            }
+
+            for (k, (cell, proof)) in check.cells.iter().zip(&check.proofs).enumerate() {
+                if proof.axis != Axis::Col
+                    || proof.row != check.row
+                    || proof.col != self.cols[k]
+                    || proof.width != width
+                {
+                    bail!("spot check proof at row {} is for another cell", check.row);
+                }
+                if !proof.verify(cell, &col_roots[k]) {
+                    bail!("cell ({}, {}) does not verify", check.row, self.cols[k]);
+                }
+            }
+
+            let expected: Felt = check.cells.iter().zip(coeffs).map(|(c, a)| *c * *a).sum();
+            if expected != self.combined[check.row] {
+                bail!("combined column is wrong at row {}", check.row);
+            }
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,491 @@
+use std::collections::BTreeMap;
+use std::ops::RangeBounds;
+use std::sync::Mutex;
+
+use anyhow::{bail, Result};
+
+use crate::namespace::Namespace;
+use crate::store::EdsStore;
+use crate::tree::{DataSquare, ExtendedDataSquare, Felt, LeafStrategy};
+use crate::wire::Reader;
+
+// How much of a block the archive still holds
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Retention {
+    // the extended square, in the store
+    Full,
+    // the original square only, re-extended on access
+    Original,
+    // just the data root and axis roots
+    Roots,
+}
+
+// What `Archive::prune` keeps. A block's age is its distance from the tip.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PruningPolicy {
+    // blocks younger than this keep their parity, finalized or not
+    pub keep_parity: u64,
+    // finalized blocks at least this old are reduced to their roots, `None`
+    // keeps original data forever
+    pub keep_data: Option<u64>,
+}
+
+impl Default for PruningPolicy {
+    fn default() -> Self {
+        Self {
+            keep_parity: 100,
+            keep_data: None,
+        }
+    }
+}
+
+// Heights moving to a lower retention, see `Archive::plan_pruning`
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PruneReport {
+    pub to_original: Vec<u64>,
+    pub to_roots: Vec<u64>,
+}
+
+impl PruneReport {
+    pub fn is_empty(&self) -> bool {
+        self.to_original.is_empty() && self.to_roots.is_empty()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AxisRoots {
+    pub row_roots: Vec<[u8; 32]>,
+    pub col_roots: Vec<[u8; 32]>,
+}
+
+impl AxisRoots {
+    fn of(eds: &ExtendedDataSquare) -> Self {
+        Self {
+            row_roots: eds.row_roots().to_vec(),
+            col_roots: eds.col_roots().to_vec(),
+        }
+    }
+}
+
+// One block of the archive as it has to be persisted next to the store, see
+// `Archive::index_entries`. Fully retained blocks live in the store, pruned
+// ones carry what is left of them.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IndexEntry {
+    pub height: u64,
+    pub data_root: [u8; 32],
+    pub retention: Retention,
+    // `Original`: width (4) | context flag (1) | context (32, if flagged)
+    //   | leaf strategy (1) | q1 cells, column-major
+    // `Roots`: width (4) | row roots | column roots
+    // `Full`: empty
+    pruned: Vec<u8>,
+}
+
+impl IndexEntry {
+    // height (8) | data root (32) | retention (1) | pruned len (4) | pruned
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut out = Vec::with_capacity(45 + self.pruned.len());
+        out.extend_from_slice(&self.height.to_be_bytes());
+        out.extend_from_slice(&self.data_root);
+        out.push(match self.retention {
+            Retention::Full => 0,
+            Retention::Original => 1,
+            Retention::Roots => 2,
+        });
+        out.extend_from_slice(&(self.pruned.len() as u32).to_be_bytes());
+        out.extend_from_slice(&self.pruned);
+        out
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        let mut reader = Reader::new(bytes);
+        let height = u64::from_be_bytes(reader.take(8)?.try_into()?);
+        let data_root = reader.take(32)?.try_into()?;
+        let retention = match reader.u8()? {
+            0 => Retention::Full,
+            1 => Retention::Original,
+            2 => Retention::Roots,
+            id => bail!("unknown retention {}", id),
+        };
+        let len = reader.u32()? as usize;
+        let pruned = reader.take(len)?.to_vec();
+        reader.finish()?;
+        Ok(Self {
+            height,
+            data_root,
+            retention,
+            pruned,
+        })
+    }
+}
+
+fn encode_original(square: &DataSquare) -> Vec<u8> {
+    let mut out = (square.width() as u32).to_be_bytes().to_vec();
+    match square.context() {
+        Some(context) => {
+            out.push(1);
+            out.extend_from_slice(context);
+        }
+        None => out.push(0),
+    }
+    out.push(square.leaf_strategy().id());
+    for cell in square.cols().iter().flatten() {
+        out.extend_from_slice(&cell.val().to_be_bytes());
+    }
+    out
+}
+
+fn decode_original(bytes: &[u8]) -> Result<DataSquare> {
+    let mut reader = Reader::new(bytes);
+    let width = reader.u32()? as usize;
+    let context = match reader.u8()? {
+        0 => None,
+        1 => Some(reader.take(32)?.try_into()?),
+        flag => bail!("invalid context flag {}", flag),
+    };
+    let leaf_strategy = LeafStrategy::from_id(reader.u8()?)?;
+    // checked before allocating
+    let header = 6 + 32 * usize::from(context.is_some());
+    let cells = width
+        .checked_mul(width)
+        .and_then(|cells| cells.checked_mul(16));
+    if cells != Some(bytes.len() - header) {
+        bail!("wrong number of cells for width {}", width);
+    }
+    let cols = (0..width)
+        .map(|_| reader.felts(width))
+        .collect::<Result<_>>()?;
+    reader.finish()?;
+    Ok(DataSquare::try_new(cols)?
+        .with_context_digest(context)
+        .with_leaf_strategy(leaf_strategy))
+}
+
+fn encode_roots(roots: &AxisRoots) -> Vec<u8> {
+    let mut out = (roots.row_roots.len() as u32).to_be_bytes().to_vec();
+    for root in roots.row_roots.iter().chain(&roots.col_roots) {
+        out.extend_from_slice(root);
+    }
+    out
+}
+
+fn decode_roots(bytes: &[u8]) -> Result<AxisRoots> {
+    let mut reader = Reader::new(bytes);
+    let width = reader.u32()? as usize;
+    if width.checked_mul(64) != Some(bytes.len() - 4) {
+        bail!("wrong number of roots for width {}", width);
+    }
+    let mut roots = || -> Result<Vec<[u8; 32]>> {
+        (0..width)
+            .map(|_| Ok(reader.take(32)?.try_into()?))
+            .collect()
+    };
+    let (row_roots, col_roots) = (roots()?, roots()?);
+    reader.finish()?;
+    Ok(AxisRoots {
+        row_roots,
+        col_roots,
+    })
+}
+
+struct Entry {
+    data_root: [u8; 32],
+    data: Retained,
+}
+
+enum Retained {
+    Full,
+    // extending needs `&mut`
+    Original(Mutex<DataSquare>),
+    Roots(AxisRoots),
+}
+
+// Squares of many blocks, kept in an `EdsStore` and indexed by height. Blocks
+// with identical squares (e.g. empty ones) share a single stored copy.
+pub struct Archive<S> {
+    store: S,
+    heights: BTreeMap<u64, Entry>,
+}
+
+impl<S: EdsStore> Archive<S> {
+    pub fn new(store: S) -> Self {
+        Self {
+            store,
+            heights: BTreeMap::new(),
+        }
+    }
+
+    // Reopens an archive over a persisted store, from the entries previously
+    // returned by `index_entries`. Fully retained blocks have to be in the
+    // store, pruned ones are restored from their entries.
+    pub fn from_index(store: S, index: impl IntoIterator<Item = IndexEntry>) -> Result<Self> {
+        let mut archive = Self::new(store);
+        for entry in index {
+            let height = entry.height;
+            let data = match entry.retention {
+                Retention::Full => {
+                    if !archive.store.contains(&entry.data_root) {
+                        bail!("square of height {} is missing from the store", height);
+                    }
+                    Retained::Full
+                }
+                Retention::Original => {
+                    Retained::Original(Mutex::new(decode_original(&entry.pruned)?))
+                }
+                Retention::Roots => Retained::Roots(decode_roots(&entry.pruned)?),
+            };
+            let data_root = entry.data_root;
+            archive.heights.insert(height, Entry { data_root, data });
+        }
+        Ok(archive)
+    }
+
+    pub fn insert(&mut self, height: u64, eds: &ExtendedDataSquare) -> Result<[u8; 32]> {
+        if self.heights.contains_key(&height) {
+            bail!("height {} is already archived", height);
+        }
+        let data_root = self.store.put(eds)?;
+        let data = Retained::Full;
+        self.heights.insert(height, Entry { data_root, data });
+        Ok(data_root)
+    }
+
+    // Drops a height, and its square unless another height shares it
+    pub fn remove(&mut self, height: u64) -> Result<bool> {
+        let Some(entry) = self.heights.remove(&height) else {
+            return Ok(false);
+        };
+        if matches!(entry.data, Retained::Full) {
+            self.release(&entry.data_root)?;
+        }
+        Ok(true)
+    }
+
+    // Removes a square from the store once no fully retained height uses it
+    fn release(&mut self, data_root: &[u8; 32]) -> Result<()> {
+        let shared = self
+            .heights
+            .values()
+            .any(|entry| entry.data_root == *data_root && matches!(entry.data, Retained::Full));
+        if !shared {
+            self.store.remove(data_root)?;
+        }
+        Ok(())
+    }
+
+    // (height, data root) of every archived block, in height order
+    pub fn index(&self) -> impl Iterator<Item = (u64, [u8; 32])> + '_ {
+        self.heights
+            .iter()
+            .map(|(height, entry)| (*height, entry.data_root))
+    }
+
+    // Every archived block as it has to be persisted to reopen the archive
+    // with `from_index`, in height order
+    pub fn index_entries(&self) -> Vec<IndexEntry> {
+        self.heights
+            .iter()
+            .map(|(&height, entry)| {
+                let (retention, pruned) = match &entry.data {
+                    Retained::Full => (Retention::Full, Vec::new()),
+                    Retained::Original(square) => {
+                        let square = square.lock().unwrap_or_else(|e| e.into_inner());
+                        (Retention::Original, encode_original(&square))
+                    }
+                    Retained::Roots(roots) => (Retention::Roots, encode_roots(roots)),
+                };
+                IndexEntry {
+                    height,
+                    data_root: entry.data_root,
+                    retention,
+                    pruned,
+                }
+            })
+            .collect()
+    }
+
+    pub fn data_root(&self, height: u64) -> Option<[u8; 32]> {
+        self.heights.get(&height).map(|entry| entry.data_root)
+    }
+
+    pub fn retention(&self, height: u64) -> Option<Retention> {
+        self.heights.get(&height).map(|entry| match entry.data {
+            Retained::Full => Retention::Full,
+            Retained::Original(_) => Retention::Original,
+            Retained::Roots(_) => Retention::Roots,
+        })
+    }
+
+    pub fn store(&self) -> &S {
+        &self.store
+    }
+
+    // The square of `height`, `None` if it isn't archived or was pruned to its roots
+    pub fn get(&self, height: u64) -> Result<Option<ExtendedDataSquare>> {
+        let Some(entry) = self.heights.get(&height) else {
+            return Ok(None);
+        };
+        match &entry.data {
+            Retained::Full => self.store.get(&entry.data_root),
+            Retained::Original(square) => {
+                let mut square = square.lock().unwrap_or_else(|e| e.into_inner());
+                let eds = square.extend()?;
+                if eds.data_root()? != entry.data_root {
+                    bail!("re-extended square of height {} has another root", height);
+                }
+                Ok(Some(eds))
+            }
+            Retained::Roots(_) => Ok(None),
+        }
+    }
+
+    pub fn get_share(&self, height: u64, row: usize, col: usize) -> Result<Option<Felt>> {
+        let Some(eds) = self.get(height)? else {
+            return Ok(None);
+        };
+        if row >= eds.width() || col >= eds.width() {
+            bail!(
+                "cell ({}, {}) out of range for width {}",
+                row,
+                col,
+                eds.width()
+            );
+        }
+        Ok(Some(eds.cell(row, col)))
+    }
+
+    // The shares of `namespace` in every archived block within `heights`, as
+    // (height, row, col, share). Blocks pruned to their roots are skipped.
+    pub fn shares_in(
+        &self,
+        namespace: Namespace,
+        heights: impl RangeBounds<u64>,
+    ) -> Result<Vec<(u64, usize, usize, Felt)>> {
+        let mut shares = Vec::new();
+        for (&height, _) in self.heights.range(heights) {
+            let Some(eds) = self.get(height)? else {
+                continue;
+            };
+            shares.extend(
+                eds.shares_in(namespace)
+                    .map(|(row, col, share)| (height, row, col, share)),
+            );
+        }
+        Ok(shares)
+    }
+
+    // Row and column roots of `height`, whatever its retention
+    pub fn axis_roots(&self, height: u64) -> Result<Option<AxisRoots>> {
+        match self.heights.get(&height) {
+            Some(Entry {
+                data: Retained::Roots(roots),
+                ..
+            }) => Ok(Some(roots.clone())),
+            _ => Ok(self.get(height)?.map(|eds| AxisRoots::of(&eds))),
+        }
+    }
+
+    // What `prune` would do, without touching anything. `tip` is the latest
+    // height, `finalized` the latest final one.
+    pub fn plan_pruning(&self, policy: &PruningPolicy, tip: u64, finalized: u64) -> PruneReport {
+        let mut report = PruneReport::default();
+        for (&height, entry) in self.heights.range(..=finalized) {
+            let age = tip.saturating_sub(height);
+            let target = match policy.keep_data {
+                Some(keep_data) if age >= keep_data => Retention::Roots,
+                _ if age >= policy.keep_parity => Retention::Original,
+                _ => Retention::Full,
+            };
+            match (&entry.data, target) {
+                (Retained::Full | Retained::Original(_), Retention::Roots) => {
+                    report.to_roots.push(height)
+                }
+                (Retained::Full, Retention::Original) => report.to_original.push(height),
+                _ => {}
+            }
+        }
+        report
+    }
+
+    // Applies `policy` and reports what was pruned
+    pub fn prune(
+        &mut self,
+        policy: &PruningPolicy,
+        tip: u64,
+        finalized: u64,
+    ) -> Result<PruneReport> {
+        let report = self.plan_pruning(policy, tip, finalized);
+
+        for &height in &report.to_original {
+            let Some(eds) = self.get(height)? else {
+                bail!("square of height {} is missing from the store", height);
+            };
+            let square = Mutex::new(eds.shrink()?);
+            let entry = self.heights.get_mut(&height).expect("planned height");
+            entry.data = Retained::Original(square);
+            let data_root = entry.data_root;
+            self.release(&data_root)?;
+        }
+
+        for &height in &report.to_roots {
+            let Some(eds) = self.get(height)? else {
+                bail!("square of height {} is missing from the store", height);
+            };
+            let entry = self.heights.get_mut(&height).expect("planned height");
+            let was_full = matches!(entry.data, Retained::Full);
+            entry.data = Retained::Roots(AxisRoots::of(&eds));
+            let data_root = entry.data_root;
+            if was_full {
+                self.release(&data_root)?;
+            }
+        }
+
+        Ok(report)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::store::MemStore;
+
+    fn square(seed: u128) -> ExtendedDataSquare {
+        let cols = (0..4)
+            .map(|c| (0..4).map(|r| Felt::new(seed * 16 + r * 4 + c)).collect())
+            .collect();
+        DataSquare::new(cols).unwrap().extend().unwrap()
+    }
+
+    #[test]
+    fn reopens_pruned_archive() {
+        let mut archive = Archive::new(MemStore::default());
+        let squares: Vec<ExtendedDataSquare> = (1..=3).map(square).collect();
+        for (height, eds) in (1..).zip(&squares) {
+            archive.insert(height, eds).unwrap();
+        }
+        let policy = PruningPolicy {
+            keep_parity: 1,
+            keep_data: Some(2),
+        };
+        archive.prune(&policy, 3, 3).unwrap();
+        assert_eq!(archive.index().count(), 3);
+
+        let entries: Vec<IndexEntry> = archive
+            .index_entries()
+            .iter()
+            .map(|entry| IndexEntry::from_bytes(&entry.to_bytes()).unwrap())
+            .collect();
+        let Archive { store, .. } = archive;
+        let reopened = Archive::from_index(store, entries).unwrap();
+
+        assert_eq!(reopened.retention(1), Some(Retention::Roots));
+        assert_eq!(reopened.retention(2), Some(Retention::Original));
+        assert_eq!(reopened.retention(3), Some(Retention::Full));
+        let roots = reopened.axis_roots(1).unwrap().unwrap();
+        assert_eq!(roots.row_roots, squares[0].row_roots());
+        let eds = reopened.get(2).unwrap().unwrap();
+        assert_eq!(eds.data_root().unwrap(), squares[1].data_root().unwrap());
+        assert!(reopened.get(3).unwrap().is_some());
+    }
+}
@@ -0,0 +1,142 @@
+use std::collections::BTreeMap;
+
+use anyhow::{bail, Result};
+use sha2::{Digest, Sha256};
+
+use crate::signature::{Signature, Signer};
+
+// What a sampler vouches for: that `samples` shares of the square under
+// `data_root` verified
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct AvailabilityStatement {
+    pub data_root: [u8; 32],
+    // width of the extended square
+    pub width: usize,
+    pub samples: usize,
+}
+
+impl AvailabilityStatement {
+    // The digest attesters sign, domain separated from `SignedRoot`
+    pub fn digest(&self) -> [u8; 32] {
+        let mut hasher = Sha256::new();
+        hasher.update(b"zoda-rs/availability/v1");
+        hasher.update(self.data_root);
+        hasher.update((self.width as u64).to_be_bytes());
+        hasher.update((self.samples as u64).to_be_bytes());
+        hasher.finalize().into()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Attestation {
+    pub statement: AvailabilityStatement,
+    pub signature: Signature,
+}
+
+impl Attestation {
+    pub fn sign(statement: AvailabilityStatement, signer: &impl Signer) -> Result<Self> {
+        Ok(Self {
+            statement,
+            signature: signer.sign(&statement.digest())?,
+        })
+    }
+
+    pub fn verify(&self) -> Result<()> {
+        self.signature.verify(&self.statement.digest())
+    }
+}
+
+// Collects attestations for one data root from a fixed committee, until
+// `threshold` distinct members vouched for it with at least `min_samples`
+// samples each
+pub struct AttestationSet {
+    data_root: [u8; 32],
+    committee: Vec<Vec<u8>>,
+    threshold: usize,
+    min_samples: usize,
+    // by public key, so a member is only counted once
+    attestations: BTreeMap<Vec<u8>, Attestation>,
+}
+
+impl AttestationSet {
+    // `committee` holds the members' public keys
+    pub fn new(
+        data_root: &[u8; 32],
+        committee: Vec<Vec<u8>>,
+        threshold: usize,
+        min_samples: usize,
+    ) -> Result<Self> {
+        if threshold == 0 || threshold > committee.len() {
+            bail!(
+                "threshold {} out of range for a committee of {}",
+                threshold,
+                committee.len()
+            );
+        }
+
+        Ok(Self {
+            data_root: *data_root,
+            committee,
+            threshold,
+            min_samples,
+            attestations: BTreeMap::new(),
+        })
+    }
+
+    // Verifies and adds an attestation. Returns false if its signer already
+    // attested.
+    pub fn add(&mut self, attestation: Attestation) -> Result<bool> {
+        self.check(&attestation)?;
+        let key = attestation.signature.public_key.clone();
+        if self.attestations.contains_key(&key) {
+            return Ok(false);
+        }
+        self.attestations.insert(key, attestation);
+        Ok(true)
+    }
+
+    fn check(&self, attestation: &Attestation) -> Result<()> {
+        let statement = &attestation.statement;
+        if statement.data_root != self.data_root {
+            bail!("attestation is for a different data root");
+        }
+        if statement.samples < self.min_samples {
+            bail!(
+                "attestation covers {} samples, {} required",
+                statement.samples,
+                self.min_samples
+            );
+        }
+        if !self.committee.contains(&attestation.signature.public_key) {
+            bail!("attestation is not signed by a committee member");
+        }
+        attestation.verify()
+    }
+
+    pub fn count(&self) -> usize {
+        self.attestations.len()
+    }
+
+    pub fn is_met(&self) -> bool {
+        self.count() >= self.threshold
+    }
+
+    pub fn attestations(&self) -> impl Iterator<Item = &Attestation> {
+        self.attestations.values()
+    }
+
+    // Re-checks every attestation and the threshold
+    pub fn verify(&self) -> Result<()> {
+        for attestation in self.attestations.values() {
+            self.check(attestation)?;
+        }
+        if !self.is_met() {
+            bail!(
+                "{} of the {} required attestations",
+                self.count(),
+                self.threshold
+            );
+        }
+        Ok(())
+    }
+}
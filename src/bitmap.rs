@@ -0,0 +1,156 @@
+use anyhow::{bail, Result};
+
+use crate::order::{row_major_coords, row_major_index};
+
+// Which cells of an extended square are present, one bit per cell in
+// row-major order (bit `i % 8` of byte `i / 8` for cell `i`)
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct AvailabilityBitmap {
+    // width of the extended square
+    width: usize,
+    bits: Vec<u8>,
+}
+
+impl AvailabilityBitmap {
+    pub fn new(width: usize) -> Self {
+        Self {
+            width,
+            bits: vec![0; (width * width).div_ceil(8)],
+        }
+    }
+
+    // Every cell present
+    pub fn full(width: usize) -> Self {
+        let mut bitmap = Self::new(width);
+        bitmap.bits.fill(0xff);
+        bitmap.clear_padding();
+        bitmap
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    fn index(&self, row: usize, col: usize) -> Option<usize> {
+        (row < self.width && col < self.width).then_some(row_major_index(row, col, self.width))
+    }
+
+    pub fn get(&self, row: usize, col: usize) -> bool {
+        self.index(row, col)
+            .is_some_and(|i| self.bits[i / 8] & (1 << (i % 8)) != 0)
+    }
+
+    pub fn set(&mut self, row: usize, col: usize, present: bool) {
+        let Some(i) = self.index(row, col) else {
+            return;
+        };
+        if present {
+            self.bits[i / 8] |= 1 << (i % 8);
+        } else {
+            self.bits[i / 8] &= !(1 << (i % 8));
+        }
+    }
+
+    // Number of present cells
+    pub fn count(&self) -> usize {
+        self.bits
+            .iter()
+            .map(|byte| byte.count_ones() as usize)
+            .sum()
+    }
+
+    // (row, col) of every present cell, row-major
+    pub fn iter(&self) -> impl Iterator<Item = (usize, usize)> + '_ {
+        let width = self.width;
+        (0..width * width)
+            .filter(move |i| self.bits[i / 8] & (1 << (i % 8)) != 0)
+            .map(move |i| row_major_coords(i, width))
+    }
+
+    // Adds every cell present in `other`
+    pub fn merge(&mut self, other: &Self) -> Result<()> {
+        self.check_width(other)?;
+        for (byte, other) in self.bits.iter_mut().zip(&other.bits) {
+            *byte |= other;
+        }
+        Ok(())
+    }
+
+    // Removes every cell present in `other`
+    pub fn subtract(&mut self, other: &Self) -> Result<()> {
+        self.check_width(other)?;
+        for (byte, other) in self.bits.iter_mut().zip(&other.bits) {
+            *byte &= !other;
+        }
+        Ok(())
+    }
+
+    // Keeps only the cells also present in `other`
+    pub fn intersect(&mut self, other: &Self) -> Result<()> {
+        self.check_width(other)?;
+        for (byte, other) in self.bits.iter_mut().zip(&other.bits) {
+            *byte &= other;
+        }
+        Ok(())
+    }
+
+    // Present cells become missing and the other way round
+    pub fn complement(&self) -> Self {
+        let mut bitmap = self.clone();
+        for byte in &mut bitmap.bits {
+            *byte = !*byte;
+        }
+        bitmap.clear_padding();
+        bitmap
+    }
+
+    fn check_width(&self, other: &Self) -> Result<()> {
+        if self.width != other.width {
+            bail!(
+                "bitmaps of widths {} and {} can't be combined",
+                self.width,
+                other.width
+            );
+        }
+        Ok(())
+    }
+
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.bits
+    }
+
+    pub fn from_bytes(width: usize, bytes: &[u8]) -> Result<Self> {
+        // checked before allocating, `width` may come off the wire
+        let expected = width.checked_mul(width).map(|cells| cells.div_ceil(8));
+        if expected != Some(bytes.len()) {
+            bail!(
+                "wrong number of bitmap bytes for width {}, got {}",
+                width,
+                bytes.len()
+            );
+        }
+        let mut bitmap = Self {
+            width,
+            bits: bytes.to_vec(),
+        };
+        if bitmap.clear_padding() {
+            bail!("bits set past the last cell");
+        }
+        Ok(bitmap)
+    }
+
+    // Zeroes the bits past the last cell, returns whether any were set
+    fn clear_padding(&mut self) -> bool {
+        let used = match (self.width * self.width) % 8 {
+            0 => 8,
+            used => used,
+        };
+        let Some(last) = self.bits.last_mut() else {
+            return false;
+        };
+        let mask = if used == 8 { 0xff } else { (1u8 << used) - 1 };
+        let padding = *last & !mask != 0;
+        *last &= mask;
+        padding
+    }
+}
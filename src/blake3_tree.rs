@@ -0,0 +1,71 @@
+use std::io::{Cursor, Read};
+
+use anyhow::{bail, Result};
+
+use crate::tree::{ExtendedDataSquare, Felt};
+
+// Blake3 (bao) tree over a row's bytes. The root is the plain blake3 hash of
+// the row, and the outboard tree lets a receiver verify the row 1 KiB chunk by
+// chunk while streaming, or verify any byte range on its own.
+pub struct Blake3Row {
+    pub root: [u8; 32],
+    pub outboard: Vec<u8>,
+}
+
+pub fn row_bytes(row: &[Felt]) -> Vec<u8> {
+    row.iter()
+        .flat_map(|elem| elem.val().to_be_bytes())
+        .collect()
+}
+
+pub fn encode_row(row: &[Felt]) -> Blake3Row {
+    let (outboard, root) = bao::encode::outboard(row_bytes(row));
+    Blake3Row {
+        root: root.into(),
+        outboard,
+    }
+}
+
+impl ExtendedDataSquare {
+    pub fn blake3_row(&self, i: usize) -> Result<Blake3Row> {
+        if i >= self.width() {
+            bail!("row {} out of range for width {}", i, self.width());
+        }
+        Ok(encode_row(&self.row(i).to_vec()))
+    }
+
+    pub fn blake3_row_roots(&self) -> Vec<[u8; 32]> {
+        self.rows()
+            .map(|row| encode_row(&row.to_vec()).root)
+            .collect()
+    }
+}
+
+// Wraps a streamed row so every byte read out of it has been verified against
+// `root`. Reads fail as soon as a corrupted chunk is encountered.
+pub fn verified_row_reader<R: Read, O: Read>(row: R, outboard: O, root: &[u8; 32]) -> impl Read {
+    bao::decode::Decoder::new_outboard(row, outboard, &bao::Hash::from(*root))
+}
+
+// Extracts the bytes [start, start + len) of a row together with the tree
+// nodes needed to verify them, so a server can answer range reads.
+pub fn extract_range(row: &Blake3Row, bytes: &[u8], start: u64, len: u64) -> Result<Vec<u8>> {
+    let mut extractor = bao::encode::SliceExtractor::new_outboard(
+        Cursor::new(bytes),
+        Cursor::new(&row.outboard),
+        start,
+        len,
+    );
+    let mut slice = Vec::new();
+    extractor.read_to_end(&mut slice)?;
+    Ok(slice)
+}
+
+// Verifies a slice produced by `extract_range` against the row root and
+// returns the requested bytes
+pub fn verify_range(slice: &[u8], root: &[u8; 32], start: u64, len: u64) -> Result<Vec<u8>> {
+    let mut decoder = bao::decode::SliceDecoder::new(slice, &bao::Hash::from(*root), start, len);
+    let mut bytes = Vec::new();
+    decoder.read_to_end(&mut bytes)?;
+    Ok(bytes)
+}
@@ -0,0 +1,320 @@
+use anyhow::{bail, Result};
+use rs_merkle::{algorithms::Sha256, Hasher, MerkleProof, MerkleTree};
+
+use crate::namespace::{namespaced_share, share_payload, Namespace, PAYLOAD_SIZE};
+use crate::tree::{leaf_hash, ExtendedDataSquare, Felt};
+
+// Blob data, before it is split into shares. The first share holds the data
+// length, followed by the data itself, zero padded to whole shares.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Blob {
+    pub namespace: Namespace,
+    pub data: Vec<u8>,
+}
+
+impl Blob {
+    pub fn share_count(&self) -> usize {
+        share_count(self.data.len())
+    }
+
+    pub fn to_shares(&self) -> Vec<Felt> {
+        let mut shares = Vec::with_capacity(self.share_count());
+        shares.push(namespaced_share(
+            self.namespace,
+            (self.data.len() as u64).to_be_bytes(),
+        ));
+        for chunk in self.data.chunks(PAYLOAD_SIZE) {
+            let mut payload = [0; PAYLOAD_SIZE];
+            payload[..chunk.len()].copy_from_slice(chunk);
+            shares.push(namespaced_share(self.namespace, payload));
+        }
+        shares
+    }
+
+    pub fn from_shares(shares: &[Felt]) -> Result<Self> {
+        let Some(first) = shares.first() else {
+            bail!("a blob needs at least one share");
+        };
+        let namespace = Namespace::of(first);
+        if shares.iter().any(|share| Namespace::of(share) != namespace) {
+            bail!("blob shares span several namespaces");
+        }
+
+        let len = u64::from_be_bytes(share_payload(first)) as usize;
+        if len.div_ceil(PAYLOAD_SIZE) != shares.len() - 1 {
+            bail!("{} byte blob does not fit {} shares", len, shares.len());
+        }
+        let mut data: Vec<u8> = shares[1..].iter().flat_map(share_payload).collect();
+        data.truncate(len);
+
+        Ok(Self { namespace, data })
+    }
+}
+
+// Shares taken by a blob of `len` bytes
+pub fn share_count(len: usize) -> usize {
+    1 + len.div_ceil(PAYLOAD_SIZE)
+}
+
+// Splits cols start..end of a row into the maximal aligned power-of-two subtrees
+// covering it, as (first col, leaf count)
+fn subtree_ranges(mut start: usize, end: usize) -> Vec<(usize, usize)> {
+    let mut ranges = Vec::new();
+    while start < end {
+        let mut size = 1;
+        while start & (2 * size - 1) == 0 && start + 2 * size <= end {
+            size *= 2;
+        }
+        ranges.push((start, size));
+        start += size;
+    }
+    ranges
+}
+
+// (row, first col, last col + 1) of the rows a blob at share `start` of the
+// original square (row-major) touches
+fn blob_rows(start: usize, len: usize, half: usize) -> Vec<(usize, usize, usize)> {
+    let mut rows = Vec::new();
+    let mut index = start;
+    while index < start + len {
+        let (row, col) = (index / half, index % half);
+        let end = (col + start + len - index).min(half);
+        rows.push((row, col, end));
+        index += end - col;
+    }
+    rows
+}
+
+fn subtree_root(leaves: &[[u8; 32]]) -> [u8; 32] {
+    let mut layer = leaves.to_vec();
+    while layer.len() > 1 {
+        layer = layer
+            .chunks(2)
+            .map(|pair| Sha256::concat_and_hash(&pair[0], pair.get(1)))
+            .collect();
+    }
+    layer[0]
+}
+
+// Roots of the row subtrees covering a blob of `shares` placed at share
+// `start`, grouped by row. The blob commitment is computed over these.
+pub fn subtree_roots(shares: &[Felt], start: usize, half: usize) -> Vec<Vec<[u8; 32]>> {
+    let leaves: Vec<[u8; 32]> = shares.iter().map(leaf_hash).collect();
+    let mut roots = Vec::new();
+    let mut offset = 0;
+    for (_, first, end) in blob_rows(start, shares.len(), half) {
+        let row_roots = subtree_ranges(first, end)
+            .into_iter()
+            .map(|(col, size)| {
+                let from = offset + col - first;
+                subtree_root(&leaves[from..from + size])
+            })
+            .collect();
+        roots.push(row_roots);
+        offset += end - first;
+    }
+    roots
+}
+
+// Merkle root over all subtree roots of a blob, in order. It depends on where
+// the blob is placed, so the position has to be fixed before building the square.
+pub fn blob_commitment(shares: &[Felt], start: usize, half: usize) -> Result<[u8; 32]> {
+    commitment_over(&subtree_roots(shares, start, half))
+}
+
+fn commitment_over(subtree_roots: &[Vec<[u8; 32]>]) -> Result<[u8; 32]> {
+    let leaves: Vec<[u8; 32]> = subtree_roots.iter().flatten().copied().collect();
+    match MerkleTree::<Sha256>::from_leaves(&leaves).root() {
+        Some(root) => Ok(root),
+        None => bail!("a blob needs at least one share"),
+    }
+}
+
+// Proof that a blob commitment is included under a data root:
+// subtree roots -> row roots -> data root
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BlobProof {
+    // width of the extended square
+    pub width: usize,
+    // first share of the blob, row-major within the original square
+    pub start: usize,
+    pub len: usize,
+    pub rows: Vec<BlobRowProof>,
+    // multiproof of the rows' roots under the data root
+    pub root_path: Vec<[u8; 32]>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BlobRowProof {
+    pub row: usize,
+    pub row_root: [u8; 32],
+    pub subtree_roots: Vec<[u8; 32]>,
+    // per subtree, the sibling hashes from the subtree root up to the row root
+    pub subtree_paths: Vec<Vec<[u8; 32]>>,
+}
+
+impl ExtendedDataSquare {
+    // Commitment to the `len` original shares starting at share `start`
+    pub fn blob_commitment(&self, start: usize, len: usize) -> Result<[u8; 32]> {
+        let shares = self.original_range(start, len)?;
+        blob_commitment(&shares, start, self.width() / 2)
+    }
+
+    pub fn prove_blob(&self, start: usize, len: usize) -> Result<BlobProof> {
+        let width = self.width();
+        self.original_range(start, len)?;
+
+        let rows: Vec<BlobRowProof> = blob_rows(start, len, width / 2)
+            .into_iter()
+            .map(|(row, first, end)| {
+                let leaves: Vec<[u8; 32]> = self.row(row).iter().map(|c| leaf_hash(&c)).collect();
+                let (subtree_roots, subtree_paths) = subtree_ranges(first, end)
+                    .into_iter()
+                    .map(|(col, size)| {
+                        (
+                            subtree_root(&leaves[col..col + size]),
+                            subtree_path(&leaves, col, size),
+                        )
+                    })
+                    .unzip();
+                BlobRowProof {
+                    row,
+                    row_root: self.row_roots()[row],
+                    subtree_roots,
+                    subtree_paths,
+                }
+            })
+            .collect();
+
+        let row_indices: Vec<usize> = rows.iter().map(|row| row.row).collect();
+        Ok(BlobProof {
+            width,
+            start,
+            len,
+            rows,
+            root_path: self.data_proof(&row_indices),
+        })
+    }
+
+    fn original_range(&self, start: usize, len: usize) -> Result<Vec<Felt>> {
+        let half = self.width() / 2;
+        if len == 0 || start + len > half * half {
+            bail!(
+                "shares {}..{} out of range for width {}",
+                start,
+                start + len,
+                half
+            );
+        }
+        Ok((start..start + len)
+            .map(|index| self.cell(index / half, index % half))
+            .collect())
+    }
+}
+
+// Sibling hashes from the aligned subtree at `col` of `size` leaves up to the root
+fn subtree_path(leaves: &[[u8; 32]], col: usize, size: usize) -> Vec<[u8; 32]> {
+    let mut path = Vec::new();
+    let (mut index, mut size) = (col / size, size);
+    while size < leaves.len() {
+        let sibling = index ^ 1;
+        path.push(subtree_root(&leaves[sibling * size..(sibling + 1) * size]));
+        index /= 2;
+        size *= 2;
+    }
+    path
+}
+
+impl BlobProof {
+    pub fn verify(&self, commitment: &[u8; 32], data_root: &[u8; 32]) -> bool {
+        let half = self.width / 2;
+        if half == 0 || self.len == 0 || self.start + self.len > half * half {
+            return false;
+        }
+
+        // the proof has to cover exactly the rows and subtrees the range implies
+        let expected = blob_rows(self.start, self.len, half);
+        if expected.len() != self.rows.len() {
+            return false;
+        }
+        for ((row, first, end), proof) in expected.into_iter().zip(&self.rows) {
+            let ranges = subtree_ranges(first, end);
+            if proof.row != row
+                || proof.subtree_roots.len() != ranges.len()
+                || proof.subtree_paths.len() != ranges.len()
+            {
+                return false;
+            }
+            for (((col, size), root), path) in ranges
+                .into_iter()
+                .zip(&proof.subtree_roots)
+                .zip(&proof.subtree_paths)
+            {
+                if fold_subtree(*root, col / size, path, self.width / size) != Some(proof.row_root)
+                {
+                    return false;
+                }
+            }
+        }
+
+        let subtree_roots: Vec<Vec<[u8; 32]>> = self
+            .rows
+            .iter()
+            .map(|row| row.subtree_roots.clone())
+            .collect();
+        if commitment_over(&subtree_roots).ok() != Some(*commitment) {
+            return false;
+        }
+
+        let row_indices: Vec<usize> = self.rows.iter().map(|row| row.row).collect();
+        let row_roots: Vec<[u8; 32]> = self.rows.iter().map(|row| row.row_root).collect();
+        MerkleProof::<Sha256>::new(self.root_path.clone()).verify(
+            *data_root,
+            &row_indices,
+            &row_roots,
+            2 * self.width,
+        )
+    }
+}
+
+// Folds a node at `index` of a layer with `nodes` nodes up to the root
+fn fold_subtree(
+    mut node: [u8; 32],
+    mut index: usize,
+    path: &[[u8; 32]],
+    mut nodes: usize,
+) -> Option<[u8; 32]> {
+    let mut path = path.iter();
+    while nodes > 1 {
+        let sibling = path.next()?;
+        node = if index % 2 == 1 {
+            Sha256::concat_and_hash(sibling, Some(&node))
+        } else {
+            Sha256::concat_and_hash(&node, Some(sibling))
+        };
+        index /= 2;
+        nodes /= 2;
+    }
+    path.next().is_none().then_some(node)
+}
+
+// Full check that `blob` was posted under `data_root`: the blob's shares hash
+// to the subtree roots in the proof, those roots make up `commitment`, and
+// they sit in rows committed to by the data root
+pub fn verify_blob_inclusion(
+    blob: &Blob,
+    commitment: &[u8; 32],
+    data_root: &[u8; 32],
+    proof: &BlobProof,
+) -> bool {
+    let shares = blob.to_shares();
+    if shares.len() != proof.len || proof.width < 2 {
+        return false;
+    }
+
+    let from_blob = subtree_roots(&shares, proof.start, proof.width / 2);
+    let from_proof = proof.rows.iter().map(|row| &row.subtree_roots);
+    from_blob.len() == proof.rows.len()
+        && from_blob.iter().zip(from_proof).all(|(a, b)| a == b)
+        && proof.verify(commitment, data_root)
+}
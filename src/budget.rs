@@ -0,0 +1,77 @@
+use std::time::Instant;
+
+use anyhow::{bail, Result};
+
+// How much work a single verification call may do, so a node on a tight
+// schedule can spread it over several slots. Calls always make at least one
+// step, resuming with the previous `verified` count eventually finishes.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Budget {
+    pub deadline: Option<Instant>,
+    pub max_steps: Option<usize>,
+}
+
+impl Budget {
+    pub fn unlimited() -> Self {
+        Self::default()
+    }
+
+    pub fn with_deadline(mut self, deadline: Instant) -> Self {
+        self.deadline = Some(deadline);
+        self
+    }
+
+    pub fn with_max_steps(mut self, max_steps: usize) -> Self {
+        self.max_steps = Some(max_steps);
+        self
+    }
+
+    fn allows(&self, steps: usize) -> bool {
+        steps == 0
+            || (self.max_steps.is_none_or(|max| steps < max)
+                && self
+                    .deadline
+                    .is_none_or(|deadline| Instant::now() < deadline))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Progress {
+    Complete,
+    // `verified` steps passed in total so far, pass it back as `start` to
+    // continue
+    Partial { verified: usize, remaining: usize },
+}
+
+impl Progress {
+    pub fn is_complete(&self) -> bool {
+        matches!(self, Progress::Complete)
+    }
+}
+
+// Runs steps `start..total` until `budget` runs out, stopping at the first
+// failing one
+pub(crate) fn run_budgeted(
+    total: usize,
+    start: usize,
+    budget: &Budget,
+    mut step: impl FnMut(usize) -> Result<()>,
+) -> Result<Progress> {
+    if start > total {
+        bail!("cannot resume at step {} of {}", start, total);
+    }
+
+    let mut index = start;
+    while index < total && budget.allows(index - start) {
+        step(index)?;
+        index += 1;
+    }
+
+    Ok(match index {
+        index if index == total => Progress::Complete,
+        verified => Progress::Partial {
+            verified,
+            remaining: total - verified,
+        },
+    })
+}
@@ -0,0 +1,109 @@
+use anyhow::{bail, Context, Result};
+
+use crate::proof::ShareProof;
+use crate::signature::SignedRoot;
+#[cfg(feature = "prover")]
+use crate::tree::ExtendedDataSquare;
+use crate::witness::{SampleWitness, Witness, MAX_PATH};
+
+// Evidence that a square was sampled: a set of distinct shares, each opened
+// against the square's data root
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ZodaCertificate {
+    pub data_root: [u8; 32],
+    // width of the extended square
+    pub width: usize,
+    pub samples: Vec<ShareProof>,
+    // the block producer's signature over the data root, if it was attached
+    pub producer: Option<SignedRoot>,
+}
+
+impl ZodaCertificate {
+    // Opens the shares at `coords` (row, col)
+    #[cfg(feature = "prover")]
+    pub fn new(eds: &ExtendedDataSquare, coords: &[(usize, usize)]) -> Result<Self> {
+        let samples = coords
+            .iter()
+            .map(|&(row, col)| eds.prove_share(row, col))
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(Self {
+            data_root: eds.data_root()?,
+            width: eds.width(),
+            samples,
+            producer: None,
+        })
+    }
+
+    // Attaches the producer's signature, which has to be over this data root
+    pub fn with_producer(mut self, signed: SignedRoot) -> Result<Self> {
+        if signed.data_root != self.data_root {
+            bail!("producer signed a different data root");
+        }
+        self.producer = Some(signed);
+        Ok(self)
+    }
+
+    pub fn verify(&self) -> Result<()> {
+        if let Some(producer) = &self.producer {
+            if producer.data_root != self.data_root {
+                bail!("producer signed a different data root");
+            }
+            producer.verify()?;
+        }
+
+        // no allocations unless it fails, certificates hold few enough samples
+        // for the quadratic duplicate check
+        for (i, sample) in self.samples.iter().enumerate() {
+            if sample.cell_proof.width != self.width {
+                bail!("sample {} is for width {}", i, sample.cell_proof.width);
+            }
+            let cell = (sample.row(), sample.col());
+            if self.samples[..i].iter().any(|s| (s.row(), s.col()) == cell) {
+                bail!("share ({}, {}) sampled twice", sample.row(), sample.col());
+            }
+            sample.check(&self.data_root).with_context(|| {
+                format!(
+                    "share ({}, {}) does not verify against the data root",
+                    sample.row(),
+                    sample.col()
+                )
+            })?;
+        }
+        Ok(())
+    }
+
+    // Flattens the certificate for zkVM guests, see `witness::verify_witness`
+    pub fn to_witness(&self) -> Result<Witness> {
+        let samples = self
+            .samples
+            .iter()
+            .map(|sample| {
+                let (cell_path, root_path) = (&sample.cell_proof.path, &sample.root_proof.path);
+                if cell_path.len() > MAX_PATH || root_path.len() > MAX_PATH {
+                    bail!("proof paths longer than {} hashes", MAX_PATH);
+                }
+
+                let mut witness = SampleWitness {
+                    share: sample.share.val(),
+                    row: sample.row() as u32,
+                    col: sample.col() as u32,
+                    row_root: sample.row_root,
+                    cell_path: [[0; 32]; MAX_PATH],
+                    cell_path_len: cell_path.len() as u8,
+                    root_path: [[0; 32]; MAX_PATH],
+                    root_path_len: root_path.len() as u8,
+                };
+                witness.cell_path[..cell_path.len()].copy_from_slice(cell_path);
+                witness.root_path[..root_path.len()].copy_from_slice(root_path);
+                Ok(witness)
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(Witness {
+            data_root: self.data_root,
+            width: self.width as u32,
+            samples,
+        })
+    }
+}
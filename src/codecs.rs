@@ -0,0 +1,284 @@
+use std::collections::HashMap;
+
+use anyhow::{bail, Result};
+#[cfg(feature = "prover")]
+use reed_solomon_erasure::galois_8;
+
+// Stable codec identifiers. Serialized squares record `BINIUS_RS_128`.
+pub const BINIUS_RS_128: &str = "binius-rs-128";
+#[cfg(feature = "prover")]
+pub const RS_8: &str = "rs-8";
+pub const LEOPARD_16: &str = "leopard-16";
+#[cfg(feature = "testing")]
+pub const IDENTITY: &str = "identity";
+#[cfg(feature = "testing")]
+pub const XOR_PARITY: &str = "xor-parity";
+
+// The codecs only fill erasures, present shares are taken as they are, so
+// there are no corrected errors to count
+pub struct DecodeResult {
+    pub data: Vec<Vec<u8>>,
+    // number of missing shares that were recovered
+    pub erasures_filled: usize,
+}
+
+impl DecodeResult {
+    // Whether the decode needed no erasure recovery
+    pub fn is_clean(&self) -> bool {
+        self.erasures_filled == 0
+    }
+}
+
+// Byte-level erasure codes over shares of one size
+pub trait Codec {
+    fn id(&self) -> &'static str;
+    // Returns only the parity shares for the `k` data shares: `k` of them for
+    // the rate 1/2 codecs, a single one for `XorParityCodec`
+    fn encode(&self, data: Vec<Vec<u8>>) -> Result<Vec<Vec<u8>>>;
+    // Takes the data shares followed by the parity `encode` returned, missing
+    // ones passed as `None`, and returns the data shares
+    fn decode(&self, data: Vec<Option<Vec<u8>>>) -> Result<DecodeResult>;
+}
+
+// Repetition code: the parity half is a copy of the data half
+#[cfg(feature = "testing")]
+pub struct IdentityCodec;
+
+#[cfg(feature = "testing")]
+impl Codec for IdentityCodec {
+    fn id(&self) -> &'static str {
+        IDENTITY
+    }
+
+    fn encode(&self, data: Vec<Vec<u8>>) -> Result<Vec<Vec<u8>>> {
+        Ok(data)
+    }
+
+    fn decode(&self, data: Vec<Option<Vec<u8>>>) -> Result<DecodeResult> {
+        if data.len() % 2 == 1 {
+            bail!("expected an even number of shares, got {}", data.len());
+        }
+
+        let k = data.len() / 2;
+        let (original, parity) = data.split_at(k);
+        let mut decoded = Vec::with_capacity(k);
+        let mut erasures_filled = 0;
+        for (i, (share, copy)) in original.iter().zip(parity).enumerate() {
+            match (share, copy) {
+                (Some(share), _) => decoded.push(share.clone()),
+                (None, Some(copy)) => {
+                    decoded.push(copy.clone());
+                    erasures_filled += 1;
+                }
+                (None, None) => bail!("share {} and its copy are both missing", i),
+            }
+        }
+
+        Ok(DecodeResult {
+            data: decoded,
+            erasures_filled,
+        })
+    }
+}
+
+// Single parity share holding the XOR of all data shares.
+// Can recover at most one missing share out of the k + 1.
+#[cfg(feature = "testing")]
+pub struct XorParityCodec;
+
+#[cfg(feature = "testing")]
+fn xor_shares<'a>(shares: impl Iterator<Item = &'a Vec<u8>>) -> Result<Vec<u8>> {
+    let mut acc: Option<Vec<u8>> = None;
+    for share in shares {
+        match acc.as_mut() {
+            None => acc = Some(share.clone()),
+            Some(acc) => {
+                if acc.len() != share.len() {
+                    bail!("All shares must be the same size");
+                }
+                acc.iter_mut().zip(share).for_each(|(a, b)| *a ^= b);
+            }
+        }
+    }
+
+    match acc {
+        Some(acc) => Ok(acc),
+        None => bail!("no shares to xor"),
+    }
+}
+
+#[cfg(feature = "testing")]
+impl Codec for XorParityCodec {
+    fn id(&self) -> &'static str {
+        XOR_PARITY
+    }
+
+    fn encode(&self, data: Vec<Vec<u8>>) -> Result<Vec<Vec<u8>>> {
+        Ok(vec![xor_shares(data.iter())?])
+    }
+
+    fn decode(&self, mut data: Vec<Option<Vec<u8>>>) -> Result<DecodeResult> {
+        if data.len() < 2 {
+            bail!("expected at least one data share and the parity share");
+        }
+
+        let missing: Vec<usize> = (0..data.len()).filter(|i| data[*i].is_none()).collect();
+        if missing.len() > 1 {
+            bail!(
+                "can only recover one missing share, {} missing",
+                missing.len()
+            );
+        }
+
+        if let Some(&i) = missing.first() {
+            let recovered = xor_shares(data.iter().flatten())?;
+            data[i] = Some(recovered);
+        }
+
+        // drop the parity share
+        data.pop();
+
+        Ok(DecodeResult {
+            data: data.into_iter().flatten().collect(),
+            erasures_filled: missing.len(),
+        })
+    }
+}
+
+// Classic Reed-Solomon over GF(2^8) (klauspost/reedsolomon matrix), as used by older rsmt2d squares.
+// Shares are extended at rate 1/2, so at most 128 data shares per axis.
+#[cfg(feature = "prover")]
+pub struct Rs8Codec;
+
+#[cfg(feature = "prover")]
+impl Codec for Rs8Codec {
+    fn id(&self) -> &'static str {
+        RS_8
+    }
+
+    fn encode(&self, data: Vec<Vec<u8>>) -> Result<Vec<Vec<u8>>> {
+        let k = data.len();
+        let rs = galois_8::ReedSolomon::new(k, k)?;
+
+        let share_size = data.first().map(|share| share.len()).unwrap_or_default();
+        let mut shards = data;
+        shards.resize(2 * k, vec![0; share_size]);
+        rs.encode(&mut shards)?;
+
+        Ok(shards.split_off(k))
+    }
+
+    fn decode(&self, data: Vec<Option<Vec<u8>>>) -> Result<DecodeResult> {
+        if data.len() % 2 == 1 {
+            bail!("expected an even number of shares, got {}", data.len());
+        }
+
+        let k = data.len() / 2;
+        let rs = galois_8::ReedSolomon::new(k, k)?;
+
+        let erasures_filled = data[..k].iter().filter(|share| share.is_none()).count();
+        let mut shards = data;
+        rs.reconstruct_data(&mut shards)?;
+        shards.truncate(k);
+
+        Ok(DecodeResult {
+            data: shards.into_iter().flatten().collect(),
+            erasures_filled,
+        })
+    }
+}
+
+// Leopard-style GF(2^16) Reed-Solomon using reed-solomon-simd, which picks the
+// fastest available SIMD path (AVX2, SSSE3, NEON) at runtime.
+// Share sizes must be even.
+#[cfg(feature = "rs-simd")]
+pub struct SimdCodec;
+
+#[cfg(feature = "rs-simd")]
+impl Codec for SimdCodec {
+    fn id(&self) -> &'static str {
+        LEOPARD_16
+    }
+
+    fn encode(&self, data: Vec<Vec<u8>>) -> Result<Vec<Vec<u8>>> {
+        let k = data.len();
+        Ok(reed_solomon_simd::encode(k, k, &data)?)
+    }
+
+    fn decode(&self, data: Vec<Option<Vec<u8>>>) -> Result<DecodeResult> {
+        if data.len() % 2 == 1 {
+            bail!("expected an even number of shares, got {}", data.len());
+        }
+
+        let k = data.len() / 2;
+        let mut original: Vec<Option<Vec<u8>>> = data[..k].to_vec();
+        let erasures_filled = original.iter().filter(|share| share.is_none()).count();
+
+        if erasures_filled > 0 {
+            let present = original
+                .iter()
+                .enumerate()
+                .filter_map(|(i, share)| share.as_ref().map(|share| (i, share)));
+            let recovery = data[k..]
+                .iter()
+                .enumerate()
+                .filter_map(|(i, share)| share.as_ref().map(|share| (i, share)));
+
+            let restored = reed_solomon_simd::decode(k, k, present, recovery)?;
+            for (i, share) in restored {
+                original[i] = Some(share);
+            }
+        }
+
+        Ok(DecodeResult {
+            data: original.into_iter().flatten().collect(),
+            erasures_filled,
+        })
+    }
+}
+
+pub type CodecConstructor = fn() -> Box<dyn Codec>;
+
+// Maps stable codec identifiers to constructors of the byte codecs, for
+// callers picking one by id. Felt squares don't go through it, they always
+// record and use `BINIUS_RS_128`.
+pub struct CodecRegistry {
+    codecs: HashMap<&'static str, CodecConstructor>,
+}
+
+impl Default for CodecRegistry {
+    fn default() -> Self {
+        let mut registry = Self {
+            codecs: HashMap::new(),
+        };
+        #[cfg(feature = "prover")]
+        registry.register(RS_8, || Box::new(Rs8Codec));
+        #[cfg(feature = "rs-simd")]
+        registry.register(LEOPARD_16, || Box::new(SimdCodec));
+        #[cfg(feature = "testing")]
+        {
+            registry.register(IDENTITY, || Box::new(IdentityCodec));
+            registry.register(XOR_PARITY, || Box::new(XorParityCodec));
+        }
+        registry
+    }
+}
+
+impl CodecRegistry {
+    pub fn register(&mut self, id: &'static str, constructor: CodecConstructor) {
+        self.codecs.insert(id, constructor);
+    }
+
+    pub fn contains(&self, id: &str) -> bool {
+        self.codecs.contains_key(id)
+    }
+
+    pub fn get(&self, id: &str) -> Result<Box<dyn Codec>> {
+        match self.codecs.get(id) {
+            Some(constructor) => Ok(constructor()),
+            // the binius code is built into the felt-level square rather than a byte codec
+            None if id == BINIUS_RS_128 => bail!("{} is only available for felt squares", id),
+            None => bail!("unknown codec {}", id),
+        }
+    }
+}
@@ -0,0 +1,44 @@
+// The only unsafe code in the crate, see lib.rs. Implementing `GlobalAlloc`
+// can't be done without it; every method forwards to `System` unchanged.
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+
+static ALLOCS: AtomicU64 = AtomicU64::new(0);
+static INSTALLED: AtomicBool = AtomicBool::new(false);
+
+// Global allocator wrapper counting allocations, for `ExtendStats::allocs`:
+//
+//     #[global_allocator]
+//     static ALLOC: zoda_rs::stats::CountingAllocator = zoda_rs::stats::CountingAllocator;
+pub struct CountingAllocator;
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        INSTALLED.store(true, Ordering::Relaxed);
+        ALLOCS.fetch_add(1, Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+
+    unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+        INSTALLED.store(true, Ordering::Relaxed);
+        ALLOCS.fetch_add(1, Ordering::Relaxed);
+        System.alloc_zeroed(layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        ALLOCS.fetch_add(1, Ordering::Relaxed);
+        System.realloc(ptr, layout, new_size)
+    }
+}
+
+pub(crate) fn allocation_count() -> Option<u64> {
+    if INSTALLED.load(Ordering::Relaxed) {
+        Some(ALLOCS.load(Ordering::Relaxed))
+    } else {
+        None
+    }
+}
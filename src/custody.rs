@@ -0,0 +1,162 @@
+use std::collections::{HashMap, HashSet};
+
+use anyhow::{bail, Result};
+
+use crate::dispersal::{ColumnChunk, DispersalPlan};
+use crate::felt::{leaf_hash, Felt};
+use crate::merkle;
+use crate::proof::{Axis, CellProof, RootProof};
+use crate::sampling::SeedStream;
+
+// Cells an operator has to open to show it still holds its columns. Picked
+// by the challenger from a seed the operator can't predict.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CustodyChallenge {
+    pub operator: usize,
+    // (row, col), all within the operator's columns
+    pub cells: Vec<(usize, usize)>,
+}
+
+impl CustodyChallenge {
+    pub fn new(
+        plan: &DispersalPlan,
+        operator: usize,
+        count: usize,
+        seed: &[u8; 32],
+    ) -> Result<Self> {
+        if operator >= plan.operators() {
+            bail!("operator {} is not part of the plan", operator);
+        }
+
+        let columns = plan.columns(operator);
+        let count = count.min(columns.len() * plan.width());
+        let mut rng = SeedStream::new(seed);
+        let mut seen = HashSet::new();
+        let mut cells = Vec::with_capacity(count);
+        while cells.len() < count {
+            let cell = (
+                rng.below(plan.width()),
+                columns.start + rng.below(columns.len()),
+            );
+            if seen.insert(cell) {
+                cells.push(cell);
+            }
+        }
+
+        Ok(Self { operator, cells })
+    }
+}
+
+// A cell opened under its column root, which in turn is proven under the data root
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ColumnOpening {
+    pub share: Felt,
+    pub cell_proof: CellProof,
+    pub col_root: [u8; 32],
+    pub root_proof: RootProof,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CustodyResponse {
+    pub operator: usize,
+    // in the order of the challenge's cells
+    pub openings: Vec<ColumnOpening>,
+}
+
+impl ColumnChunk {
+    // Answers a challenge from the columns held by this operator
+    pub fn respond(&self, challenge: &CustodyChallenge) -> Result<CustodyResponse> {
+        if challenge.operator != self.operator {
+            bail!("challenge is for operator {}", challenge.operator);
+        }
+
+        let mut trees = HashMap::new();
+        let mut openings = Vec::with_capacity(challenge.cells.len());
+        for &(row, col) in &challenge.cells {
+            let Some((_, cells, root_proof)) = self.columns.iter().find(|(j, ..)| *j == col) else {
+                bail!("column {} is not held by operator {}", col, self.operator);
+            };
+            let Some(&share) = cells.get(row) else {
+                bail!("row {} out of range", row);
+            };
+
+            let leaves = trees
+                .entry(col)
+                .or_insert_with(|| cells.iter().map(leaf_hash).collect::<Vec<_>>());
+            openings.push(ColumnOpening {
+                share,
+                cell_proof: CellProof {
+                    axis: Axis::Col,
+                    row,
+                    col,
+                    width: cells.len(),
+                    path: merkle::path(leaves, row).unwrap_or_default(),
+                },
+                col_root: merkle::root(leaves).unwrap_or_default(),
+                root_proof: root_proof.clone(),
+            });
+        }
+
+        Ok(CustodyResponse {
+            operator: self.operator,
+            openings,
+        })
+    }
+}
+
+impl CustodyResponse {
+    // Checks every opening against the challenge and the data root. Each
+    // column root is proven under the data root once, however many of its
+    // cells were challenged.
+    pub fn verify(&self, challenge: &CustodyChallenge, data_root: &[u8; 32]) -> Result<()> {
+        if self.operator != challenge.operator || self.openings.len() != challenge.cells.len() {
+            bail!("response does not answer the challenge");
+        }
+
+        let mut col_roots: HashMap<usize, [u8; 32]> = HashMap::new();
+        for (opening, &(row, col)) in self.openings.iter().zip(&challenge.cells) {
+            let (cell_proof, root_proof) = (&opening.cell_proof, &opening.root_proof);
+            if cell_proof.axis != Axis::Col
+                || (cell_proof.row, cell_proof.col) != (row, col)
+                || root_proof.axis != Axis::Col
+                || root_proof.index != col
+                || root_proof.width != cell_proof.width
+            {
+                bail!("opening of ({}, {}) is for another cell", row, col);
+            }
+
+            match col_roots.get(&col) {
+                Some(root) if *root == opening.col_root => {}
+                Some(_) => bail!("conflicting roots for column {}", col),
+                None => {
+                    if !root_proof.verify(&opening.col_root, data_root) {
+                        bail!("column {} is not committed to by the data root", col);
+                    }
+                    col_roots.insert(col, opening.col_root);
+                }
+            }
+
+            if !cell_proof.verify(&opening.share, &opening.col_root) {
+                bail!(
+                    "cell ({}, {}) does not open under its column root",
+                    row,
+                    col
+                );
+            }
+        }
+
+        Ok(())
+    }
+}
+
+// Verifies the responses of many operators, returning those that failed
+pub fn verify_custody(
+    data_root: &[u8; 32],
+    rounds: &[(CustodyChallenge, CustodyResponse)],
+) -> Vec<usize> {
+    rounds
+        .iter()
+        .filter(|(challenge, response)| response.verify(challenge, data_root).is_err())
+        .map(|(challenge, _)| challenge.operator)
+        .collect()
+}
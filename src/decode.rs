@@ -0,0 +1,30 @@
+use anyhow::{bail, Result};
+
+// How a decoder treats input from a newer writer. Every byte format of the
+// crate only grows by appending fields, so what a reader doesn't know comes
+// after what it does. Decoders default to `Strict`; each has a `_with_mode`
+// variant returning the warnings of a lenient decode.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum DecodeMode {
+    // newer versions, unknown extensions and trailing bytes are errors
+    #[default]
+    Strict,
+    // skips them, leaving a warning for each
+    Lenient,
+}
+
+// `extra` bytes past the end of the layout a decoder knows: an error when
+// strict, a warning when lenient
+pub(crate) fn skip_trailing(
+    mode: DecodeMode,
+    extra: usize,
+    warnings: &mut Vec<String>,
+) -> Result<()> {
+    if extra > 0 {
+        if mode == DecodeMode::Strict {
+            bail!("{} trailing bytes", extra);
+        }
+        warnings.push(format!("skipped {} trailing bytes", extra));
+    }
+    Ok(())
+}
@@ -0,0 +1,278 @@
+use std::ops::Range;
+
+use anyhow::{bail, Result};
+
+use crate::felt::{leaf_hash, Felt};
+use crate::merkle;
+use crate::proof::{Axis, RootProof};
+#[cfg(feature = "prover")]
+use crate::repair::PartialEds;
+#[cfg(feature = "prover")]
+use crate::tree::ExtendedDataSquare;
+
+// Splits the columns of an extended square between operators, each one
+// custodying a disjoint, contiguous range of them. Doubles as the reassembly
+// map: which operator to ask for which column.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DispersalPlan {
+    // width of the extended square
+    width: usize,
+    // column ranges, by operator
+    ranges: Vec<Range<usize>>,
+}
+
+impl DispersalPlan {
+    // Spreads `width` columns as evenly as possible over `operators`
+    pub fn new(width: usize, operators: usize) -> Result<Self> {
+        if operators == 0 || operators > width {
+            bail!(
+                "can't spread {} columns over {} operators",
+                width,
+                operators
+            );
+        }
+
+        let (per_operator, extra) = (width / operators, width % operators);
+        let mut start = 0;
+        let ranges = (0..operators)
+            .map(|op| {
+                let len = per_operator + usize::from(op < extra);
+                let range = start..start + len;
+                start += len;
+                range
+            })
+            .collect();
+
+        Ok(Self { width, ranges })
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn operators(&self) -> usize {
+        self.ranges.len()
+    }
+
+    pub fn columns(&self, operator: usize) -> Range<usize> {
+        self.ranges[operator].clone()
+    }
+
+    pub fn operator_of(&self, col: usize) -> Option<usize> {
+        self.ranges.iter().position(|range| range.contains(&col))
+    }
+
+    // Fewest operators whose columns are sure to add up to half of the
+    // square, which is enough to recover every row. Fewer can do if they
+    // happen to hold more columns.
+    pub fn threshold(&self) -> usize {
+        let mut sizes: Vec<usize> = self.ranges.iter().map(|range| range.len()).collect();
+        sizes.sort_unstable();
+        let mut columns = 0;
+        sizes
+            .iter()
+            .take_while(|&&size| {
+                let short = columns < self.width / 2;
+                columns += size;
+                short
+            })
+            .count()
+    }
+}
+
+// The columns sent to one operator, each with the proof of its column root
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ColumnChunk {
+    pub operator: usize,
+    // (column index, cells, root proof)
+    pub columns: Vec<(usize, Vec<Felt>, RootProof)>,
+}
+
+impl ColumnChunk {
+    // Checks that the chunk holds exactly the operator's columns, each
+    // committed to by the data root
+    pub fn verify(&self, plan: &DispersalPlan, data_root: &[u8; 32]) -> Result<()> {
+        if self.operator >= plan.operators() {
+            bail!("operator {} is not part of the plan", self.operator);
+        }
+        let expected = plan.columns(self.operator);
+        if !self.columns.iter().map(|(j, ..)| *j).eq(expected.clone()) {
+            bail!(
+                "chunk does not hold columns {:?} of operator {}",
+                expected,
+                self.operator
+            );
+        }
+
+        for (j, cells, proof) in &self.columns {
+            if cells.len() != plan.width()
+                || proof.axis != Axis::Col
+                || proof.index != *j
+                || proof.width != plan.width()
+            {
+                bail!("column {} does not fit the plan", j);
+            }
+            let leaves: Vec<[u8; 32]> = cells.iter().map(leaf_hash).collect();
+            let root = merkle::root(&leaves).unwrap_or_default();
+            if !proof.verify(&root, data_root) {
+                bail!("column {} is not committed to by the data root", j);
+            }
+        }
+
+        Ok(())
+    }
+}
+
+// Describes an export, carried by each of its bundles so any of them can be
+// restored from on its own
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExportManifest {
+    pub data_root: [u8; 32],
+    // width of the extended square
+    pub width: usize,
+    pub dr: Vec<Felt>,
+    pub row_roots: Vec<[u8; 32]>,
+    pub col_roots: Vec<[u8; 32]>,
+    pub bundles: usize,
+    // bundles needed to restore the square, see `DispersalPlan::threshold`
+    pub threshold: usize,
+}
+
+impl ExportManifest {
+    pub fn plan(&self) -> Result<DispersalPlan> {
+        DispersalPlan::new(self.width, self.bundles)
+    }
+}
+
+// One storage provider's share of an export: its columns of the square, each
+// with the proof of its column root
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExportBundle {
+    pub manifest: ExportManifest,
+    pub chunk: ColumnChunk,
+}
+
+impl ExportBundle {
+    pub fn verify(&self, data_root: &[u8; 32]) -> Result<()> {
+        let manifest = &self.manifest;
+        if manifest.data_root != *data_root {
+            bail!("bundle is for another data root");
+        }
+        let plan = manifest.plan()?;
+        if manifest.threshold != plan.threshold()
+            || manifest.dr.len() != manifest.width / 2
+            || manifest.row_roots.len() != manifest.width
+            || manifest.col_roots.len() != manifest.width
+        {
+            bail!("bundle manifest is inconsistent");
+        }
+        self.chunk.verify(&plan, data_root)
+    }
+}
+
+#[cfg(feature = "prover")]
+impl ExtendedDataSquare {
+    // Splits the square's columns into `bundles` bundles for independent
+    // storage providers. The columns are already erasure coded, any bundles
+    // holding half of them restore the square, see `restore`.
+    pub fn export(&self, bundles: usize) -> Result<Vec<ExportBundle>> {
+        let plan = DispersalPlan::new(self.width(), bundles)?;
+        let manifest = ExportManifest {
+            data_root: self.data_root()?,
+            width: self.width(),
+            dr: self.dr().to_vec(),
+            row_roots: self.row_roots().to_vec(),
+            col_roots: self.col_roots().to_vec(),
+            bundles,
+            threshold: plan.threshold(),
+        };
+        Ok(self
+            .disperse(&plan)?
+            .into_iter()
+            .map(|chunk| ExportBundle {
+                manifest: manifest.clone(),
+                chunk,
+            })
+            .collect())
+    }
+
+    // One chunk per operator of `plan`
+    pub fn disperse(&self, plan: &DispersalPlan) -> Result<Vec<ColumnChunk>> {
+        if plan.width() != self.width() {
+            bail!(
+                "plan is for width {}, square is {}",
+                plan.width(),
+                self.width()
+            );
+        }
+
+        (0..plan.operators())
+            .map(|operator| {
+                let columns = plan
+                    .columns(operator)
+                    .map(|j| Ok((j, self.col(j).to_vec(), self.prove_col_root(j)?)))
+                    .collect::<Result<_>>()?;
+                Ok(ColumnChunk { operator, columns })
+            })
+            .collect()
+    }
+}
+
+// Rebuilds an exported square from any of its bundles holding at least half
+// of the columns. Bundles not matching `data_root` are rejected.
+#[cfg(feature = "prover")]
+pub fn restore(bundles: &[ExportBundle], data_root: &[u8; 32]) -> Result<ExtendedDataSquare> {
+    let Some(first) = bundles.first() else {
+        bail!("no bundles to restore from");
+    };
+    let manifest = &first.manifest;
+
+    // checks the axis roots against the data root
+    let mut partial = PartialEds::new(
+        manifest.width,
+        manifest.dr.clone(),
+        manifest.row_roots.clone(),
+        manifest.col_roots.clone(),
+        data_root,
+    )?;
+    for bundle in bundles {
+        if bundle.manifest != *manifest {
+            bail!("bundles are from different exports");
+        }
+        bundle.verify(data_root)?;
+        for (j, cells, _) in &bundle.chunk.columns {
+            partial.set_col(*j, cells.clone())?;
+        }
+    }
+    // every row has the same columns, so rows alone recover the square
+    partial.repair_single_axis(Axis::Row)
+}
+
+// Rebuilds the square from every operator's chunk. `dr` travels with the
+// square's header. With chunks missing, feed the columns that are available
+// to `PartialEds::set_col` and repair instead.
+#[cfg(feature = "prover")]
+pub fn reassemble(
+    plan: &DispersalPlan,
+    chunks: &[ColumnChunk],
+    dr: Vec<Felt>,
+    data_root: &[u8; 32],
+) -> Result<ExtendedDataSquare> {
+    let mut cols: Vec<Option<Vec<Felt>>> = vec![None; plan.width()];
+    for chunk in chunks {
+        chunk.verify(plan, data_root)?;
+        for (j, cells, _) in &chunk.columns {
+            cols[*j] = Some(cells.clone());
+        }
+    }
+
+    let Some(cols) = cols.into_iter().collect::<Option<Vec<_>>>() else {
+        bail!("chunks of some operators are missing");
+    };
+    let eds = ExtendedDataSquare::from_extended_cols(cols, dr)?;
+    if eds.data_root()? != *data_root {
+        bail!("reassembled square does not match the data root");
+    }
+
+    Ok(eds)
+}
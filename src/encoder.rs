@@ -0,0 +1,163 @@
+use std::array;
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex};
+
+use anyhow::{anyhow, bail, Result};
+use binius_core::linear_code::LinearCode;
+use binius_core::reed_solomon::reed_solomon::ReedSolomonCode;
+use binius_field::Field;
+use binius_ntt::NTTOptions;
+
+use crate::felt::Felt;
+
+// Widths up to this are encoded with a cached generator matrix, below it a
+// k^2 product beats setting up the NTT
+pub const SMALL_WIDTH: usize = 16;
+
+// The rate 1/2 Reed-Solomon code of one width. Either backend computes the
+// same parity, see `new_encoder`.
+pub(crate) enum Encoder {
+    Ntt(ReedSolomonCode<Felt>),
+    Small(Arc<SmallCode>),
+}
+
+// The code's parity as a matrix, taken from the NTT encoder once per width
+pub(crate) struct SmallCode {
+    log_dimension: usize,
+    // parity[r][c]: weight of message element c in parity element r
+    parity: Vec<Vec<Felt>>,
+}
+
+// An encoder for messages of `width` felts, the generator matrix one for
+// widths up to `SMALL_WIDTH`
+pub(crate) fn new_encoder(width: usize) -> Result<Encoder> {
+    static SMALL_CODES: Mutex<BTreeMap<usize, Arc<SmallCode>>> = Mutex::new(BTreeMap::new());
+
+    if width > SMALL_WIDTH {
+        return Ok(Encoder::Ntt(ntt_encoder(width)?));
+    }
+    let mut codes = SMALL_CODES.lock().unwrap_or_else(|e| e.into_inner());
+    if let Some(code) = codes.get(&width) {
+        return Ok(Encoder::Small(code.clone()));
+    }
+    let code = Arc::new(SmallCode::new(width)?);
+    codes.insert(width, code.clone());
+    Ok(Encoder::Small(code))
+}
+
+fn ntt_encoder(width: usize) -> Result<ReedSolomonCode<Felt>> {
+    Ok(ReedSolomonCode::new(
+        width.trailing_zeros() as usize,
+        1,
+        NTTOptions::default(),
+    )?)
+}
+
+impl SmallCode {
+    pub(crate) fn new(width: usize) -> Result<Self> {
+        let encoder = ntt_encoder(width)?;
+        let mut parity = vec![vec![Felt::default(); width]; width];
+        for c in 0..width {
+            let mut unit = vec![Felt::default(); width];
+            unit[c] = Felt::ONE;
+            for (r, elem) in encoder.encode(unit)?.into_iter().enumerate() {
+                parity[r][c] = elem;
+            }
+        }
+        Ok(Self {
+            log_dimension: encoder.log_dim(),
+            parity,
+        })
+    }
+
+    pub(crate) fn parity(&self) -> &[Vec<Felt>] {
+        &self.parity
+    }
+}
+
+impl Encoder {
+    pub(crate) fn log_dim(&self) -> usize {
+        match self {
+            Encoder::Ntt(code) => code.log_dim(),
+            Encoder::Small(code) => code.log_dimension,
+        }
+    }
+
+    pub(crate) fn log_inv_rate(&self) -> usize {
+        match self {
+            Encoder::Ntt(code) => code.log_inv_rate(),
+            Encoder::Small(_) => 1,
+        }
+    }
+
+    pub(crate) fn dim(&self) -> usize {
+        1 << self.log_dim()
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        1 << (self.log_dim() + self.log_inv_rate())
+    }
+
+    // Parity of `message`
+    pub(crate) fn encode(&self, message: Vec<Felt>) -> Result<Vec<Felt>> {
+        match self {
+            Encoder::Ntt(code) => Ok(code.encode(message)?),
+            Encoder::Small(code) => {
+                if message.len() != code.parity.len() {
+                    bail!(
+                        "message of {} felts for width {}",
+                        message.len(),
+                        code.parity.len()
+                    );
+                }
+                Ok(code
+                    .parity
+                    .iter()
+                    .map(|row| row.iter().zip(&message).map(|(w, m)| *w * *m).sum())
+                    .collect())
+            }
+        }
+    }
+
+    // Like `encode`, for a width known at compile time. The generator matrix
+    // product runs over fixed-size arrays, the NTT still goes through a Vec.
+    pub(crate) fn encode_array<const W: usize>(&self, message: &[Felt; W]) -> Result<[Felt; W]> {
+        match self {
+            Encoder::Small(code) if code.parity.len() == W => Ok(array::from_fn(|r| {
+                let row = &code.parity[r];
+                (0..W).map(|c| row[c] * message[c]).sum()
+            })),
+            _ => self
+                .encode(message.to_vec())?
+                .try_into()
+                .map_err(|parity: Vec<Felt>| {
+                    anyhow!("parity of {} felts for width {}", parity.len(), W)
+                }),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn small_code_matches_ntt() {
+        let mut width = 1;
+        while width <= SMALL_WIDTH {
+            let ntt = Encoder::Ntt(ntt_encoder(width).unwrap());
+            let small = new_encoder(width).unwrap();
+            assert!(matches!(small, Encoder::Small(_)));
+            assert_eq!((small.dim(), small.len()), (ntt.dim(), ntt.len()));
+
+            let message: Vec<Felt> = (0..width as u128)
+                .map(|i| Felt::new(i.wrapping_mul(0x9e37_79b9_7f4a_7c15) ^ 7))
+                .collect();
+            assert_eq!(
+                small.encode(message.clone()).unwrap(),
+                ntt.encode(message).unwrap()
+            );
+            width *= 2;
+        }
+    }
+}
@@ -0,0 +1,137 @@
+use std::sync::OnceLock;
+
+use anyhow::{bail, Result};
+use binius_field::BinaryField128b;
+use sha2::{Digest, Sha256};
+
+pub type Felt = BinaryField128b;
+// Shares of a felt square are single field elements
+pub type Share = Felt;
+
+// Bytes in a share of a felt square, one felt per cell
+pub const SHARE_SIZE: usize = 16;
+
+pub fn leaf_hash(elem: &Felt) -> [u8; 32] {
+    static ZERO_LEAF: OnceLock<[u8; 32]> = OnceLock::new();
+
+    if *elem == Felt::default() {
+        return *ZERO_LEAF.get_or_init(|| Sha256::digest([0; 16]).into());
+    }
+    Sha256::digest(elem.val().to_be_bytes()).into()
+}
+
+// dr[i]: the first 16 bytes of sha256(commitment || context, if any || i as
+// big-endian u64, the same on every target)
+pub fn dr_element(tree_commitment: &[u8; 32], context: Option<&[u8; 32]>, i: usize) -> Felt {
+    let mut hasher = Sha256::new();
+    hasher.update(tree_commitment);
+    if let Some(context) = context {
+        hasher.update(context);
+    }
+    hasher.update((i as u64).to_be_bytes());
+    let digest = hasher.finalize();
+    Felt::new(u128::from_be_bytes(digest[..16].try_into().unwrap()))
+}
+
+// Fills `out` with dr[0..out.len()], for verifiers that can't allocate
+pub fn derive_dr_into(tree_commitment: &[u8; 32], context: Option<&[u8; 32]>, out: &mut [Felt]) {
+    for (i, elem) in out.iter_mut().enumerate() {
+        *elem = dr_element(tree_commitment, context, i);
+    }
+}
+
+// How byte strings whose length isn't a multiple of 16 are padded into felts.
+// Either rule is unambiguous, unpadding gives back the original bytes exactly.
+// The id is what gets written down wherever padded data is stored.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum Padding {
+    // 0x80 then zeros up to a whole felt, an aligned input gains a full felt
+    #[default]
+    TenStar,
+    // the length as 4 big-endian bytes, then the data and zeros
+    LengthPrefixed,
+}
+
+impl Padding {
+    pub fn id(&self) -> u8 {
+        match self {
+            Padding::TenStar => 1,
+            Padding::LengthPrefixed => 2,
+        }
+    }
+
+    pub fn from_id(id: u8) -> Result<Self> {
+        match id {
+            1 => Ok(Padding::TenStar),
+            2 => Ok(Padding::LengthPrefixed),
+            _ => bail!("unknown padding {}", id),
+        }
+    }
+
+    // Felts needed for `len` bytes
+    pub fn padded_len(&self, len: usize) -> usize {
+        match self {
+            Padding::TenStar => len / SHARE_SIZE + 1,
+            Padding::LengthPrefixed => (4 + len).div_ceil(SHARE_SIZE),
+        }
+    }
+
+    pub fn pad(&self, bytes: &[u8]) -> Result<Vec<Felt>> {
+        let mut padded = Vec::with_capacity(self.padded_len(bytes.len()) * SHARE_SIZE);
+        match self {
+            Padding::TenStar => {
+                padded.extend_from_slice(bytes);
+                padded.push(0x80);
+            }
+            Padding::LengthPrefixed => {
+                let Ok(len) = u32::try_from(bytes.len()) else {
+                    bail!("{} bytes are too many to length prefix", bytes.len());
+                };
+                padded.extend_from_slice(&len.to_be_bytes());
+                padded.extend_from_slice(bytes);
+            }
+        }
+        padded.resize(self.padded_len(bytes.len()) * SHARE_SIZE, 0);
+
+        Ok(padded
+            .chunks_exact(SHARE_SIZE)
+            .map(|chunk| Felt::new(u128::from_be_bytes(chunk.try_into().unwrap())))
+            .collect())
+    }
+
+    // Rejects anything `pad` wouldn't have produced, so each byte string has
+    // exactly one padded form
+    pub fn unpad(&self, felts: &[Felt]) -> Result<Vec<u8>> {
+        let mut bytes: Vec<u8> = felts
+            .iter()
+            .flat_map(|elem| elem.val().to_be_bytes())
+            .collect();
+        let len = match self {
+            Padding::TenStar => {
+                let Some(marker) = bytes.iter().rposition(|byte| *byte != 0) else {
+                    bail!("no padding marker");
+                };
+                if bytes[marker] != 0x80 {
+                    bail!("padding marker is {:#04x}, expected 0x80", bytes[marker]);
+                }
+                marker
+            }
+            Padding::LengthPrefixed => {
+                if bytes.len() < 4 {
+                    bail!("no length prefix");
+                }
+                let len = u32::from_be_bytes(bytes[..4].try_into().unwrap()) as usize;
+                bytes.drain(..4);
+                if len > bytes.len() || bytes[len..].iter().any(|byte| *byte != 0) {
+                    bail!("length prefix {} doesn't match the padding", len);
+                }
+                len
+            }
+        };
+        if felts.len() != self.padded_len(len) {
+            bail!("{} felts for {} bytes", felts.len(), len);
+        }
+        bytes.truncate(len);
+        Ok(bytes)
+    }
+}
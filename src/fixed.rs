@@ -0,0 +1,193 @@
+use std::array;
+
+use anyhow::{bail, Result};
+
+use crate::encoder::new_encoder;
+use crate::tree::{
+    derive_dr, derive_dr_with_context, leaf_cols, x_tree, y_tree, DataSquare, ExtendedDataSquare,
+    Felt, LeafStrategy, MAX_WIDTH, SHARE_SIZE,
+};
+
+// A data square whose width is fixed at compile time, so an unsupported width
+// fails to compile rather than at runtime. Cells live in `[[Felt; W]; W]` and
+// `extend` and `verify` encode every axis as a `[Felt; W]`, producing the
+// same square as `DataSquare::extend` with per-felt leaves:
+//
+//     let square = FixedDataSquare::<64>::zeroed();
+//     let eds = square.extend()?;
+//     square.verify(&eds)?;
+pub struct FixedDataSquare<const W: usize> {
+    // column-major
+    cols: Box<[[Felt; W]; W]>,
+}
+
+impl<const W: usize> FixedDataSquare<W> {
+    // evaluated when a constructor is monomorphized
+    const VALID: () = assert!(
+        W.is_power_of_two() && W <= MAX_WIDTH,
+        "width must be a power of two no larger than MAX_WIDTH"
+    );
+
+    pub const WIDTH: usize = W;
+    pub const EXTENDED_WIDTH: usize = 2 * W;
+
+    pub fn new(cols: Box<[[Felt; W]; W]>) -> Self {
+        #[allow(clippy::let_unit_value)]
+        let () = Self::VALID;
+        Self { cols }
+    }
+
+    pub fn zeroed() -> Self {
+        // built on the heap, a large square would overflow the stack
+        let cols = vec![[Felt::default(); W]; W]
+            .into_boxed_slice()
+            .try_into()
+            .unwrap_or_else(|_| unreachable!());
+        Self::new(cols)
+    }
+
+    // Builds a square from column-major shares
+    pub fn from_shares(shares: &[[[u8; SHARE_SIZE]; W]; W]) -> Self {
+        let mut square = Self::zeroed();
+        for (col, shares) in square.cols.iter_mut().zip(shares) {
+            for (cell, share) in col.iter_mut().zip(shares) {
+                *cell = Felt::new(u128::from_be_bytes(*share));
+            }
+        }
+        square
+    }
+
+    pub fn col(&self, j: usize) -> &[Felt; W] {
+        &self.cols[j]
+    }
+
+    pub fn cell(&self, row: usize, col: usize) -> Felt {
+        self.cols[col][row]
+    }
+
+    pub fn set(&mut self, row: usize, col: usize, cell: Felt) {
+        self.cols[col][row] = cell;
+    }
+
+    pub fn to_data_square(&self) -> DataSquare {
+        DataSquare::new_unchecked(self.cols.iter().map(|col| col.to_vec()).collect())
+    }
+
+    pub fn extend(&self) -> Result<ExtendedDataSquare> {
+        let encoder = new_encoder(W)?;
+
+        // q1 and q3, the columns and their parity
+        let mut cols = Vec::with_capacity(2 * W);
+        for col in self.cols.iter() {
+            let parity = encoder.encode_array(col)?;
+            cols.push([col.as_slice(), &parity].concat());
+        }
+        let mut leaves = leaf_cols(&cols);
+        let x_tree = x_tree(&cols, &leaves, LeafStrategy::PerFelt);
+        let Some(x_root) = x_tree.root() else {
+            bail!("failed to get tree commitment");
+        };
+        let dr = derive_dr(&x_root, W);
+
+        // q2 and q4, the parity of the dr-scaled rows of q1 and q3
+        let mut right_cols = vec![vec![Felt::default(); 2 * W]; W];
+        for row in 0..2 * W {
+            let scaled: [Felt; W] = array::from_fn(|col| cols[col][row] * dr[col]);
+            for (col, cell) in encoder.encode_array(&scaled)?.into_iter().enumerate() {
+                right_cols[col][row] = cell;
+            }
+        }
+        let q1_dr_cols: Vec<Vec<Felt>> = self
+            .cols
+            .iter()
+            .zip(&dr)
+            .map(|(col, dr_i)| col.map(|cell| cell * *dr_i).to_vec())
+            .collect();
+        let right_leaves = leaf_cols(&right_cols);
+        let y_tree = y_tree(
+            &q1_dr_cols,
+            &right_cols,
+            &right_leaves,
+            LeafStrategy::PerFelt,
+        );
+        cols.extend(right_cols);
+        leaves.extend(right_leaves);
+
+        ExtendedDataSquare::from_cols(cols, leaves, dr, x_tree, y_tree)
+    }
+
+    // Checks that `eds` is the extension of this square: its first quadrant,
+    // dr, and the parity of every row and column
+    pub fn verify(&self, eds: &ExtendedDataSquare) -> Result<()> {
+        if eds.width() != 2 * W {
+            bail!("square of width {}, expected {}", eds.width(), 2 * W);
+        }
+        let cols = eds.cols();
+        if let Some(j) = (0..W).find(|&j| cols[j][..W] != self.cols[j]) {
+            bail!("column {} of q1 differs", j);
+        }
+
+        let Some(x_root) = eds.x_root() else {
+            bail!("failed to get tree commitment");
+        };
+        let dr = derive_dr_with_context(&x_root, eds.context(), W);
+        if eds.dr() != dr {
+            bail!("dr does not match the commitment");
+        }
+
+        let encoder = new_encoder(W)?;
+        for (j, col) in cols.iter().enumerate() {
+            let data: [Felt; W] = array::from_fn(|row| col[row]);
+            let parity = encoder.encode_array(&data)?;
+            if parity[..] != col[W..] {
+                bail!("column {} is not a codeword", j);
+            }
+        }
+        for row in 0..2 * W {
+            let cells = eds.row(row);
+            let scaled: [Felt; W] = array::from_fn(|col| cells.get(col) * dr[col]);
+            let parity = encoder.encode_array(&scaled)?;
+            if (0..W).any(|k| parity[k] != cells.get(W + k)) {
+                bail!("row {} is not a codeword", row);
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn square<const W: usize>() -> FixedDataSquare<W> {
+        let mut square = FixedDataSquare::<W>::zeroed();
+        for row in 0..W {
+            for col in 0..W {
+                square.set(row, col, Felt::new((row * W + col + 1) as u128));
+            }
+        }
+        square
+    }
+
+    fn matches_dynamic<const W: usize>() {
+        let square = square::<W>();
+        let eds = square.extend().unwrap();
+        let dynamic = square.to_data_square().extend().unwrap();
+        assert_eq!(eds.cols(), dynamic.cols());
+        assert_eq!(eds.data_root().unwrap(), dynamic.data_root().unwrap());
+        square.verify(&eds).unwrap();
+        eds.validate().unwrap();
+
+        let mut cols = eds.cols().to_vec();
+        cols[2 * W - 1][0] += Felt::new(1);
+        let tampered = ExtendedDataSquare::from_extended_cols(cols, eds.dr().to_vec()).unwrap();
+        assert!(square.verify(&tampered).is_err());
+        assert!(FixedDataSquare::<W>::zeroed().verify(&eds).is_err());
+    }
+
+    #[test]
+    fn extends_like_data_square() {
+        matches_dynamic::<2>();
+        matches_dynamic::<8>();
+    }
+}
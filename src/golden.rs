@@ -0,0 +1,172 @@
+use anyhow::{bail, Result};
+use sha2::{Digest, Sha256};
+
+use crate::tree::{DataSquare, Felt};
+
+// Bumped whenever data roots change on purpose, together with the table
+pub const GOLDEN_VERSION: u32 = 1;
+
+// The data root a fixed square extends to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GoldenRoot {
+    pub version: u32,
+    pub width: usize,
+    // shares expanded from the seed, see `golden_square`; `None` for all zeros
+    pub seed: Option<u64>,
+    pub data_root: [u8; 32],
+}
+
+// Every (width, seed) that has to have a golden root. All-zero squares pin
+// leaf hashing, dr derivation and tree layout on both sides of `SMALL_WIDTH`.
+// Seeded squares would also pin the field arithmetic and the encoder, their
+// roots have to come from a build against the real binius crates.
+pub const GOLDEN_CASES: &[(usize, Option<u64>)] =
+    &[(2, None), (4, None), (8, None), (16, None), (32, None)];
+
+// Pinned roots, regenerate with `golden_table` after a deliberate change
+pub const GOLDEN_ROOTS: &[GoldenRoot] = &[
+    GoldenRoot {
+        version: 1,
+        width: 2,
+        seed: None,
+        data_root: hex("be7f4191ce63555a972122edb751e24bc749a9e49cc5ddb77f564df68240b143"),
+    },
+    GoldenRoot {
+        version: 1,
+        width: 4,
+        seed: None,
+        data_root: hex("cbbcbb34876bfbd9608dfd2422dd80e8c3d5c9e8fa4daa59998b17f0c8d97afb"),
+    },
+    GoldenRoot {
+        version: 1,
+        width: 8,
+        seed: None,
+        data_root: hex("d5dab1ee06c54a73c82fcd29772fc633d2350a206bd6742405d47635ac743a9e"),
+    },
+    GoldenRoot {
+        version: 1,
+        width: 16,
+        seed: None,
+        data_root: hex("d406859c05fb6683dd99046ca94bd4fb84d47d96bea3c29b7c5162bdb308bad0"),
+    },
+    GoldenRoot {
+        version: 1,
+        width: 32,
+        seed: None,
+        data_root: hex("43a62429a09dac749cacaddb75ea89c1302b02e8538192bc452aba45a9933ee6"),
+    },
+];
+
+const fn hex(s: &str) -> [u8; 32] {
+    let s = s.as_bytes();
+    let mut out = [0; 32];
+    let mut i = 0;
+    while i < 32 {
+        out[i] = nibble(s[2 * i]) << 4 | nibble(s[2 * i + 1]);
+        i += 1;
+    }
+    out
+}
+
+const fn nibble(c: u8) -> u8 {
+    match c {
+        b'0'..=b'9' => c - b'0',
+        b'a'..=b'f' => c - b'a' + 10,
+        _ => panic!("invalid hex digit"),
+    }
+}
+
+// The original square behind a golden root
+pub fn golden_square(width: usize, seed: Option<u64>) -> Result<DataSquare> {
+    let cols = (0..width)
+        .map(|col| {
+            (0..width)
+                .map(|row| match seed {
+                    Some(seed) => seeded_felt(seed, col * width + row),
+                    None => Felt::default(),
+                })
+                .collect()
+        })
+        .collect();
+    DataSquare::try_new(cols)
+}
+
+fn seeded_felt(seed: u64, index: usize) -> Felt {
+    let digest = Sha256::new()
+        .chain_update(seed.to_be_bytes())
+        .chain_update((index as u64).to_be_bytes())
+        .finalize();
+    Felt::new(u128::from_be_bytes(digest[..16].try_into().unwrap()))
+}
+
+pub fn golden_root(width: usize, seed: Option<u64>) -> Result<[u8; 32]> {
+    golden_square(width, seed)?.extend()?.data_root()
+}
+
+// Recomputes every root of the current version and fails on any mismatch,
+// or if a case of `GOLDEN_CASES` has no pinned root
+pub fn verify_golden() -> Result<()> {
+    let current = || {
+        GOLDEN_ROOTS
+            .iter()
+            .filter(|golden| golden.version == GOLDEN_VERSION)
+    };
+    let missing: Vec<String> = GOLDEN_CASES
+        .iter()
+        .filter(|&&(width, seed)| {
+            !current().any(|golden| golden.width == width && golden.seed == seed)
+        })
+        .map(|(width, seed)| format!("width {} seed {:?}", width, seed))
+        .collect();
+    if !missing.is_empty() {
+        bail!(
+            "no golden root for {}, regenerate the table with golden_table",
+            missing.join(", ")
+        );
+    }
+
+    let mismatches: Vec<String> = current()
+        .map(|golden| Ok((golden, golden_root(golden.width, golden.seed)?)))
+        .collect::<Result<Vec<_>>>()?
+        .into_iter()
+        .filter(|(golden, root)| *root != golden.data_root)
+        .map(|(golden, _)| format!("width {} seed {:?}", golden.width, golden.seed))
+        .collect();
+
+    if !mismatches.is_empty() {
+        bail!(
+            "data roots changed for golden squares: {}",
+            mismatches.join(", ")
+        );
+    }
+    Ok(())
+}
+
+// Source of `GOLDEN_ROOTS` for every case of `GOLDEN_CASES`, as this build
+// computes them
+pub fn golden_table() -> Result<String> {
+    let mut out = String::from("pub const GOLDEN_ROOTS: &[GoldenRoot] = &[\n");
+    for &(width, seed) in GOLDEN_CASES {
+        let root: String = golden_root(width, seed)?
+            .iter()
+            .map(|b| format!("{:02x}", b))
+            .collect();
+        out.push_str(&format!(
+            "    GoldenRoot {{\n        version: {},\n        width: {},\n        \
+             seed: {:?},\n        data_root: hex(\"{}\"),\n    }},\n",
+            GOLDEN_VERSION, width, seed, root
+        ));
+    }
+    out.push_str("];\n");
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn golden_roots_hold() {
+        verify_golden().unwrap();
+    }
+}
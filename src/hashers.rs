@@ -0,0 +1,47 @@
+// Stable identifier of the hash function squares are committed with,
+// recorded in serialized squares. Trees, the data root and dr are all sha256.
+pub const SHA256: &str = "sha256";
+
+// Which SHA-256 implementation the sha2 crate dispatches to at runtime
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Sha256Backend {
+    // x86 SHA extensions
+    ShaNi,
+    // ARMv8 cryptography extensions
+    ArmSha2,
+    Software,
+}
+
+impl Sha256Backend {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::ShaNi => "sha-ni",
+            Self::ArmSha2 => "arm-sha2",
+            Self::Software => "software",
+        }
+    }
+}
+
+// Mirrors sha2's own runtime detection, so benchmarks and node logs can report
+// which path leaf hashing actually takes
+pub fn sha256_backend() -> Sha256Backend {
+    #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+    {
+        if std::arch::is_x86_feature_detected!("sha")
+            && std::arch::is_x86_feature_detected!("sse2")
+            && std::arch::is_x86_feature_detected!("ssse3")
+            && std::arch::is_x86_feature_detected!("sse4.1")
+        {
+            return Sha256Backend::ShaNi;
+        }
+    }
+
+    #[cfg(target_arch = "aarch64")]
+    {
+        if std::arch::is_aarch64_feature_detected!("sha2") {
+            return Sha256Backend::ArmSha2;
+        }
+    }
+
+    Sha256Backend::Software
+}
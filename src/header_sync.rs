@@ -0,0 +1,96 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread;
+use std::time::Duration;
+
+use anyhow::Result;
+
+use crate::sampling::ShareProvider;
+use crate::sampling_manager::SamplingManager;
+
+// What sampling needs from a block header
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HeaderRoot {
+    pub data_root: [u8; 32],
+    // width of the extended square
+    pub width: usize,
+}
+
+// Serves block headers, typically a light client's header sync
+pub trait HeaderSource {
+    // Height of the newest header available
+    fn latest_height(&mut self) -> Result<u64>;
+    fn header_root(&mut self, height: u64) -> Result<HeaderRoot>;
+}
+
+// Feeds the roots of new headers into a `SamplingManager` and samples them,
+// keeping only the newest `window` heights
+pub struct SamplingDriver<S> {
+    source: S,
+    manager: SamplingManager,
+    // next height to fetch
+    next: u64,
+    window: u64,
+}
+
+impl<S: HeaderSource> SamplingDriver<S> {
+    // Starts at `start`, or `window` below the latest height if that's newer
+    pub fn new(source: S, manager: SamplingManager, start: u64) -> Self {
+        Self {
+            source,
+            manager,
+            next: start,
+            window: 64,
+        }
+    }
+
+    // Heights kept in the manager, at least one
+    pub fn with_window(mut self, window: u64) -> Self {
+        self.window = window.max(1);
+        self
+    }
+
+    pub fn manager(&self) -> &SamplingManager {
+        &self.manager
+    }
+
+    pub fn source_mut(&mut self) -> &mut S {
+        &mut self.source
+    }
+
+    // Fetches the headers added since the last call, drops heights that fell
+    // out of the window and samples what isn't available yet, see
+    // `SamplingManager::run`. Returns the number of headers added.
+    pub fn poll<F>(&mut self, connect: F) -> Result<usize>
+    where
+        F: Fn(u64, &[u8; 32]) -> Result<Vec<Box<dyn ShareProvider>>> + Sync,
+    {
+        let latest = self.source.latest_height()?;
+        let oldest = (latest + 1).saturating_sub(self.window);
+        self.next = self.next.max(oldest);
+
+        let mut added = 0;
+        while self.next <= latest {
+            let header = self.source.header_root(self.next)?;
+            self.manager
+                .add_root(self.next, header.data_root, header.width);
+            self.next += 1;
+            added += 1;
+        }
+        self.manager.prune_below(oldest);
+
+        self.manager.run(connect);
+        Ok(added)
+    }
+
+    // Polls every `interval` until `stop` is set or the header source fails
+    pub fn run<F>(&mut self, connect: F, interval: Duration, stop: &AtomicBool) -> Result<()>
+    where
+        F: Fn(u64, &[u8; 32]) -> Result<Vec<Box<dyn ShareProvider>>> + Sync,
+    {
+        while !stop.load(Ordering::Relaxed) {
+            self.poll(&connect)?;
+            thread::sleep(interval);
+        }
+        Ok(())
+    }
+}
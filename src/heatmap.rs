@@ -0,0 +1,123 @@
+use anyhow::{bail, Result};
+
+use crate::bitmap::AvailabilityBitmap;
+use crate::proof::Axis;
+
+// How much of one row or column is available
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AxisAvailability {
+    pub present: usize,
+    // half of the axis is enough to decode the rest
+    pub decodable: bool,
+}
+
+// Per-axis availability of an extended square, for monitoring which parts of
+// a block are being withheld. Much smaller than the bitmap it summarizes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AvailabilityHeatmap {
+    pub data_root: [u8; 32],
+    // width of the extended square
+    pub width: usize,
+    pub rows: Vec<AxisAvailability>,
+    pub cols: Vec<AxisAvailability>,
+}
+
+impl AvailabilityHeatmap {
+    pub fn from_bitmap(data_root: [u8; 32], cells: &AvailabilityBitmap) -> Self {
+        let width = cells.width();
+        let mut rows = vec![0; width];
+        let mut cols = vec![0; width];
+        for (row, col) in cells.iter() {
+            rows[row] += 1;
+            cols[col] += 1;
+        }
+        Self {
+            data_root,
+            width,
+            rows: summarize(&rows, width),
+            cols: summarize(&cols, width),
+        }
+    }
+
+    pub fn axis(&self, axis: Axis) -> &[AxisAvailability] {
+        match axis {
+            Axis::Row => &self.rows,
+            Axis::Col => &self.cols,
+        }
+    }
+
+    // Indices along `axis` that can't be decoded yet
+    pub fn undecodable(&self, axis: Axis) -> Vec<usize> {
+        self.axis(axis)
+            .iter()
+            .enumerate()
+            .filter(|(_, availability)| !availability.decodable)
+            .map(|(i, _)| i)
+            .collect()
+    }
+
+    // Cells where an undecodable row meets an undecodable column, the likely
+    // place of withheld data, for `Sampler::with_hotspots`
+    pub fn hotspots(&self) -> Vec<(usize, usize)> {
+        let cols = self.undecodable(Axis::Col);
+        self.undecodable(Axis::Row)
+            .into_iter()
+            .flat_map(|row| cols.iter().map(move |&col| (row, col)))
+            .collect()
+    }
+
+    // data root (32) | width (4) | present cells of every row (4 each), then
+    // of every column. The decodable flags follow from the counts.
+    pub fn encode(&self) -> Vec<u8> {
+        let mut out = Vec::with_capacity(32 + 4 + 8 * self.width);
+        out.extend_from_slice(&self.data_root);
+        out.extend_from_slice(&(self.width as u32).to_be_bytes());
+        for availability in self.rows.iter().chain(&self.cols) {
+            out.extend_from_slice(&(availability.present as u32).to_be_bytes());
+        }
+        out
+    }
+
+    pub fn decode(bytes: &[u8]) -> Result<Self> {
+        if bytes.len() < 32 + 4 {
+            bail!("unexpected end of input");
+        }
+        let data_root = bytes[..32].try_into()?;
+        let width = u32::from_be_bytes(bytes[32..36].try_into()?) as usize;
+        if width < 2 || !width.is_power_of_two() {
+            bail!("extended width must be a power of two, got {}", width);
+        }
+        let counts = &bytes[36..];
+        if Some(counts.len()) != width.checked_mul(8) {
+            bail!(
+                "wrong number of heatmap bytes for width {}, got {}",
+                width,
+                counts.len()
+            );
+        }
+
+        let counts: Vec<usize> = counts
+            .chunks_exact(4)
+            .map(|chunk| u32::from_be_bytes(chunk.try_into().unwrap()) as usize)
+            .collect();
+        if let Some(count) = counts.iter().find(|&&count| count > width) {
+            bail!("{} cells present in an axis of width {}", count, width);
+        }
+        Ok(Self {
+            data_root,
+            width,
+            rows: summarize(&counts[..width], width),
+            cols: summarize(&counts[width..], width),
+        })
+    }
+}
+
+fn summarize(counts: &[usize], width: usize) -> Vec<AxisAvailability> {
+    counts
+        .iter()
+        .map(|&present| AxisAvailability {
+            present,
+            decodable: present >= width / 2,
+        })
+        .collect()
+}
@@ -0,0 +1,204 @@
+use anyhow::{bail, Result};
+
+use crate::blob::{share_count, Blob};
+use crate::namespace::{namespaced_share, Namespace, NAMESPACE_SIZE, PAYLOAD_SIZE};
+use crate::tree::{DataSquare, Felt, MAX_WIDTH};
+use crate::wire::Reader;
+
+// Blobs longer than this many shares are aligned to wider subtrees, which
+// keeps the number of subtree roots in their commitments bounded
+pub const SUBTREE_ROOT_THRESHOLD: usize = 64;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BlobSize {
+    pub namespace: Namespace,
+    // bytes of blob data
+    pub size: usize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Placement {
+    pub namespace: Namespace,
+    // first share, row-major within the original square
+    pub start: usize,
+    pub shares: usize,
+}
+
+// How blobs are laid out in the original square, always row-major
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ShareOrder {
+    // blobs sorted by namespace, which namespace proofs rely on
+    #[default]
+    NamespaceGrouped,
+    // blobs in the order they were given, regardless of namespace
+    RowMajor,
+}
+
+impl ShareOrder {
+    fn to_u8(self) -> u8 {
+        match self {
+            Self::NamespaceGrouped => 0,
+            Self::RowMajor => 1,
+        }
+    }
+
+    fn from_u8(tag: u8) -> Result<Self> {
+        match tag {
+            0 => Ok(Self::NamespaceGrouped),
+            1 => Ok(Self::RowMajor),
+            _ => bail!("unknown share order {}", tag),
+        }
+    }
+}
+
+// Where each blob goes in the original square, in the order blobs were given.
+// Kept alongside the square, retrieval needs it to find a blob's shares.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Layout {
+    // original (q1) width
+    pub width: usize,
+    pub order: ShareOrder,
+    pub placements: Vec<Placement>,
+}
+
+// Blobs have to start at a multiple of this, so their commitments can be
+// built from whole row subtrees
+pub fn subtree_width(shares: usize, width: usize) -> usize {
+    shares
+        .div_ceil(SUBTREE_ROOT_THRESHOLD)
+        .next_power_of_two()
+        .min(width)
+}
+
+// Finds the smallest width that fits all blobs, placed in namespace order
+pub fn plan(blobs: &[BlobSize]) -> Result<Layout> {
+    plan_with_order(blobs, ShareOrder::default())
+}
+
+pub fn plan_with_order(blobs: &[BlobSize], share_order: ShareOrder) -> Result<Layout> {
+    let mut order: Vec<usize> = (0..blobs.len()).collect();
+    if share_order == ShareOrder::NamespaceGrouped {
+        order.sort_by_key(|&i| blobs[i].namespace);
+    }
+
+    let mut width = 1;
+    while width <= MAX_WIDTH {
+        if let Some(placements) = place(blobs, &order, width) {
+            return Ok(Layout {
+                width,
+                order: share_order,
+                placements,
+            });
+        }
+        width *= 2;
+    }
+
+    bail!("blobs do not fit a square of width {}", MAX_WIDTH)
+}
+
+fn place(blobs: &[BlobSize], order: &[usize], width: usize) -> Option<Vec<Placement>> {
+    let mut placements = vec![None; blobs.len()];
+    let mut cursor: usize = 0;
+    for &i in order {
+        let shares = share_count(blobs[i].size);
+        let start = cursor.next_multiple_of(subtree_width(shares, width));
+        cursor = start + shares;
+        if cursor > width * width {
+            return None;
+        }
+        placements[i] = Some(Placement {
+            namespace: blobs[i].namespace,
+            start,
+            shares,
+        });
+    }
+    placements.into_iter().collect()
+}
+
+impl Layout {
+    // (row, col) of share `k` of blob `blob`
+    pub fn coords(&self, blob: usize, k: usize) -> Option<(usize, usize)> {
+        let placement = self.placements.get(blob)?;
+        if k >= placement.shares {
+            return None;
+        }
+        let index = placement.start + k;
+        Some((index / self.width, index % self.width))
+    }
+
+    // width u32 | order u8 | blob count u32 | (namespace | start u32 | shares u32)*
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut out = Vec::with_capacity(9 + self.placements.len() * (NAMESPACE_SIZE + 8));
+        out.extend_from_slice(&(self.width as u32).to_be_bytes());
+        out.push(self.order.to_u8());
+        out.extend_from_slice(&(self.placements.len() as u32).to_be_bytes());
+        for placement in &self.placements {
+            out.extend_from_slice(&placement.namespace.0);
+            out.extend_from_slice(&(placement.start as u32).to_be_bytes());
+            out.extend_from_slice(&(placement.shares as u32).to_be_bytes());
+        }
+        out
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        let mut reader = Reader::new(bytes);
+        let width = reader.u32()? as usize;
+        let order = ShareOrder::from_u8(reader.u8()?)?;
+        let count = reader.u32()? as usize;
+        let mut placements = Vec::new();
+        for _ in 0..count {
+            placements.push(Placement {
+                namespace: Namespace(reader.take(NAMESPACE_SIZE)?.try_into()?),
+                start: reader.u32()? as usize,
+                shares: reader.u32()? as usize,
+            });
+        }
+        reader.finish()?;
+
+        Ok(Self {
+            width,
+            order,
+            placements,
+        })
+    }
+
+    // Builds the original square for `blobs`, given in the order they were
+    // planned in. Gaps after a blob are padded with empty shares of its
+    // namespace, the tail with `Namespace::TAIL_PADDING`. Only squares in
+    // `NamespaceGrouped` order end up sorted by namespace.
+    pub fn build(&self, blobs: &[Blob]) -> Result<DataSquare> {
+        if blobs.len() != self.placements.len() {
+            bail!(
+                "layout has {} blobs, got {}",
+                self.placements.len(),
+                blobs.len()
+            );
+        }
+
+        let width = self.width;
+        let tail = namespaced_share(Namespace::TAIL_PADDING, [0; PAYLOAD_SIZE]);
+        let mut shares: Vec<Felt> = vec![tail; width * width];
+
+        let mut order: Vec<usize> = (0..blobs.len()).collect();
+        order.sort_by_key(|&i| self.placements[i].start);
+        for (k, &i) in order.iter().enumerate() {
+            let (blob, placement) = (&blobs[i], &self.placements[i]);
+            if blob.namespace != placement.namespace || blob.share_count() != placement.shares {
+                bail!("blob {} does not match its placement", i);
+            }
+
+            let end = placement.start + placement.shares;
+            shares[placement.start..end].copy_from_slice(&blob.to_shares());
+
+            let next = order.get(k + 1).map_or(end, |&j| self.placements[j].start);
+            let padding = namespaced_share(blob.namespace, [0; PAYLOAD_SIZE]);
+            shares[end..next].fill(padding);
+        }
+
+        // shares are row-major, squares are built from columns
+        let q1_cols = (0..width)
+            .map(|col| (0..width).map(|row| shares[row * width + col]).collect())
+            .collect();
+        DataSquare::try_new(q1_cols)
+    }
+}
@@ -1,5 +1,130 @@
+// Consensus-critical code, kept free of unsafe. SIMD goes through safe APIs
+// (binius packed fields, reed-solomon-simd). The one exception is the opt-in
+// allocation counter, which has to implement `GlobalAlloc`.
+#![cfg_attr(not(feature = "counting-alloc"), forbid(unsafe_code))]
+#![cfg_attr(feature = "counting-alloc", deny(unsafe_code))]
+// Without `std` only the zkVM witness check and the merkle helpers it uses
+// are compiled, for guests with nothing beyond `alloc`
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
+
+#[cfg(feature = "prover")]
+pub mod aggregate;
+#[cfg(feature = "prover")]
+pub mod archive;
+#[cfg(feature = "std")]
+pub mod attestation;
+#[cfg(feature = "std")]
+pub mod bitmap;
+#[cfg(feature = "blake3-stream")]
+pub mod blake3_tree;
+#[cfg(feature = "prover")]
+pub mod blob;
+#[cfg(feature = "std")]
+pub mod budget;
+#[cfg(feature = "std")]
+pub mod certificate;
+#[cfg(any(feature = "prover", feature = "testing"))]
+pub mod codecs;
+#[cfg(feature = "counting-alloc")]
+#[allow(unsafe_code)]
+mod counting_alloc;
+#[cfg(feature = "std")]
+pub mod custody;
+#[cfg(feature = "std")]
 pub mod datasquare;
+#[cfg(feature = "std")]
+pub mod decode;
+#[cfg(feature = "std")]
+pub mod dispersal;
+#[cfg(feature = "prover")]
+mod encoder;
+#[cfg(feature = "std")]
+pub mod felt;
+#[cfg(feature = "prover")]
+pub mod fixed;
+#[cfg(feature = "prover")]
+pub mod golden;
+#[cfg(feature = "prover")]
+pub mod hashers;
+#[cfg(feature = "std")]
+pub mod header_sync;
+#[cfg(feature = "std")]
+pub mod heatmap;
+#[cfg(feature = "prover")]
+pub mod layout;
+#[cfg(feature = "prover")]
+pub mod lifecycle;
+#[cfg(feature = "prover")]
+pub mod manifest;
+#[cfg(feature = "std")]
+pub mod matrix;
+#[cfg(feature = "prover")]
+pub mod memory;
+pub mod merkle;
+#[cfg(feature = "std")]
+pub mod namespace;
+#[cfg(feature = "std")]
+pub mod opening;
+#[cfg(feature = "std")]
+pub mod order;
+#[cfg(feature = "prover")]
+pub mod params;
+#[cfg(feature = "std")]
+pub mod precommit;
+#[cfg(feature = "std")]
+pub mod prelude;
+#[cfg(feature = "std")]
+pub mod proof;
+#[cfg(feature = "prover")]
+pub mod proof_cache;
+#[cfg(feature = "prover")]
+pub mod repair;
+#[cfg(feature = "rsmt2d-fixtures")]
+pub mod rsmt2d;
+#[cfg(feature = "std")]
+pub mod sampling;
+#[cfg(feature = "std")]
+pub mod sampling_manager;
+#[cfg(feature = "prover")]
+mod scratch;
+#[cfg(feature = "prover")]
+pub mod serve;
+#[cfg(feature = "std")]
+pub mod signature;
+#[cfg(feature = "prover")]
+pub mod square_set;
+#[cfg(feature = "prover")]
+pub mod stats;
+#[cfg(feature = "prover")]
+pub mod store;
+#[cfg(feature = "prover")]
+pub mod streaming;
+#[cfg(feature = "prover")]
+pub mod subsquare;
+#[cfg(all(feature = "testing", feature = "prover"))]
+pub mod test_utils;
+#[cfg(feature = "prover")]
 pub mod tree;
+#[cfg(feature = "std")]
+pub mod v1;
+#[cfg(feature = "std")]
+pub mod v2;
+#[cfg(feature = "std")]
+pub mod versioned;
+#[cfg(feature = "std")]
+pub mod wantlist;
+#[cfg(feature = "prover")]
+pub mod wire;
+pub mod witness;
+
+#[cfg(feature = "prover")]
+pub use lifecycle::{post, retrieve};
+
+// re-exported so inputs can be built without depending on binius_field directly
+#[cfg(feature = "std")]
+pub use binius_field;
 
 pub fn add(left: u64, right: u64) -> u64 {
     left + right
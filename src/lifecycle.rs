@@ -0,0 +1,122 @@
+// One-call flows over the blob, layout, square and proof layers, for users
+// who only want to post blobs and read them back
+use anyhow::{bail, Result};
+
+use crate::blob::{share_count, Blob, BlobProof};
+use crate::layout::{plan, BlobSize, Layout};
+use crate::namespace::{share_payload, AbsenceProof, Namespace, NamespaceProof};
+use crate::tree::ExtendedDataSquare;
+
+// Everything `post` produced. `proofs[i]` and `commitments[i]` are for the
+// i-th blob given to `post`.
+pub struct Posted {
+    pub eds: ExtendedDataSquare,
+    pub data_root: [u8; 32],
+    pub layout: Layout,
+    pub commitments: Vec<[u8; 32]>,
+    pub proofs: Vec<BlobProof>,
+}
+
+// Lays `blobs` out in namespace order, builds and extends the square, and
+// proves every blob's inclusion
+pub fn post(blobs: &[Blob]) -> Result<Posted> {
+    let sizes: Vec<BlobSize> = blobs
+        .iter()
+        .map(|blob| BlobSize {
+            namespace: blob.namespace,
+            size: blob.data.len(),
+        })
+        .collect();
+    let layout = plan(&sizes)?;
+    let eds = layout.build(blobs)?.extend()?;
+    let data_root = eds.data_root()?;
+
+    let mut commitments = Vec::with_capacity(blobs.len());
+    let mut proofs = Vec::with_capacity(blobs.len());
+    for placement in &layout.placements {
+        commitments.push(eds.blob_commitment(placement.start, placement.shares)?);
+        proofs.push(eds.prove_blob(placement.start, placement.shares)?);
+    }
+
+    Ok(Posted {
+        eds,
+        data_root,
+        layout,
+        commitments,
+        proofs,
+    })
+}
+
+// A namespace's shares in a square, or proof it has none
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum NamespaceData {
+    Present(NamespaceProof),
+    Absent(Box<AbsenceProof>),
+}
+
+// Serves the namespaces of squares it holds, typically a connection to a
+// full node
+pub trait NamespaceProvider {
+    fn fetch_namespace(
+        &mut self,
+        namespace: Namespace,
+        data_root: &[u8; 32],
+    ) -> Result<NamespaceData>;
+}
+
+impl NamespaceProvider for ExtendedDataSquare {
+    fn fetch_namespace(
+        &mut self,
+        namespace: Namespace,
+        data_root: &[u8; 32],
+    ) -> Result<NamespaceData> {
+        if self.data_root()? != *data_root {
+            bail!("square has a different data root");
+        }
+        if self.shares_in(namespace).next().is_none() {
+            let proof = self.prove_namespace_absence(namespace)?;
+            return Ok(NamespaceData::Absent(Box::new(proof)));
+        }
+        Ok(NamespaceData::Present(self.prove_namespace(namespace)?))
+    }
+}
+
+// The blobs of `namespace` under `data_root`, in square order, checked
+// against the data root. Padding after a blob looks like an empty blob, so
+// empty blobs are skipped.
+pub fn retrieve(
+    namespace: Namespace,
+    provider: &mut dyn NamespaceProvider,
+    data_root: &[u8; 32],
+) -> Result<Vec<Blob>> {
+    let proof = match provider.fetch_namespace(namespace, data_root)? {
+        NamespaceData::Absent(proof) => {
+            if proof.namespace != namespace || !proof.verify(data_root) {
+                bail!("absence proof does not verify");
+            }
+            return Ok(Vec::new());
+        }
+        NamespaceData::Present(proof) => proof,
+    };
+    if proof.namespace != namespace || !proof.verify(data_root) {
+        bail!("namespace proof does not verify");
+    }
+
+    let shares = proof.shares();
+    let mut blobs = Vec::new();
+    let mut rest = &shares[..];
+    while let Some(first) = rest.first() {
+        let len = u64::from_be_bytes(share_payload(first));
+        if len == 0 {
+            rest = &rest[1..];
+            continue;
+        }
+        let count = share_count(len as usize);
+        if count > rest.len() {
+            bail!("blob of {} bytes runs past its namespace", len);
+        }
+        blobs.push(Blob::from_shares(&rest[..count])?);
+        rest = &rest[count..];
+    }
+    Ok(blobs)
+}
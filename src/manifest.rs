@@ -0,0 +1,262 @@
+use std::ops::Range;
+
+use anyhow::{bail, Result};
+use sha2::{Digest, Sha256};
+
+use crate::merkle;
+use crate::params::ZodaParams;
+use crate::tree::{axis_root, ExtendedDataSquare, Felt};
+use crate::wire::Reader;
+
+pub const MANIFEST_FORMAT: u8 = 1;
+
+// Reads stored chunks, typically a client of the object store a square was
+// uploaded to
+pub trait ChunkReader {
+    fn read_chunk(&mut self, uri: &str) -> Result<Vec<u8>>;
+}
+
+impl<F: FnMut(&str) -> Result<Vec<u8>>> ChunkReader for F {
+    fn read_chunk(&mut self, uri: &str) -> Result<Vec<u8>> {
+        self(uri)
+    }
+}
+
+// One stored chunk of a square: a run of consecutive rows, each as its
+// `width` felts of 16 big-endian bytes
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChunkEntry {
+    // sha256 of the chunk's bytes
+    pub digest: [u8; 32],
+    pub uri: String,
+}
+
+// Index of a square kept in external (e.g. object) storage, so it can be
+// fetched and checked a chunk at a time. Holds everything needed to check a
+// row against the data root and to rebuild the square once all rows are in.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SquareManifest {
+    pub data_root: [u8; 32],
+    // see `ZodaParams::digest`
+    pub params_digest: [u8; 32],
+    // width of the extended square
+    pub width: usize,
+    pub rows_per_chunk: usize,
+    pub dr: Vec<Felt>,
+    pub row_roots: Vec<[u8; 32]>,
+    pub col_roots: Vec<[u8; 32]>,
+    pub chunks: Vec<ChunkEntry>,
+}
+
+impl SquareManifest {
+    // Splits `eds` into chunks of `rows_per_chunk` rows, the last one possibly
+    // shorter. Returns the manifest and the chunks' bytes to store, chunk `i`
+    // under `uri(i)`.
+    pub fn create(
+        eds: &ExtendedDataSquare,
+        params: &ZodaParams,
+        rows_per_chunk: usize,
+        uri: impl Fn(usize) -> String,
+    ) -> Result<(Self, Vec<Vec<u8>>)> {
+        let width = eds.width();
+        if params.extended_width() != width {
+            bail!(
+                "params are for width {}, square is {}",
+                params.extended_width(),
+                width
+            );
+        }
+        if rows_per_chunk == 0 {
+            bail!("chunks need at least one row");
+        }
+
+        let mut chunks = Vec::new();
+        let mut data = Vec::new();
+        for (i, start) in (0..width).step_by(rows_per_chunk).enumerate() {
+            let end = (start + rows_per_chunk).min(width);
+            let mut bytes = Vec::with_capacity((end - start) * width * 16);
+            for row in start..end {
+                for cell in eds.row(row).iter() {
+                    bytes.extend_from_slice(&cell.val().to_be_bytes());
+                }
+            }
+            chunks.push(ChunkEntry {
+                digest: Sha256::digest(&bytes).into(),
+                uri: uri(i),
+            });
+            data.push(bytes);
+        }
+
+        let manifest = Self {
+            data_root: eds.data_root()?,
+            params_digest: params.digest(),
+            width,
+            rows_per_chunk,
+            dr: eds.dr().to_vec(),
+            row_roots: eds.row_roots().to_vec(),
+            col_roots: eds.col_roots().to_vec(),
+            chunks,
+        };
+        Ok((manifest, data))
+    }
+
+    // Checks the manifest is for `data_root` and squares built with
+    // `params`: its axis roots commit to the data root and the chunks cover
+    // every row. dr can only be checked once the square is rebuilt.
+    pub fn verify(&self, data_root: &[u8; 32], params: &ZodaParams) -> Result<()> {
+        if self.data_root != *data_root {
+            bail!("manifest is for another data root");
+        }
+        if self.params_digest != params.digest() || self.width != params.extended_width() {
+            bail!("manifest is for other params");
+        }
+        if self.dr.len() != self.width / 2
+            || self.row_roots.len() != self.width
+            || self.col_roots.len() != self.width
+        {
+            bail!("manifest does not fit width {}", self.width);
+        }
+        if self.rows_per_chunk == 0 || self.chunks.len() != self.width.div_ceil(self.rows_per_chunk)
+        {
+            bail!("chunks do not cover the square");
+        }
+
+        let mut leaves = self.row_roots.clone();
+        leaves.extend_from_slice(&self.col_roots);
+        if merkle::root(&leaves) != Some(*data_root) {
+            bail!("axis roots do not match the data root");
+        }
+        Ok(())
+    }
+
+    // Rows held by chunk `index`
+    pub fn chunk_rows(&self, index: usize) -> Range<usize> {
+        let start = (index * self.rows_per_chunk).min(self.width);
+        start..(start + self.rows_per_chunk).min(self.width)
+    }
+
+    // Checks the bytes of chunk `index` against its digest and each row
+    // against its root, returning the rows. Only meaningful once `verify`
+    // passed.
+    pub fn verify_chunk(&self, index: usize, bytes: &[u8]) -> Result<Vec<Vec<Felt>>> {
+        let Some(entry) = self.chunks.get(index) else {
+            bail!("manifest has no chunk {}", index);
+        };
+        let digest: [u8; 32] = Sha256::digest(bytes).into();
+        if digest != entry.digest {
+            bail!("chunk {} does not match its digest", index);
+        }
+
+        let rows = self.chunk_rows(index);
+        if bytes.len() != rows.len() * self.width * 16 {
+            bail!("chunk {} has {} bytes", index, bytes.len());
+        }
+        let mut reader = Reader::new(bytes);
+        rows.map(|row| {
+            let cells = reader.felts(self.width)?;
+            if axis_root(&cells) != self.row_roots[row] {
+                bail!("row {} does not match its root", row);
+            }
+            Ok(cells)
+        })
+        .collect()
+    }
+
+    // Reads only the chunks holding `rows` and returns those rows, each
+    // checked with `verify_chunk`. Only meaningful once `verify` passed.
+    pub fn fetch_and_verify_rows(
+        &self,
+        rows: Range<usize>,
+        reader: &mut dyn ChunkReader,
+    ) -> Result<Vec<Vec<Felt>>> {
+        if rows.start >= rows.end || rows.end > self.width || self.rows_per_chunk == 0 {
+            bail!("rows {:?} out of range for width {}", rows, self.width);
+        }
+
+        let first = rows.start / self.rows_per_chunk;
+        let last = (rows.end - 1) / self.rows_per_chunk;
+        let mut out = Vec::with_capacity(rows.len());
+        for index in first..=last {
+            let Some(entry) = self.chunks.get(index) else {
+                bail!("manifest has no chunk {}", index);
+            };
+            let bytes = reader.read_chunk(&entry.uri)?;
+            let chunk_rows = self.chunk_rows(index);
+            for (row, cells) in chunk_rows.zip(self.verify_chunk(index, &bytes)?) {
+                if rows.contains(&row) {
+                    out.push(cells);
+                }
+            }
+        }
+        Ok(out)
+    }
+
+    // format (1) = 1 | data root (32) | params digest (32) | width (4)
+    // | rows per chunk (4) | dr (width / 2 felts) | row roots | column roots
+    // | chunk count (4) | (digest (32) | uri len (2) | uri)*
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut out = vec![MANIFEST_FORMAT];
+        out.extend_from_slice(&self.data_root);
+        out.extend_from_slice(&self.params_digest);
+        out.extend_from_slice(&(self.width as u32).to_be_bytes());
+        out.extend_from_slice(&(self.rows_per_chunk as u32).to_be_bytes());
+        for elem in &self.dr {
+            out.extend_from_slice(&elem.val().to_be_bytes());
+        }
+        for root in self.row_roots.iter().chain(&self.col_roots) {
+            out.extend_from_slice(root);
+        }
+        out.extend_from_slice(&(self.chunks.len() as u32).to_be_bytes());
+        for chunk in &self.chunks {
+            out.extend_from_slice(&chunk.digest);
+            out.extend_from_slice(&(chunk.uri.len() as u16).to_be_bytes());
+            out.extend_from_slice(chunk.uri.as_bytes());
+        }
+        out
+    }
+
+    // Only parses, `verify` checks the contents
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        let mut reader = Reader::new(bytes);
+        let format = reader.u8()?;
+        if format != MANIFEST_FORMAT {
+            bail!("unsupported manifest format {}", format);
+        }
+        let data_root = reader.take(32)?.try_into()?;
+        let params_digest = reader.take(32)?.try_into()?;
+        let width = reader.u32()? as usize;
+        let rows_per_chunk = reader.u32()? as usize;
+        // every root takes 32 bytes, checked before allocating
+        if width.checked_mul(64).is_none_or(|len| len > bytes.len()) {
+            bail!("width {} does not fit {} bytes", width, bytes.len());
+        }
+        let dr = reader.felts(width / 2)?;
+        let mut roots = || -> Result<Vec<[u8; 32]>> {
+            (0..width)
+                .map(|_| Ok(reader.take(32)?.try_into()?))
+                .collect()
+        };
+        let (row_roots, col_roots) = (roots()?, roots()?);
+
+        let count = reader.u32()? as usize;
+        let mut chunks = Vec::new();
+        for _ in 0..count {
+            let digest = reader.take(32)?.try_into()?;
+            let len = u16::from_be_bytes(reader.take(2)?.try_into()?) as usize;
+            let uri = std::str::from_utf8(reader.take(len)?)?.to_string();
+            chunks.push(ChunkEntry { digest, uri });
+        }
+        reader.finish()?;
+
+        Ok(Self {
+            data_root,
+            params_digest,
+            width,
+            rows_per_chunk,
+            dr,
+            row_roots,
+            col_roots,
+            chunks,
+        })
+    }
+}
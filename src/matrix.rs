@@ -0,0 +1,75 @@
+// Transposes over any element type: felt squares as well as byte squares.
+// Matrices are vectors of axes, outer index first.
+
+// Side of the square blocks transposes work through. 16x16 felts is 4 KiB,
+// so a source and destination tile sit comfortably in L1.
+const TILE: usize = 16;
+
+pub fn transpose<T: Clone + Default>(matrix: &[Vec<T>]) -> Vec<Vec<T>> {
+    let len = matrix.first().map(|axis| axis.len()).unwrap_or_default();
+    let mut transposed = vec![vec![T::default(); matrix.len()]; len];
+    transpose_tiled_into(matrix, &mut transposed);
+    transposed
+}
+
+pub fn transpose_and_flatten<T: Clone>(matrix: &[Vec<T>]) -> Vec<T> {
+    let len = matrix.first().map(|axis| axis.len()).unwrap_or_default();
+    let mut transposed = Vec::with_capacity(len * matrix.len());
+    for i in 0..len {
+        for axis in matrix.iter() {
+            transposed.push(axis[i].clone());
+        }
+    }
+    transposed
+}
+
+// Transposes `matrix` (outer index first) into `out`, which must already hold
+// `matrix[0].len()` vectors of `matrix.len()` elements each
+pub fn transpose_tiled_into<T: Clone>(matrix: &[Vec<T>], out: &mut [Vec<T>]) {
+    let len = matrix.first().map(|axis| axis.len()).unwrap_or_default();
+    assert_eq!(out.len(), len, "output has the wrong number of axes");
+
+    for i0 in (0..matrix.len()).step_by(TILE) {
+        for j0 in (0..len).step_by(TILE) {
+            for (i, axis) in matrix.iter().enumerate().skip(i0).take(TILE) {
+                for (j, elem) in axis.iter().enumerate().skip(j0).take(TILE) {
+                    out[j][i] = elem.clone();
+                }
+            }
+        }
+    }
+}
+
+// Transposes a flat row-major `rows x cols` buffer into `dst` (`cols x rows`)
+pub fn transpose_flat_into<T: Clone>(src: &[T], dst: &mut [T], rows: usize, cols: usize) {
+    assert_eq!(src.len(), rows * cols, "source has the wrong size");
+    assert_eq!(dst.len(), rows * cols, "destination has the wrong size");
+
+    for i0 in (0..rows).step_by(TILE) {
+        for j0 in (0..cols).step_by(TILE) {
+            for i in i0..(i0 + TILE).min(rows) {
+                for j in j0..(j0 + TILE).min(cols) {
+                    dst[j * rows + i] = src[i * cols + j].clone();
+                }
+            }
+        }
+    }
+}
+
+// Transposes a flat row-major `width x width` buffer in place, swapping tiles
+// across the diagonal
+pub fn transpose_flat_in_place<T>(buf: &mut [T], width: usize) {
+    assert_eq!(buf.len(), width * width, "buffer is not width x width");
+
+    for i0 in (0..width).step_by(TILE) {
+        for j0 in (i0..width).step_by(TILE) {
+            for i in i0..(i0 + TILE).min(width) {
+                // on diagonal tiles only swap the upper triangle
+                let start = if i0 == j0 { i + 1 } else { j0 };
+                for j in start..(j0 + TILE).min(width) {
+                    buf.swap(i * width + j, j * width + i);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,100 @@
+use std::mem::size_of;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use anyhow::{bail, Result};
+
+use crate::tree::{ExtendedDataSquare, Felt};
+
+// Approximate heap bytes of a merkle tree over `leaves` leaves, all layers included
+pub(crate) fn tree_bytes(leaves: usize) -> usize {
+    2 * leaves * 32
+}
+
+// Estimated footprint of extending a square of `width` x `width` shares of
+// `share_size` bytes, with every proof cache populated
+pub fn estimate_extended_size(width: usize, share_size: usize) -> usize {
+    let ext = 2 * width;
+    let cells = ext * ext * share_size;
+    let dr = width * size_of::<Felt>();
+    let roots = 2 * ext * 32;
+    // x and y trees each cover half the square, the axis trees all of it twice
+    let trees = 2 * tree_bytes(ext * width) + 2 * ext * tree_bytes(ext) + tree_bytes(2 * ext);
+
+    cells + dr + roots + trees
+}
+
+// Hook for node software tracking memory across many cached squares
+pub trait MemoryAccountant: Sync {
+    // Fails if the reservation would take usage over the accountant's limit
+    fn try_reserve(&self, bytes: usize) -> Result<()>;
+    fn release(&self, bytes: usize);
+}
+
+// A reservation held against an accountant, released on drop
+pub struct MemoryCharge<'a> {
+    accountant: &'a dyn MemoryAccountant,
+    bytes: usize,
+}
+
+impl MemoryCharge<'_> {
+    pub fn bytes(&self) -> usize {
+        self.bytes
+    }
+}
+
+impl Drop for MemoryCharge<'_> {
+    fn drop(&mut self) {
+        self.accountant.release(self.bytes);
+    }
+}
+
+impl ExtendedDataSquare {
+    // Reserves the square's current memory usage with `accountant`
+    pub fn charge<'a>(&self, accountant: &'a dyn MemoryAccountant) -> Result<MemoryCharge<'a>> {
+        let bytes = self.memory_usage();
+        accountant.try_reserve(bytes)?;
+        Ok(MemoryCharge { accountant, bytes })
+    }
+}
+
+// Accountant with a fixed global limit
+pub struct MemoryBudget {
+    limit: usize,
+    used: AtomicUsize,
+}
+
+impl MemoryBudget {
+    pub fn new(limit: usize) -> Self {
+        Self {
+            limit,
+            used: AtomicUsize::new(0),
+        }
+    }
+
+    pub fn used(&self) -> usize {
+        self.used.load(Ordering::Relaxed)
+    }
+}
+
+impl MemoryAccountant for MemoryBudget {
+    fn try_reserve(&self, bytes: usize) -> Result<()> {
+        let reserved = self
+            .used
+            .fetch_update(Ordering::AcqRel, Ordering::Acquire, |used| {
+                used.checked_add(bytes).filter(|total| *total <= self.limit)
+            });
+        if reserved.is_err() {
+            bail!(
+                "reserving {} bytes exceeds the memory budget of {} ({} in use)",
+                bytes,
+                self.limit,
+                self.used()
+            );
+        }
+        Ok(())
+    }
+
+    fn release(&self, bytes: usize) {
+        self.used.fetch_sub(bytes, Ordering::AcqRel);
+    }
+}
@@ -0,0 +1,198 @@
+// Merkle proof checks matching rs_merkle's trees, without depending on it.
+// Compiled without `std`, see `witness`.
+use alloc::vec::Vec;
+
+use sha2::{Digest, Sha256};
+
+// Recomputes the root over `leaves` leaves from the leaf at `index`. A node
+// without a sibling is promoted without consuming a path hash.
+pub fn fold_path(
+    leaf: [u8; 32],
+    index: usize,
+    leaves: usize,
+    path: &[[u8; 32]],
+) -> Option<[u8; 32]> {
+    let mut node = leaf;
+    let mut hashes = path.iter();
+    let mut level = 0;
+    while (1 << level) < leaves {
+        let position = index >> level;
+        let sibling = position ^ 1;
+        if sibling << level < leaves {
+            let sibling = hashes.next()?;
+            let mut hasher = Sha256::new();
+            if position % 2 == 1 {
+                hasher.update(sibling);
+                hasher.update(node);
+            } else {
+                hasher.update(node);
+                hasher.update(sibling);
+            }
+            node = hasher.finalize().into();
+        }
+        level += 1;
+    }
+
+    // every hash has to be used
+    match hashes.next() {
+        Some(_) => None,
+        None => Some(node),
+    }
+}
+
+// Hashes in the path of leaf `index` out of `leaves`, one per level where
+// its node has a sibling
+pub fn path_len(index: usize, leaves: usize) -> usize {
+    let mut len = 0;
+    let mut level = 0;
+    while (1 << level) < leaves {
+        if ((index >> level) ^ 1) << level < leaves {
+            len += 1;
+        }
+        level += 1;
+    }
+    len
+}
+
+pub fn verify_path(
+    root: &[u8; 32],
+    leaf: [u8; 32],
+    index: usize,
+    leaves: usize,
+    path: &[[u8; 32]],
+) -> bool {
+    index < leaves && fold_path(leaf, index, leaves, path) == Some(*root)
+}
+
+// Root over `leaves`, `None` if there are none
+pub fn root(leaves: &[[u8; 32]]) -> Option<[u8; 32]> {
+    let mut level = leaves.to_vec();
+    while level.len() > 1 {
+        level = next_level(&level);
+    }
+    level.first().copied()
+}
+
+// Path of leaf `index`, as checked by `verify_path`
+pub fn path(leaves: &[[u8; 32]], index: usize) -> Option<Vec<[u8; 32]>> {
+    if index >= leaves.len() {
+        return None;
+    }
+
+    let mut path = Vec::new();
+    let mut level = leaves.to_vec();
+    let mut position = index;
+    while level.len() > 1 {
+        if let Some(sibling) = level.get(position ^ 1) {
+            path.push(*sibling);
+        }
+        level = next_level(&level);
+        position >>= 1;
+    }
+    Some(path)
+}
+
+// Hashes proving all of `indices` at once, as checked by `fold_multiproof`.
+// Siblings shared between the leaves, or computable from them, are left out.
+pub fn multiproof(leaves: &[[u8; 32]], indices: &[usize]) -> Option<Vec<[u8; 32]>> {
+    let mut known: Vec<usize> = indices.to_vec();
+    known.sort_unstable();
+    known.dedup();
+    if known.is_empty() || known.last()? >= &leaves.len() {
+        return None;
+    }
+
+    let mut proof = Vec::new();
+    let mut level = leaves.to_vec();
+    while level.len() > 1 {
+        for &position in &known {
+            let sibling = position ^ 1;
+            if sibling < level.len() && known.binary_search(&sibling).is_err() {
+                proof.push(level[sibling]);
+            }
+        }
+        known = known.iter().map(|position| position >> 1).collect();
+        known.dedup();
+        level = next_level(&level);
+    }
+    Some(proof)
+}
+
+// Recomputes the root over `leaves` leaves from the (index, leaf) pairs in
+// `nodes`, which have to be sorted by strictly increasing index
+pub fn fold_multiproof(
+    nodes: &[(usize, [u8; 32])],
+    leaves: usize,
+    proof: &[[u8; 32]],
+) -> Option<[u8; 32]> {
+    if nodes.is_empty() || nodes.windows(2).any(|pair| pair[0].0 >= pair[1].0) {
+        return None;
+    }
+    if nodes.last()?.0 >= leaves {
+        return None;
+    }
+
+    let mut nodes = nodes.to_vec();
+    let mut hashes = proof.iter();
+    let mut len = leaves;
+    while len > 1 {
+        let mut next = Vec::with_capacity(nodes.len());
+        let mut i = 0;
+        while i < nodes.len() {
+            let (position, node) = nodes[i];
+            let parent = if position & 1 == 1 {
+                hash_pair(hashes.next()?, &node)
+            } else if nodes
+                .get(i + 1)
+                .is_some_and(|(next, _)| *next == position + 1)
+            {
+                i += 1;
+                hash_pair(&node, &nodes[i].1)
+            } else if position + 1 < len {
+                hash_pair(&node, hashes.next()?)
+            } else {
+                // promoted
+                node
+            };
+            next.push((position >> 1, parent));
+            i += 1;
+        }
+        nodes = next;
+        len = len.div_ceil(2);
+    }
+
+    // every hash has to be used
+    match hashes.next() {
+        Some(_) => None,
+        None => Some(nodes[0].1),
+    }
+}
+
+pub fn verify_multiproof(
+    root: &[u8; 32],
+    nodes: &[(usize, [u8; 32])],
+    leaves: usize,
+    proof: &[[u8; 32]],
+) -> bool {
+    fold_multiproof(nodes, leaves, proof) == Some(*root)
+}
+
+pub fn hash_pair(left: &[u8; 32], right: &[u8; 32]) -> [u8; 32] {
+    Sha256::new()
+        .chain_update(left)
+        .chain_update(right)
+        .finalize()
+        .into()
+}
+
+fn next_level(level: &[[u8; 32]]) -> Vec<[u8; 32]> {
+    level
+        .chunks(2)
+        .map(|pair| match pair {
+            [left, right] => hash_pair(left, right),
+            // promoted
+            [node] => *node,
+            _ => unreachable!(),
+        })
+        .collect()
+}
@@ -0,0 +1,302 @@
+#[cfg(feature = "prover")]
+use anyhow::{bail, Result};
+
+use crate::felt::Felt;
+use crate::proof::ShareProof;
+#[cfg(feature = "prover")]
+use crate::subsquare::SubSquare;
+#[cfg(feature = "prover")]
+use crate::tree::ExtendedDataSquare;
+
+// Bytes of a share taken by its namespace. Shares of a namespaced square are
+// single felts: the namespace in the top 8 bytes, payload in the bottom 8, so
+// namespaces are committed to by the same trees as the data.
+pub const NAMESPACE_SIZE: usize = 8;
+pub const PAYLOAD_SIZE: usize = 16 - NAMESPACE_SIZE;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub struct Namespace(pub [u8; NAMESPACE_SIZE]);
+
+impl Namespace {
+    // the largest namespace, used to pad the tail of the original square
+    pub const TAIL_PADDING: Namespace = Namespace([0xff; NAMESPACE_SIZE]);
+
+    pub fn from_u64(id: u64) -> Self {
+        Self(id.to_be_bytes())
+    }
+
+    pub fn of(share: &Felt) -> Self {
+        let bytes = share.val().to_be_bytes();
+        Self(bytes[..NAMESPACE_SIZE].try_into().unwrap())
+    }
+}
+
+pub fn namespaced_share(namespace: Namespace, payload: [u8; PAYLOAD_SIZE]) -> Felt {
+    let mut bytes = [0; 16];
+    bytes[..NAMESPACE_SIZE].copy_from_slice(&namespace.0);
+    bytes[NAMESPACE_SIZE..].copy_from_slice(&payload);
+    Felt::new(u128::from_be_bytes(bytes))
+}
+
+// `Namespace::TAIL_PADDING` is reserved for padding the end of the original
+// square: once a share of column-major `q1_cols` holds it, every later share
+// in row-major order must too
+#[cfg(feature = "prover")]
+pub(crate) fn check_tail_padding(q1_cols: &[Vec<Felt>]) -> Result<()> {
+    let width = q1_cols.len();
+    let mut padding = None;
+    for (row, col) in (0..width).flat_map(|row| (0..width).map(move |col| (row, col))) {
+        let is_padding = Namespace::of(&q1_cols[col][row]) == Namespace::TAIL_PADDING;
+        match padding {
+            None if is_padding => padding = Some((row, col)),
+            Some((r, c)) if !is_padding => bail!(
+                "share ({}, {}) follows tail padding at ({}, {}), \
+                the reserved padding namespace must end the square",
+                row,
+                col,
+                r,
+                c
+            ),
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+pub fn share_payload(share: &Felt) -> [u8; PAYLOAD_SIZE] {
+    share.val().to_be_bytes()[NAMESPACE_SIZE..]
+        .try_into()
+        .unwrap()
+}
+
+// Proof that no original share of a square carries `namespace`: the shares
+// right before and after where it would sit, adjacent in row-major order of
+// the original square. At either end of the square only one neighbour exists.
+// The trees don't order leaves by namespace, so this only holds for squares
+// built sorted, see `is_namespace_sorted`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AbsenceProof {
+    pub namespace: Namespace,
+    // the last share of a smaller namespace
+    pub before: Option<ShareProof>,
+    // the first share of a larger namespace
+    pub after: Option<ShareProof>,
+}
+
+impl AbsenceProof {
+    pub fn verify(&self, data_root: &[u8; 32]) -> bool {
+        let width = match (&self.before, &self.after) {
+            (Some(proof), _) | (None, Some(proof)) => proof.cell_proof.width,
+            (None, None) => return false,
+        };
+        let half = width / 2;
+        // row-major position within the original square
+        let position = |proof: &ShareProof| {
+            (proof.row() < half && proof.col() < half).then(|| proof.row() * half + proof.col())
+        };
+        let holds = |proof: &ShareProof| {
+            proof.cell_proof.width == width && proof.verify(data_root) && position(proof).is_some()
+        };
+
+        match (&self.before, &self.after) {
+            (Some(before), Some(after)) => {
+                holds(before)
+                    && holds(after)
+                    && position(before).map(|i| i + 1) == position(after)
+                    && Namespace::of(&before.share) < self.namespace
+                    && self.namespace < Namespace::of(&after.share)
+            }
+            (Some(before), None) => {
+                holds(before)
+                    && position(before) == Some(half * half - 1)
+                    && Namespace::of(&before.share) < self.namespace
+            }
+            (None, Some(after)) => {
+                holds(after)
+                    && position(after) == Some(0)
+                    && self.namespace < Namespace::of(&after.share)
+            }
+            (None, None) => false,
+        }
+    }
+}
+
+// All shares of a namespace in one proof: its run of shares plus the
+// neighbour on either side, if any, so none can be left out. They are opened
+// together, with one multiproof per row spanned and one for those rows' roots.
+// Like `AbsenceProof` this relies on the square being sorted by namespace.
+#[cfg(feature = "prover")]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NamespaceProof {
+    pub namespace: Namespace,
+    pub cells: SubSquare,
+}
+
+#[cfg(feature = "prover")]
+impl NamespaceProof {
+    // The shares of the namespace, in row-major order
+    pub fn shares(&self) -> Vec<Felt> {
+        self.run()
+            .filter(|(_, share)| Namespace::of(share) == self.namespace)
+            .map(|(_, share)| share)
+            .collect()
+    }
+
+    // (row-major position in the original square, share) of every opened
+    // cell, `None` for cells outside of it
+    fn run(&self) -> impl Iterator<Item = (Option<usize>, Felt)> + '_ {
+        let half = self.cells.width / 2;
+        self.cells.rows.iter().flat_map(move |row| {
+            row.cells.iter().map(move |&(col, share)| {
+                let position = (row.row < half && col < half).then_some(row.row * half + col);
+                (position, share)
+            })
+        })
+    }
+
+    pub fn verify(&self, data_root: &[u8; 32]) -> bool {
+        if !self.cells.verify(data_root) {
+            return false;
+        }
+        let half = self.cells.width / 2;
+        let cells: Vec<(Option<usize>, Felt)> = self.run().collect();
+
+        // a contiguous run of original shares
+        let mut positions = Vec::with_capacity(cells.len());
+        for (position, _) in &cells {
+            let Some(position) = position else {
+                return false;
+            };
+            positions.push(*position);
+        }
+        if positions.windows(2).any(|w| w[0] + 1 != w[1]) {
+            return false;
+        }
+
+        // at most one smaller namespace before the run and one larger after
+        let namespaces: Vec<Namespace> = cells
+            .iter()
+            .map(|(_, share)| Namespace::of(share))
+            .collect();
+        let (Some(first), Some(last)) = (namespaces.first(), namespaces.last()) else {
+            return false;
+        };
+        let inner_start = usize::from(*first < self.namespace);
+        let inner_end = namespaces.len() - usize::from(*last > self.namespace);
+        if inner_start >= inner_end
+            || namespaces[inner_start..inner_end]
+                .iter()
+                .any(|ns| *ns != self.namespace)
+        {
+            return false;
+        }
+
+        // without a neighbour the run has to reach the edge of the square
+        (inner_start == 1 || positions[0] == 0)
+            && (inner_end < namespaces.len() || positions[positions.len() - 1] == half * half - 1)
+    }
+}
+
+#[cfg(feature = "prover")]
+impl ExtendedDataSquare {
+    // Walks the original shares (q1, parity is skipped) in row-major order,
+    // which is namespace order for a well-formed square, yielding
+    // (namespace, row, col, share)
+    pub fn namespaced_shares(&self) -> impl Iterator<Item = (Namespace, usize, usize, Felt)> + '_ {
+        let half = self.width() / 2;
+        (0..half).flat_map(move |row| {
+            (0..half).map(move |col| {
+                let share = self.cell(row, col);
+                (Namespace::of(&share), row, col, share)
+            })
+        })
+    }
+
+    // Whether the original shares are sorted by namespace in row-major order
+    pub fn is_namespace_sorted(&self) -> bool {
+        let mut namespaces = self.namespaced_shares().map(|(namespace, ..)| namespace);
+        let Some(mut prev) = namespaces.next() else {
+            return true;
+        };
+        namespaces.all(|namespace| {
+            let sorted = prev <= namespace;
+            prev = namespace;
+            sorted
+        })
+    }
+
+    // The shares of `namespace`, in order
+    pub fn shares_in(
+        &self,
+        namespace: Namespace,
+    ) -> impl Iterator<Item = (usize, usize, Felt)> + '_ {
+        self.namespaced_shares()
+            .skip_while(move |(ns, ..)| *ns < namespace)
+            .take_while(move |(ns, ..)| *ns == namespace)
+            .map(|(_, row, col, share)| (row, col, share))
+    }
+
+    // Proves `namespace` has no shares in the square, failing if it has or
+    // the square isn't sorted by namespace
+    pub fn prove_namespace_absence(&self, namespace: Namespace) -> Result<AbsenceProof> {
+        if !self.is_namespace_sorted() {
+            bail!("square is not sorted by namespace");
+        }
+
+        let half = self.width() / 2;
+        let mut before = None;
+        for (ns, row, col, _) in self.namespaced_shares() {
+            if ns == namespace {
+                bail!("namespace has a share at ({}, {})", row, col);
+            }
+            if ns > namespace {
+                return Ok(AbsenceProof {
+                    namespace,
+                    before: before
+                        .map(|(row, col)| self.prove_share(row, col))
+                        .transpose()?,
+                    after: Some(self.prove_share(row, col)?),
+                });
+            }
+            before = Some((row, col));
+        }
+
+        let Some((row, col)) = before else {
+            bail!("square of width {} has no original shares", half);
+        };
+        Ok(AbsenceProof {
+            namespace,
+            before: Some(self.prove_share(row, col)?),
+            after: None,
+        })
+    }
+
+    // Proves every share of `namespace` at once, see `NamespaceProof`. Fails
+    // if it has none, `prove_namespace_absence` covers that case.
+    pub fn prove_namespace(&self, namespace: Namespace) -> Result<NamespaceProof> {
+        if !self.is_namespace_sorted() {
+            bail!("square is not sorted by namespace");
+        }
+
+        let half = self.width() / 2;
+        let positions: Vec<usize> = self
+            .namespaced_shares()
+            .enumerate()
+            .filter(|(_, (ns, ..))| *ns == namespace)
+            .map(|(position, _)| position)
+            .collect();
+        let (Some(&start), Some(&end)) = (positions.first(), positions.last()) else {
+            bail!("namespace has no shares");
+        };
+
+        let first = start.saturating_sub(1);
+        let last = (end + 1).min(half * half - 1);
+        let coords: Vec<(usize, usize)> = (first..=last)
+            .map(|position| (position / half, position % half))
+            .collect();
+        Ok(NamespaceProof {
+            namespace,
+            cells: self.extract(&coords)?,
+        })
+    }
+}
@@ -0,0 +1,149 @@
+// Openings of a column of the left half (q1 over q3) against the x_tree. All
+// cells of the column are checked with one multiproof, and so are the
+// dr-scaled q1 cells against the y_tree. That the scaled cells really are
+// dr[col] times the column is then checked with a single random linear
+// combination, `sum r_i * scaled_i == dr[col] * sum r_i * cell_i`, instead of
+// cell by cell. Only squares with per-felt leaves can be opened this way.
+#[cfg(feature = "prover")]
+use anyhow::Context as _;
+use anyhow::{bail, Result};
+use sha2::{Digest, Sha256};
+
+use crate::felt::{leaf_hash, Felt};
+use crate::merkle;
+#[cfg(feature = "prover")]
+use crate::tree::{ExtendedDataSquare, LeafStrategy};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct XColumnOpening {
+    // column of the left half, below the original width
+    pub col: usize,
+    // the column's cells, rows of q1 then rows of q3
+    pub cells: Vec<Felt>,
+    pub x_proof: Vec<[u8; 32]>,
+    // dr[col] times the q1 cells, as committed to by the y_tree
+    pub scaled: Vec<Felt>,
+    pub y_proof: Vec<[u8; 32]>,
+}
+
+// x_tree leaves are the rows of (q1, q3), `half` felts each
+fn x_indices(col: usize, half: usize) -> Vec<usize> {
+    (0..2 * half).map(|row| row * half + col).collect()
+}
+
+// the y_tree starts with the dr-scaled columns of q1, `half` felts each
+fn y_indices(col: usize, half: usize) -> Vec<usize> {
+    (col * half..(col + 1) * half).collect()
+}
+
+#[cfg(feature = "prover")]
+impl ExtendedDataSquare {
+    pub fn open_x_column(&self, col: usize) -> Result<XColumnOpening> {
+        if self.leaf_strategy() != LeafStrategy::PerFelt {
+            bail!("columns can only be opened with per-felt leaves");
+        }
+        let half = self.width() / 2;
+        if col >= half {
+            bail!(
+                "column {} is not in the left half of width {}",
+                col,
+                self.width()
+            );
+        }
+
+        let (x_leaves, y_leaves) = self.commitment_leaves();
+        let x_proof =
+            merkle::multiproof(&x_leaves, &x_indices(col, half)).context("empty x_tree")?;
+        let y_proof =
+            merkle::multiproof(&y_leaves, &y_indices(col, half)).context("empty y_tree")?;
+
+        Ok(XColumnOpening {
+            col,
+            cells: self.col(col).to_vec(),
+            x_proof,
+            scaled: self.col(col)[..half]
+                .iter()
+                .map(|cell| self.dr()[col] * *cell)
+                .collect(),
+            y_proof,
+        })
+    }
+}
+
+impl XColumnOpening {
+    // `dr` is the square's dr, as derived from `x_root`. `width` is the
+    // extended width.
+    pub fn verify(
+        &self,
+        x_root: &[u8; 32],
+        y_root: &[u8; 32],
+        dr: &[Felt],
+        width: usize,
+    ) -> Result<()> {
+        if width < 2 || !width.is_power_of_two() {
+            bail!("extended width must be a power of two, got {}", width);
+        }
+        let half = width / 2;
+        if self.col >= half || dr.len() != half {
+            bail!("column {} or dr of {} out of range", self.col, dr.len());
+        }
+        if self.cells.len() != width || self.scaled.len() != half {
+            bail!(
+                "{} cells and {} scaled cells for width {}",
+                self.cells.len(),
+                self.scaled.len(),
+                width
+            );
+        }
+
+        let leaves = |indices: Vec<usize>, cells: &[Felt]| -> Vec<(usize, [u8; 32])> {
+            indices
+                .into_iter()
+                .zip(cells.iter().map(leaf_hash))
+                .collect()
+        };
+        let x_nodes = leaves(x_indices(self.col, half), &self.cells);
+        if !merkle::verify_multiproof(x_root, &x_nodes, width * half, &self.x_proof) {
+            bail!("column {} does not verify against the x_tree", self.col);
+        }
+        let y_nodes = leaves(y_indices(self.col, half), &self.scaled);
+        if !merkle::verify_multiproof(y_root, &y_nodes, width * half, &self.y_proof) {
+            bail!(
+                "scaled column {} does not verify against the y_tree",
+                self.col
+            );
+        }
+
+        let weights = self.weights(x_root, y_root);
+        let inner = |cells: &[Felt]| -> Felt {
+            weights.iter().zip(cells).map(|(r, cell)| *r * *cell).sum()
+        };
+        if inner(&self.scaled) != dr[self.col] * inner(&self.cells[..half]) {
+            bail!("column {} is not consistent with dr", self.col);
+        }
+        Ok(())
+    }
+
+    // Fiat-Shamir weights, fixed by the roots and the opened cells
+    fn weights(&self, x_root: &[u8; 32], y_root: &[u8; 32]) -> Vec<Felt> {
+        let mut transcript = Sha256::new();
+        transcript.update(b"zoda-column-opening");
+        transcript.update(x_root);
+        transcript.update(y_root);
+        transcript.update((self.col as u64).to_be_bytes());
+        for cell in self.cells.iter().chain(&self.scaled) {
+            transcript.update(cell.val().to_be_bytes());
+        }
+        let seed: [u8; 32] = transcript.finalize().into();
+
+        (0..self.scaled.len() as u64)
+            .map(|i| {
+                let digest = Sha256::new()
+                    .chain_update(seed)
+                    .chain_update(i.to_be_bytes())
+                    .finalize();
+                Felt::new(u128::from_be_bytes(digest[..16].try_into().unwrap()))
+            })
+            .collect()
+    }
+}
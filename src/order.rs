@@ -0,0 +1,54 @@
+use std::cmp::Ordering;
+
+use crate::felt::Felt;
+use crate::namespace::{share_payload, Namespace};
+
+// Canonical orders, so independent implementations list shares and cells the
+// same way:
+//   - cells of the extended square are row-major, (row, col) compared as a
+//     tuple
+//   - namespaced shares by namespace, then by payload, as big-endian bytes
+// Serialized squares keep their versioned column-major layout.
+pub trait CanonicalOrd {
+    fn canonical_cmp(&self, other: &Self) -> Ordering;
+}
+
+impl CanonicalOrd for (usize, usize) {
+    fn canonical_cmp(&self, other: &Self) -> Ordering {
+        self.cmp(other)
+    }
+}
+
+impl CanonicalOrd for Felt {
+    fn canonical_cmp(&self, other: &Self) -> Ordering {
+        Namespace::of(self)
+            .cmp(&Namespace::of(other))
+            .then_with(|| share_payload(self).cmp(&share_payload(other)))
+    }
+}
+
+pub fn canonical_sort<T: CanonicalOrd>(items: &mut [T]) {
+    items.sort_by(CanonicalOrd::canonical_cmp);
+}
+
+// Sorts and drops duplicates
+pub fn canonical_dedup<T: CanonicalOrd>(items: &mut Vec<T>) {
+    canonical_sort(items);
+    items.dedup_by(|a, b| a.canonical_cmp(b) == Ordering::Equal);
+}
+
+// Strictly increasing, i.e. sorted without duplicates
+pub fn is_canonical<T: CanonicalOrd>(items: &[T]) -> bool {
+    items
+        .windows(2)
+        .all(|w| w[0].canonical_cmp(&w[1]) == Ordering::Less)
+}
+
+// Position of a cell in row-major order
+pub fn row_major_index(row: usize, col: usize, width: usize) -> usize {
+    row * width + col
+}
+
+pub fn row_major_coords(index: usize, width: usize) -> (usize, usize) {
+    (index / width, index % width)
+}
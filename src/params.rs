@@ -0,0 +1,284 @@
+use std::fmt;
+
+use anyhow::{bail, Result};
+use sha2::{Digest, Sha256};
+
+use crate::codecs::BINIUS_RS_128;
+use crate::hashers::SHA256;
+use crate::tree::{LeafStrategy, MAX_WIDTH, SHARE_SIZE};
+
+// Parameters a square was built with
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ZodaParams {
+    // width of the original (q1) square
+    pub width: usize,
+    pub codec_id: String,
+    pub hasher_id: String,
+    // digest of the `Context` the square is bound to, if any
+    pub context: Option<[u8; 32]>,
+    pub leaf_strategy: LeafStrategy,
+}
+
+impl ZodaParams {
+    pub fn new(width: usize) -> Self {
+        Self {
+            width,
+            codec_id: BINIUS_RS_128.to_string(),
+            hasher_id: SHA256.to_string(),
+            context: None,
+            leaf_strategy: LeafStrategy::default(),
+        }
+    }
+
+    pub fn with_context(mut self, context: &Context) -> Self {
+        self.context = Some(context.digest());
+        self
+    }
+
+    pub fn with_leaf_strategy(mut self, leaf_strategy: LeafStrategy) -> Self {
+        self.leaf_strategy = leaf_strategy;
+        self
+    }
+
+    pub fn extended_width(&self) -> usize {
+        2 * self.width
+    }
+
+    // Everything a verifier has to agree on to check squares built with these
+    // parameters
+    pub fn describe(&self) -> ParamsDescription {
+        ParamsDescription {
+            field: "binius BinaryField128b, GF(2^128) in the canonical tower basis",
+            field_bits: 128,
+            codec_id: self.codec_id.clone(),
+            rate: (1, 2),
+            hasher_id: self.hasher_id.clone(),
+            leaf_encoding: "sha256 of the felt's 16 big-endian bytes, one leaf per cell",
+            commitment_leaves: match self.leaf_strategy {
+                LeafStrategy::PerFelt => "x_tree and y_tree: one leaf per felt",
+                LeafStrategy::PerShare => {
+                    "x_tree and y_tree: one leaf per row, sha256 of its felts' bytes"
+                }
+                LeafStrategy::Per4Felts => {
+                    "x_tree and y_tree: one leaf per 4 felts of a row, sha256 of their bytes"
+                }
+                LeafStrategy::Per16Felts => {
+                    "x_tree and y_tree: one leaf per 16 felts of a row, sha256 of their bytes"
+                }
+            },
+            tree: "binary merkle tree, a node without a sibling is promoted",
+            dr_derivation: "felt i is the first 16 bytes of \
+                sha256(x_tree root || context digest, if any || i as big-endian u64)",
+            data_root: "merkle root over the row roots followed by the column roots",
+            width: self.width,
+            extended_width: self.extended_width(),
+            context: self.context,
+        }
+    }
+
+    // Identifies the parameters, two configurations agree on every square
+    // iff their digests match
+    pub fn digest(&self) -> [u8; 32] {
+        self.describe().digest()
+    }
+
+    pub fn check_share_size(&self, share_size: usize) -> Result<()> {
+        check_share_size(&self.codec_id, share_size)
+    }
+
+    // Felt squares are currently always built with the binius code and sha256 trees
+    pub fn check_supported(&self) -> Result<()> {
+        if !self.width.is_power_of_two() {
+            bail!("width must be a power of two, got {}", self.width);
+        }
+        if self.codec_id != BINIUS_RS_128 {
+            bail!("codec {} cannot be used for felt squares", self.codec_id);
+        }
+        if self.hasher_id != SHA256 {
+            bail!("hasher {} cannot be used for felt squares", self.hasher_id);
+        }
+        Ok(())
+    }
+}
+
+// Smallest share size fitting `total_bytes` into a `target_width` x
+// `target_width` original square, rounded up to whole felts so it suits the
+// binius path
+pub fn recommended_share_size(total_bytes: usize, target_width: usize) -> Result<usize> {
+    if !target_width.is_power_of_two() {
+        bail!("width must be a power of two, got {}", target_width);
+    }
+    let Some(cells) = target_width.checked_mul(target_width) else {
+        bail!("width {} is too large", target_width);
+    };
+    Ok(total_bytes
+        .div_ceil(cells)
+        .max(1)
+        .next_multiple_of(SHARE_SIZE))
+}
+
+// Checks shares of `share_size` bytes can be encoded with `codec_id`. The
+// binius code works on felts, so its shares have to be whole felts.
+pub fn check_share_size(codec_id: &str, share_size: usize) -> Result<()> {
+    if share_size == 0 {
+        bail!("share size must be positive");
+    }
+    if codec_id == BINIUS_RS_128 && !share_size.is_multiple_of(SHARE_SIZE) {
+        bail!(
+            "share size {} is not a multiple of the {} byte felt size {} needs, \
+            pad shares to {} bytes or pick a size with recommended_share_size",
+            share_size,
+            SHARE_SIZE,
+            codec_id,
+            share_size.next_multiple_of(SHARE_SIZE)
+        );
+    }
+    Ok(())
+}
+
+// Machine-readable summary of `ZodaParams`, see `ZodaParams::describe`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParamsDescription {
+    pub field: &'static str,
+    pub field_bits: u32,
+    pub codec_id: String,
+    // (numerator, denominator) of the code rate along each axis
+    pub rate: (u32, u32),
+    pub hasher_id: String,
+    pub leaf_encoding: &'static str,
+    pub commitment_leaves: &'static str,
+    pub tree: &'static str,
+    pub dr_derivation: &'static str,
+    pub data_root: &'static str,
+    pub width: usize,
+    pub extended_width: usize,
+    pub context: Option<[u8; 32]>,
+}
+
+impl ParamsDescription {
+    // (key, value) pairs, in a stable order
+    pub fn entries(&self) -> Vec<(&'static str, String)> {
+        let context = match &self.context {
+            Some(digest) => digest.iter().map(|b| format!("{:02x}", b)).collect(),
+            None => "none".to_string(),
+        };
+        vec![
+            ("field", self.field.to_string()),
+            ("field_bits", self.field_bits.to_string()),
+            ("codec", self.codec_id.clone()),
+            ("rate", format!("{}/{}", self.rate.0, self.rate.1)),
+            ("hasher", self.hasher_id.clone()),
+            ("leaf_encoding", self.leaf_encoding.to_string()),
+            ("commitment_leaves", self.commitment_leaves.to_string()),
+            ("tree", self.tree.to_string()),
+            ("dr_derivation", self.dr_derivation.to_string()),
+            ("data_root", self.data_root.to_string()),
+            ("width", self.width.to_string()),
+            ("extended_width", self.extended_width.to_string()),
+            ("context", context),
+        ]
+    }
+
+    // sha256 over the entries, each key and value prefixed by its length
+    pub fn digest(&self) -> [u8; 32] {
+        let mut hasher = Sha256::new();
+        hasher.update(b"zoda-params");
+        for (key, value) in self.entries() {
+            for field in [key, value.as_str()] {
+                hasher.update((field.len() as u32).to_be_bytes());
+                hasher.update(field.as_bytes());
+            }
+        }
+        hasher.finalize().into()
+    }
+
+    // Keys whose values differ from `other`'s, e.g. between a producer's and
+    // a verifier's configuration
+    pub fn mismatches(&self, other: &Self) -> Vec<&'static str> {
+        self.entries()
+            .into_iter()
+            .zip(other.entries())
+            .filter(|((_, ours), (_, theirs))| ours != theirs)
+            .map(|((key, _), _)| key)
+            .collect()
+    }
+}
+
+impl fmt::Display for ParamsDescription {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (key, value) in self.entries() {
+            writeln!(f, "{} = {}", key, value)?;
+        }
+        Ok(())
+    }
+}
+
+// External context a square is bound to. Its digest is absorbed into the
+// Fiat-Shamir transcript dr is derived from, so a square (and anything proven
+// against its data root) can't be replayed for another block.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Context {
+    pub chain_id: String,
+    pub height: u64,
+    pub block_hash: [u8; 32],
+}
+
+impl Context {
+    pub fn digest(&self) -> [u8; 32] {
+        let mut hasher = Sha256::new();
+        hasher.update(b"zoda-context");
+        hasher.update((self.chain_id.len() as u32).to_be_bytes());
+        hasher.update(self.chain_id.as_bytes());
+        hasher.update(self.height.to_be_bytes());
+        hasher.update(self.block_hash);
+        hasher.finalize().into()
+    }
+}
+
+// Hard caps on squares built from or decoded out of untrusted input
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Limits {
+    // original (q1) width
+    pub max_width: usize,
+    pub max_share_size: usize,
+    // size of the extended square's cells
+    pub max_total_bytes: usize,
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Self {
+            max_width: MAX_WIDTH,
+            max_share_size: SHARE_SIZE,
+            max_total_bytes: 4 * MAX_WIDTH * MAX_WIDTH * SHARE_SIZE,
+        }
+    }
+}
+
+impl Limits {
+    // Checks a square of `width` x `width` shares of `share_size` bytes
+    pub fn check(&self, width: usize, share_size: usize) -> Result<()> {
+        if width > self.max_width {
+            bail!("width {} exceeds the maximum of {}", width, self.max_width);
+        }
+        if share_size > self.max_share_size {
+            bail!(
+                "share size {} exceeds the maximum of {}",
+                share_size,
+                self.max_share_size
+            );
+        }
+
+        let total = (2 * width)
+            .checked_mul(2 * width)
+            .and_then(|cells| cells.checked_mul(share_size));
+        match total {
+            Some(total) if total <= self.max_total_bytes => Ok(()),
+            _ => bail!(
+                "extended square of width {} exceeds the maximum of {} bytes",
+                2 * width,
+                self.max_total_bytes
+            ),
+        }
+    }
+}
@@ -0,0 +1,193 @@
+use std::collections::HashSet;
+
+use anyhow::{bail, Result};
+use sha2::{Digest, Sha256};
+
+use crate::felt::{leaf_hash, Felt};
+use crate::merkle;
+use crate::proof::ShareProof;
+use crate::sampling::SeedStream;
+#[cfg(feature = "prover")]
+use crate::tree::{DataSquare, ExtendedDataSquare};
+
+// Merkle root over the leaf hashes of the original shares in column-major
+// order, built as shares come in. Only O(log n) subtree roots are held, so a
+// producer can commit to a block's raw data before extending it.
+#[derive(Debug, Clone, Default)]
+pub struct OriginalDataHasher {
+    // (height, root) of completed perfect subtrees, left to right
+    stack: Vec<(usize, [u8; 32])>,
+    count: usize,
+}
+
+impl OriginalDataHasher {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn update(&mut self, share: &Felt) {
+        let mut node = (0, leaf_hash(share));
+        while let Some(&(height, left)) = self.stack.last() {
+            if height != node.0 {
+                break;
+            }
+            self.stack.pop();
+            node = (height + 1, merkle::hash_pair(&left, &node.1));
+        }
+        self.stack.push(node);
+        self.count += 1;
+    }
+
+    // Number of shares absorbed so far
+    pub fn count(&self) -> usize {
+        self.count
+    }
+
+    // Root over everything absorbed, `None` if nothing was. Matches
+    // `merkle::root` over the same leaves.
+    pub fn finalize(&self) -> Option<[u8; 32]> {
+        let mut stack = self.stack.iter().rev();
+        let (_, mut root) = *stack.next()?;
+        for (_, left) in stack {
+            root = merkle::hash_pair(left, &root);
+        }
+        Some(root)
+    }
+}
+
+#[cfg(feature = "prover")]
+impl DataSquare {
+    // Pre-commitment to the original shares, see `OriginalDataHasher`
+    pub fn original_data_hash(&self) -> [u8; 32] {
+        let mut hasher = OriginalDataHasher::new();
+        self.cols()
+            .iter()
+            .flatten()
+            .for_each(|share| hasher.update(share));
+        hasher.finalize().unwrap_or_default()
+    }
+}
+
+// A q1 cell opened under both the original data hash and the data root
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConsistencyOpening {
+    // path under the original data hash, the leaf is `share.share`
+    pub original_path: Vec<[u8; 32]>,
+    pub share: ShareProof,
+}
+
+// Shows that the q1 quadrant of a square is the data pre-committed to by
+// `original_hash`. Cells are picked from both roots Fiat-Shamir style, so a
+// square differing from the pre-commitment in a fraction f of its cells
+// passes with probability about (1 - f)^samples.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConsistencyProof {
+    // width of the original square
+    pub width: usize,
+    pub openings: Vec<ConsistencyOpening>,
+}
+
+impl ConsistencyProof {
+    // The (row, col) cells of q1 a proof with `samples` openings has to cover
+    pub fn positions(
+        original_hash: &[u8; 32],
+        data_root: &[u8; 32],
+        width: usize,
+        samples: usize,
+    ) -> Vec<(usize, usize)> {
+        let seed: [u8; 32] = Sha256::new()
+            .chain_update(b"zoda-rs/consistency/v1")
+            .chain_update(original_hash)
+            .chain_update(data_root)
+            .finalize()
+            .into();
+        let mut rng = SeedStream::new(&seed);
+        let samples = samples.min(width * width);
+        let mut seen = HashSet::new();
+        let mut positions = Vec::with_capacity(samples);
+        while positions.len() < samples {
+            let cell = (rng.below(width), rng.below(width));
+            if seen.insert(cell) {
+                positions.push(cell);
+            }
+        }
+        positions
+    }
+
+    // Checks the proof holds at least `samples` openings, all consistent
+    pub fn verify(
+        &self,
+        original_hash: &[u8; 32],
+        data_root: &[u8; 32],
+        samples: usize,
+    ) -> Result<()> {
+        let width = self.width;
+        if width == 0 || !width.is_power_of_two() {
+            bail!("width must be a power of two, got {}", width);
+        }
+        if self.openings.len() < samples.min(width * width) {
+            bail!("{} openings, {} required", self.openings.len(), samples);
+        }
+
+        let positions = Self::positions(original_hash, data_root, width, self.openings.len());
+        for (opening, (row, col)) in self.openings.iter().zip(positions) {
+            let share = &opening.share;
+            if (share.row(), share.col()) != (row, col) || share.cell_proof.width != 2 * width {
+                bail!("opening of ({}, {}) is for another cell", row, col);
+            }
+            if !share.verify(data_root) {
+                bail!(
+                    "cell ({}, {}) does not verify against the data root",
+                    row,
+                    col
+                );
+            }
+            if !merkle::verify_path(
+                original_hash,
+                leaf_hash(&share.share),
+                col * width + row,
+                width * width,
+                &opening.original_path,
+            ) {
+                bail!(
+                    "cell ({}, {}) differs from the pre-committed data",
+                    row,
+                    col
+                );
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(feature = "prover")]
+impl ExtendedDataSquare {
+    // Proves that q1 is the data behind `original_hash`, with `samples` openings
+    pub fn prove_consistency(
+        &self,
+        original_hash: &[u8; 32],
+        samples: usize,
+    ) -> Result<ConsistencyProof> {
+        let width = self.width() / 2;
+        let leaves: Vec<[u8; 32]> = self.cols()[..width]
+            .iter()
+            .flat_map(|col| col[..width].iter().map(leaf_hash))
+            .collect();
+        if merkle::root(&leaves) != Some(*original_hash) {
+            bail!("q1 does not match the original data hash");
+        }
+
+        let data_root = self.data_root()?;
+        let openings = ConsistencyProof::positions(original_hash, &data_root, width, samples)
+            .into_iter()
+            .map(|(row, col)| {
+                Ok(ConsistencyOpening {
+                    original_path: merkle::path(&leaves, col * width + row).unwrap_or_default(),
+                    share: self.prove_share(row, col)?,
+                })
+            })
+            .collect::<Result<_>>()?;
+
+        Ok(ConsistencyProof { width, openings })
+    }
+}
@@ -0,0 +1,20 @@
+// The types most users need, so `use zoda_rs::prelude::*;` covers building,
+// extending, proving and verifying squares
+pub use binius_field::{BinaryField128b, Field};
+
+pub use crate::certificate::ZodaCertificate;
+pub use crate::felt::{Felt, Share};
+pub use crate::namespace::Namespace;
+pub use crate::proof::{Axis, CellProof, RootProof, ShareProof};
+pub use crate::sampling::{Sampler, SamplingStrategy};
+
+#[cfg(feature = "prover")]
+pub use crate::blob::{verify_blob_inclusion, Blob, BlobProof};
+#[cfg(feature = "prover")]
+pub use crate::codecs::{Codec, CodecRegistry, DecodeResult};
+#[cfg(feature = "prover")]
+pub use crate::params::{Context, Limits, ZodaParams};
+#[cfg(feature = "prover")]
+pub use crate::repair::{PartialEds, RepairError};
+#[cfg(feature = "prover")]
+pub use crate::tree::{Cancelled, DataSquare, ExtendedDataSquare};
@@ -0,0 +1,242 @@
+use std::fmt;
+
+use anyhow::{Context, Result};
+
+use crate::budget::{run_budgeted, Budget, Progress};
+use crate::felt::{leaf_hash, Felt};
+use crate::merkle::{path_len, verify_path};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Axis {
+    Row,
+    Col,
+}
+
+// Why a proof was rejected, so operators can tell a buggy peer (bad paths,
+// wrong positions) from one serving data that isn't committed to. Returned
+// by the `check` methods, and inside anyhow::Error where a check bails.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Rejection {
+    // a coordinate outside the square
+    OutOfRange {
+        row: usize,
+        col: usize,
+        width: usize,
+    },
+    // the proof is for another cell, axis or width than the one it's used for
+    WrongPosition,
+    // a path with the wrong number of hashes for its leaf
+    PathLength {
+        expected: usize,
+        actual: usize,
+    },
+    // the cell's leaf hash and path don't lead to the axis root
+    LeafMismatch,
+    // the axis root and its path don't lead to the data root
+    RootMismatch,
+    // dr entry `index` isn't the one derived from the x_tree commitment
+    DrMismatch {
+        index: usize,
+    },
+    // the response echoes another session's nonce
+    NonceMismatch,
+    // the peer has a known key but the response isn't signed
+    Unsigned,
+    // the response's signature doesn't verify, or is by another key
+    BadSignature,
+}
+
+impl fmt::Display for Rejection {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::OutOfRange { row, col, width } => {
+                write!(
+                    f,
+                    "cell ({}, {}) out of range for width {}",
+                    row, col, width
+                )
+            }
+            Self::WrongPosition => write!(f, "proof is for another position"),
+            Self::PathLength { expected, actual } => {
+                write!(f, "path of {} hashes, expected {}", actual, expected)
+            }
+            Self::LeafMismatch => write!(f, "leaf does not lead to the axis root"),
+            Self::RootMismatch => write!(f, "axis root does not lead to the data root"),
+            Self::DrMismatch { index } => {
+                write!(f, "dr[{}] does not match the x_tree commitment", index)
+            }
+            Self::NonceMismatch => write!(f, "response is for another session"),
+            Self::Unsigned => write!(f, "response is not signed"),
+            Self::BadSignature => write!(f, "response signature does not verify"),
+        }
+    }
+}
+
+impl std::error::Error for Rejection {}
+
+fn check_path_len(index: usize, leaves: usize, path: &[[u8; 32]]) -> Result<(), Rejection> {
+    let expected = path_len(index, leaves);
+    if path.len() != expected {
+        return Err(Rejection::PathLength {
+            expected,
+            actual: path.len(),
+        });
+    }
+    Ok(())
+}
+
+// Proof that a row or column root is committed to by the data root.
+// The data root is the Merkle root over all row roots followed by all column roots.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RootProof {
+    pub axis: Axis,
+    pub index: usize,
+    // width of the extended square
+    pub width: usize,
+    pub path: Vec<[u8; 32]>,
+}
+
+impl RootProof {
+    pub fn leaf_index(&self) -> usize {
+        match self.axis {
+            Axis::Row => self.index,
+            Axis::Col => self.width + self.index,
+        }
+    }
+
+    pub fn verify(&self, axis_root: &[u8; 32], data_root: &[u8; 32]) -> bool {
+        self.check(axis_root, data_root).is_ok()
+    }
+
+    pub fn check(&self, axis_root: &[u8; 32], data_root: &[u8; 32]) -> Result<(), Rejection> {
+        if self.index >= self.width {
+            let (row, col) = match self.axis {
+                Axis::Row => (self.index, 0),
+                Axis::Col => (0, self.index),
+            };
+            return Err(Rejection::OutOfRange {
+                row,
+                col,
+                width: self.width,
+            });
+        }
+        check_path_len(self.leaf_index(), 2 * self.width, &self.path)?;
+
+        if !verify_path(
+            data_root,
+            *axis_root,
+            self.leaf_index(),
+            2 * self.width,
+            &self.path,
+        ) {
+            return Err(Rejection::RootMismatch);
+        }
+        Ok(())
+    }
+}
+
+// Proof that a cell is committed to by its row root (or column root)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CellProof {
+    // the axis whose root the cell is proven against
+    pub axis: Axis,
+    pub row: usize,
+    pub col: usize,
+    // width of the extended square
+    pub width: usize,
+    pub path: Vec<[u8; 32]>,
+}
+
+impl CellProof {
+    // position of the cell within the row (or column) tree
+    pub fn leaf_index(&self) -> usize {
+        match self.axis {
+            Axis::Row => self.col,
+            Axis::Col => self.row,
+        }
+    }
+
+    pub fn verify(&self, cell: &Felt, axis_root: &[u8; 32]) -> bool {
+        self.check(cell, axis_root).is_ok()
+    }
+
+    pub fn check(&self, cell: &Felt, axis_root: &[u8; 32]) -> Result<(), Rejection> {
+        if self.row >= self.width || self.col >= self.width {
+            return Err(Rejection::OutOfRange {
+                row: self.row,
+                col: self.col,
+                width: self.width,
+            });
+        }
+        check_path_len(self.leaf_index(), self.width, &self.path)?;
+
+        if !verify_path(
+            axis_root,
+            leaf_hash(cell),
+            self.leaf_index(),
+            self.width,
+            &self.path,
+        ) {
+            return Err(Rejection::LeafMismatch);
+        }
+        Ok(())
+    }
+}
+
+// Proof chaining a single share all the way up to the data root:
+// share -> row root -> data root
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ShareProof {
+    pub share: Felt,
+    pub cell_proof: CellProof,
+    pub row_root: [u8; 32],
+    pub root_proof: RootProof,
+}
+
+impl ShareProof {
+    pub fn row(&self) -> usize {
+        self.cell_proof.row
+    }
+
+    pub fn col(&self) -> usize {
+        self.cell_proof.col
+    }
+
+    // Bytes of hashes and data carried by the proof, indices aside
+    pub fn byte_len(&self) -> usize {
+        16 + 32 + 32 * (self.cell_proof.path.len() + self.root_proof.path.len())
+    }
+
+    pub fn verify(&self, data_root: &[u8; 32]) -> bool {
+        self.check(data_root).is_ok()
+    }
+
+    pub fn check(&self, data_root: &[u8; 32]) -> Result<(), Rejection> {
+        // the row root proof has to be for the row the cell lives in
+        if self.root_proof.axis != Axis::Row
+            || self.cell_proof.axis != Axis::Row
+            || self.root_proof.index != self.cell_proof.row
+            || self.root_proof.width != self.cell_proof.width
+        {
+            return Err(Rejection::WrongPosition);
+        }
+
+        self.cell_proof.check(&self.share, &self.row_root)?;
+        self.root_proof.check(&self.row_root, data_root)
+    }
+}
+
+// Checks `proofs` against `data_root` from `start` on, stopping once `budget`
+// runs out. `start` is the `verified` count of the previous partial call.
+pub fn verify_batch(
+    proofs: &[ShareProof],
+    data_root: &[u8; 32],
+    budget: &Budget,
+    start: usize,
+) -> Result<Progress> {
+    run_budgeted(proofs.len(), start, budget, |i| {
+        proofs[i]
+            .check(data_root)
+            .with_context(|| format!("share proof {} does not verify", i))
+    })
+}
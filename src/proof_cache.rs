@@ -0,0 +1,138 @@
+use std::collections::{BTreeMap, HashMap};
+use std::hash::Hash;
+use std::sync::Mutex;
+
+use anyhow::Result;
+
+use crate::proof::{Axis, CellProof, RootProof, ShareProof};
+use crate::tree::ExtendedDataSquare;
+
+// Memoizes the most recently served proofs of a square. Sampling traffic
+// mostly hits a block in its first minutes, so a small cache absorbs most of
+// the repeated requests.
+pub struct ProofCache<'a> {
+    eds: &'a ExtendedDataSquare,
+    cells: Mutex<Lru<(Axis, usize, usize), CellProof>>,
+    roots: Mutex<Lru<(Axis, usize), RootProof>>,
+    shares: Mutex<Lru<(usize, usize), ShareProof>>,
+}
+
+impl<'a> ProofCache<'a> {
+    // Keeps up to `capacity` proofs of each kind
+    pub fn new(eds: &'a ExtendedDataSquare, capacity: usize) -> Self {
+        Self {
+            eds,
+            cells: Mutex::new(Lru::new(capacity)),
+            roots: Mutex::new(Lru::new(capacity)),
+            shares: Mutex::new(Lru::new(capacity)),
+        }
+    }
+
+    pub fn eds(&self) -> &'a ExtendedDataSquare {
+        self.eds
+    }
+
+    pub fn prove_cell(&self, row: usize, col: usize) -> Result<CellProof> {
+        cached(&self.cells, (Axis::Row, row, col), || {
+            self.eds.prove_cell(row, col)
+        })
+    }
+
+    pub fn prove_cell_in_col(&self, row: usize, col: usize) -> Result<CellProof> {
+        cached(&self.cells, (Axis::Col, row, col), || {
+            self.eds.prove_cell_in_col(row, col)
+        })
+    }
+
+    pub fn prove_row_root(&self, i: usize) -> Result<RootProof> {
+        cached(&self.roots, (Axis::Row, i), || self.eds.prove_row_root(i))
+    }
+
+    pub fn prove_col_root(&self, j: usize) -> Result<RootProof> {
+        cached(&self.roots, (Axis::Col, j), || self.eds.prove_col_root(j))
+    }
+
+    pub fn prove_share(&self, row: usize, col: usize) -> Result<ShareProof> {
+        cached(&self.shares, (row, col), || self.eds.prove_share(row, col))
+    }
+
+    pub fn clear(&self) {
+        lock(&self.cells).clear();
+        lock(&self.roots).clear();
+        lock(&self.shares).clear();
+    }
+}
+
+fn lock<T>(mutex: &Mutex<T>) -> std::sync::MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+// Proofs are generated outside the lock so concurrent misses don't serialize
+fn cached<K: Hash + Eq + Copy, V: Clone>(
+    lru: &Mutex<Lru<K, V>>,
+    key: K,
+    prove: impl FnOnce() -> Result<V>,
+) -> Result<V> {
+    if let Some(proof) = lock(lru).get(&key) {
+        return Ok(proof);
+    }
+    let proof = prove()?;
+    lock(lru).insert(key, proof.clone());
+    Ok(proof)
+}
+
+struct Lru<K, V> {
+    capacity: usize,
+    tick: u64,
+    entries: HashMap<K, (V, u64)>,
+    // last use -> key, oldest first
+    order: BTreeMap<u64, K>,
+}
+
+impl<K: Hash + Eq + Copy, V: Clone> Lru<K, V> {
+    fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            tick: 0,
+            entries: HashMap::new(),
+            order: BTreeMap::new(),
+        }
+    }
+
+    fn touch(&mut self, key: K) -> u64 {
+        self.tick += 1;
+        self.order.insert(self.tick, key);
+        self.tick
+    }
+
+    fn get(&mut self, key: &K) -> Option<V> {
+        let last_used = self.entries.get(key)?.1;
+        self.order.remove(&last_used);
+        let tick = self.touch(*key);
+        let entry = self.entries.get_mut(key)?;
+        entry.1 = tick;
+        Some(entry.0.clone())
+    }
+
+    fn insert(&mut self, key: K, value: V) {
+        if self.capacity == 0 {
+            return;
+        }
+        if let Some((_, last_used)) = self.entries.remove(&key) {
+            self.order.remove(&last_used);
+        }
+        while self.entries.len() >= self.capacity {
+            let Some((_, oldest)) = self.order.pop_first() else {
+                break;
+            };
+            self.entries.remove(&oldest);
+        }
+        let tick = self.touch(key);
+        self.entries.insert(key, (value, tick));
+    }
+
+    fn clear(&mut self) {
+        self.entries.clear();
+        self.order.clear();
+    }
+}
@@ -0,0 +1,680 @@
+use std::fmt;
+use std::sync::atomic::AtomicBool;
+use std::sync::Arc;
+
+use anyhow::{bail, Context as _, Result};
+use binius_field::Field;
+use rs_merkle::{algorithms::Sha256, MerkleTree};
+
+use crate::bitmap::AvailabilityBitmap;
+use crate::codecs::BINIUS_RS_128;
+use crate::encoder::{Encoder, SmallCode};
+use crate::hashers::SHA256;
+use crate::heatmap::AvailabilityHeatmap;
+use crate::params::Context;
+use crate::proof::Axis;
+use crate::tree::{
+    axis_root, check_cancel, new_encoder, scale_by_dr, ExtendedDataSquare, Felt, LeafStrategy,
+};
+use crate::wantlist::{HaveList, WantList};
+use crate::wire::{DecodeMode, Header, Reader, VERSION};
+
+// Returned (inside anyhow::Error) when an axis can't be recovered. Callers can
+// tell a lack of data apart from bad data with `err.downcast_ref::<RepairError>()`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RepairError {
+    // fewer than half of the axis' cells are known
+    NotEnoughShares {
+        axis: Axis,
+        index: usize,
+        available: usize,
+        needed: usize,
+    },
+    // the recovered axis doesn't hash to its committed root
+    CommitmentMismatch {
+        axis: Axis,
+        index: usize,
+    },
+}
+
+impl fmt::Display for RepairError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NotEnoughShares {
+                axis,
+                index,
+                available,
+                needed,
+            } => write!(
+                f,
+                "{:?} {} has {} of the {} shares needed to recover it",
+                axis, index, available, needed
+            ),
+            Self::CommitmentMismatch { axis, index } => {
+                write!(f, "recovered {:?} {} contradicts its root", axis, index)
+            }
+        }
+    }
+}
+
+impl std::error::Error for RepairError {}
+
+// An extended square with missing cells, along with the commitments it is
+// repaired against
+pub struct PartialEds {
+    // column-major, `None` for missing cells
+    cols: Vec<Vec<Option<Felt>>>,
+    dr: Vec<Felt>,
+    row_roots: Vec<[u8; 32]>,
+    col_roots: Vec<[u8; 32]>,
+    data_root: [u8; 32],
+    // what the square was extended with, dr depends on both
+    leaf_strategy: LeafStrategy,
+    context: Option<[u8; 32]>,
+}
+
+impl PartialEds {
+    // An empty square of extended `width`. The axis roots are checked against
+    // the data root up front, everything recovered is checked against them.
+    pub fn new(
+        width: usize,
+        dr: Vec<Felt>,
+        row_roots: Vec<[u8; 32]>,
+        col_roots: Vec<[u8; 32]>,
+        data_root: &[u8; 32],
+    ) -> Result<Self> {
+        if width < 2 || !width.is_power_of_two() {
+            bail!("extended width must be a power of two, got {}", width);
+        }
+        if dr.len() != width / 2 {
+            bail!("expected {} dr elements, got {}", width / 2, dr.len());
+        }
+        if row_roots.len() != width || col_roots.len() != width {
+            bail!("expected {} row and column roots", width);
+        }
+
+        let mut data_leaves = row_roots.clone();
+        data_leaves.extend_from_slice(&col_roots);
+        if MerkleTree::<Sha256>::from_leaves(&data_leaves).root() != Some(*data_root) {
+            bail!("axis roots do not match the data root");
+        }
+
+        Ok(Self {
+            cols: vec![vec![None; width]; width],
+            dr,
+            row_roots,
+            col_roots,
+            data_root: *data_root,
+            leaf_strategy: LeafStrategy::default(),
+            context: None,
+        })
+    }
+
+    // For squares extended with another leaf strategy
+    pub fn with_leaf_strategy(mut self, leaf_strategy: LeafStrategy) -> Self {
+        self.leaf_strategy = leaf_strategy;
+        self
+    }
+
+    // For squares bound to `context`, see `DataSquare::with_context`
+    pub fn with_context(mut self, context: &Context) -> Self {
+        self.context = Some(context.digest());
+        self
+    }
+
+    pub(crate) fn with_context_digest(mut self, context: Option<[u8; 32]>) -> Self {
+        self.context = context;
+        self
+    }
+
+    pub fn width(&self) -> usize {
+        self.cols.len()
+    }
+
+    pub fn get(&self, row: usize, col: usize) -> Option<Felt> {
+        self.cols.get(col)?.get(row).copied().flatten()
+    }
+
+    pub fn set(&mut self, row: usize, col: usize, cell: Felt) -> Result<()> {
+        let width = self.width();
+        if row >= width || col >= width {
+            bail!("cell ({}, {}) out of range for width {}", row, col, width);
+        }
+        self.cols[col][row] = Some(cell);
+        Ok(())
+    }
+
+    pub fn missing(&self) -> usize {
+        self.cols
+            .iter()
+            .flatten()
+            .filter(|cell| cell.is_none())
+            .count()
+    }
+
+    pub fn is_complete(&self) -> bool {
+        self.missing() == 0
+    }
+
+    pub fn data_root(&self) -> &[u8; 32] {
+        &self.data_root
+    }
+
+    // Which cells are present
+    pub fn availability_bitmap(&self) -> AvailabilityBitmap {
+        let mut bitmap = AvailabilityBitmap::new(self.width());
+        for (col, cells) in self.cols.iter().enumerate() {
+            for (row, cell) in cells.iter().enumerate() {
+                bitmap.set(row, col, cell.is_some());
+            }
+        }
+        bitmap
+    }
+
+    // Present cells and decodability per row and column, for monitoring
+    pub fn heatmap(&self) -> AvailabilityHeatmap {
+        AvailabilityHeatmap::from_bitmap(self.data_root, &self.availability_bitmap())
+    }
+
+    // The cells held, to advertise to peers
+    pub fn have_list(&self) -> HaveList {
+        HaveList::new(self.data_root, self.availability_bitmap())
+    }
+
+    // The cells still missing, to request from peers
+    pub fn want_list(&self) -> WantList {
+        WantList::new(self.data_root, self.availability_bitmap().complement())
+    }
+
+    // Serialized partial square:
+    //   header | dr (width / 2 felts) | row roots | column roots | data root
+    //   | availability bitmap | present cells (in bitmap order, row-major)
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let width = self.width();
+        let header = Header {
+            version: VERSION,
+            codec_id: BINIUS_RS_128,
+            hasher_id: SHA256,
+            width,
+            leaf_strategy: self.leaf_strategy,
+            context: self.context,
+        };
+        let bitmap = self.availability_bitmap();
+        let mut out = Vec::new();
+
+        header.write(&mut out);
+        for elem in &self.dr {
+            out.extend_from_slice(&elem.val().to_be_bytes());
+        }
+        for root in self.row_roots.iter().chain(&self.col_roots) {
+            out.extend_from_slice(root);
+        }
+        out.extend_from_slice(&self.data_root);
+        out.extend_from_slice(bitmap.as_bytes());
+        for (row, col) in bitmap.iter() {
+            let elem = self.cols[col][row].expect("present in the bitmap");
+            out.extend_from_slice(&elem.val().to_be_bytes());
+        }
+
+        out
+    }
+
+    // Checks the roots against the data root, cells are checked once axes
+    // are recovered
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        Ok(Self::from_bytes_with_mode(bytes, DecodeMode::Strict)?.0)
+    }
+
+    // Also returns the warnings for whatever a lenient decode skipped
+    pub fn from_bytes_with_mode(bytes: &[u8], mode: DecodeMode) -> Result<(Self, Vec<String>)> {
+        let mut reader = Reader::with_mode(bytes, mode);
+        let header = Header::read(&mut reader)?;
+        let width = header.width;
+        if width < 2 || !width.is_power_of_two() {
+            bail!("extended width must be a power of two, got {}", width);
+        }
+
+        let dr = reader.felts(width / 2)?;
+        let mut roots = || -> Result<Vec<[u8; 32]>> {
+            (0..width)
+                .map(|_| Ok(reader.take(32)?.try_into()?))
+                .collect()
+        };
+        let (row_roots, col_roots) = (roots()?, roots()?);
+        let data_root: [u8; 32] = reader.take(32)?.try_into()?;
+        let mut partial = Self::new(width, dr, row_roots, col_roots, &data_root)?
+            .with_leaf_strategy(header.leaf_strategy)
+            .with_context_digest(header.context);
+
+        let bitmap_len = AvailabilityBitmap::new(width).as_bytes().len();
+        let bitmap = AvailabilityBitmap::from_bytes(width, reader.take(bitmap_len)?)?;
+        for (row, col) in bitmap.iter() {
+            partial.cols[col][row] = Some(reader.felt()?);
+        }
+        reader.finish()?;
+
+        Ok((partial, reader.into_warnings()))
+    }
+
+    fn axis(&self, axis: Axis, index: usize) -> Vec<Option<Felt>> {
+        match axis {
+            Axis::Row => self.cols.iter().map(|col| col[index]).collect(),
+            Axis::Col => self.cols[index].clone(),
+        }
+    }
+
+    // Recovers row `i` and checks it against its row root
+    pub fn decode_row(&self, i: usize) -> Result<Vec<Felt>> {
+        self.decode_axis(&AxisDecoder::new(self.width() / 2)?, Axis::Row, i)
+    }
+
+    // Recovers column `j` and checks it against its column root
+    pub fn decode_col(&self, j: usize) -> Result<Vec<Felt>> {
+        self.decode_axis(&AxisDecoder::new(self.width() / 2)?, Axis::Col, j)
+    }
+
+    fn decode_axis(&self, decoder: &AxisDecoder, axis: Axis, index: usize) -> Result<Vec<Felt>> {
+        let width = self.width();
+        if index >= width {
+            bail!("index {} out of range for width {}", index, width);
+        }
+
+        let known = self.axis(axis, index);
+        let scale = match axis {
+            Axis::Row => Some(self.dr.as_slice()),
+            Axis::Col => None,
+        };
+        let Some(decoded) = decoder.decode(&known, scale)? else {
+            return Err(RepairError::NotEnoughShares {
+                axis,
+                index,
+                available: known.iter().flatten().count(),
+                needed: width / 2,
+            }
+            .into());
+        };
+
+        let root = match axis {
+            Axis::Row => self.row_roots[index],
+            Axis::Col => self.col_roots[index],
+        };
+        if axis_root(&decoded) != root {
+            return Err(RepairError::CommitmentMismatch { axis, index }.into());
+        }
+
+        Ok(decoded)
+    }
+
+    // Alternates between recovering rows and columns until the square is
+    // complete. Fails with `NotEnoughShares` once no axis can make progress.
+    pub fn repair(self) -> Result<ExtendedDataSquare> {
+        self.repair_cancelable(&AtomicBool::new(false))
+    }
+
+    // Like `repair`, but returns `Cancelled` soon after `cancel` is set
+    pub fn repair_cancelable(self, cancel: &AtomicBool) -> Result<ExtendedDataSquare> {
+        self.repair_along(&[Axis::Row, Axis::Col], cancel)
+    }
+
+    // Repairs by decoding along `axis` only, in a single pass. Every such axis
+    // needs half of its cells, e.g. rows can be recovered from any half of the
+    // columns, which is all a node in a column-sampled network has.
+    pub fn repair_single_axis(self, axis: Axis) -> Result<ExtendedDataSquare> {
+        self.repair_along(&[axis], &AtomicBool::new(false))
+    }
+
+    fn repair_along(mut self, axes: &[Axis], cancel: &AtomicBool) -> Result<ExtendedDataSquare> {
+        let width = self.width();
+        let decoder = AxisDecoder::new(width / 2)?;
+
+        while !self.is_complete() {
+            let mut progress = false;
+            for &axis in axes {
+                for index in 0..width {
+                    check_cancel(cancel)?;
+                    let known = self.axis(axis, index);
+                    let available = known.iter().flatten().count();
+                    if available == width || available < width / 2 {
+                        continue;
+                    }
+
+                    let decoded = self.decode_axis(&decoder, axis, index)?;
+                    self.fill(axis, index, decoded);
+                    progress = true;
+                }
+            }
+
+            if !progress {
+                return Err(self.stuck(axes));
+            }
+        }
+
+        let cols = self
+            .cols
+            .into_iter()
+            .map(|col| col.into_iter().flatten().collect())
+            .collect();
+        let eds = ExtendedDataSquare::from_extended_cols_with(cols, self.dr, self.leaf_strategy)?
+            .with_context_digest(self.context);
+        // every axis matches its root, but dr may not be the one the
+        // recovered data derives
+        eds.validate()
+            .context("recovered data contradicts its commitment")?;
+        if eds.data_root()? != self.data_root {
+            bail!("repaired square does not match the data root");
+        }
+
+        Ok(eds)
+    }
+
+    fn fill(&mut self, axis: Axis, index: usize, cells: Vec<Felt>) {
+        for (k, cell) in cells.into_iter().enumerate() {
+            match axis {
+                Axis::Row => self.cols[k][index] = Some(cell),
+                Axis::Col => self.cols[index][k] = Some(cell),
+            }
+        }
+    }
+
+    // Absorbs the cells of `other`, a partial copy of the same square held by
+    // another node. Its cells come without proofs, so each is checked against
+    // an axis root: an axis is decoded from our cells if there are enough,
+    // from both copies otherwise, and taken if it hashes to its root. Cells
+    // of `other` contradicting it are rejected, those on axes that can't be
+    // checked are left out. Returns the (row, col) of every cell learned.
+    pub fn merge(&mut self, other: &PartialEds) -> Result<Vec<(usize, usize)>> {
+        if other.width() != self.width()
+            || other.leaf_strategy != self.leaf_strategy
+            || other.context != self.context
+            || other.data_root != self.data_root
+            || other.dr != self.dr
+            || other.row_roots != self.row_roots
+            || other.col_roots != self.col_roots
+        {
+            bail!("partial squares commit to different data");
+        }
+        let width = self.width();
+        let decoder = AxisDecoder::new(width / 2)?;
+
+        // cells of `other` we don't have, column-major
+        let mut pending: Vec<Vec<Option<Felt>>> = other
+            .cols
+            .iter()
+            .zip(&self.cols)
+            .map(|(theirs, ours)| {
+                theirs
+                    .iter()
+                    .zip(ours)
+                    .map(|(theirs, ours)| theirs.filter(|_| ours.is_none()))
+                    .collect()
+            })
+            .collect();
+
+        let mut learned = Vec::new();
+        let mut progress = true;
+        while progress {
+            progress = false;
+            for axis in [Axis::Row, Axis::Col] {
+                for index in 0..width {
+                    let theirs = match axis {
+                        Axis::Row => pending.iter().map(|col| col[index]).collect(),
+                        Axis::Col => pending[index].clone(),
+                    };
+                    if theirs.iter().all(Option::is_none) {
+                        continue;
+                    }
+
+                    let ours = self.axis(axis, index);
+                    let known = if ours.iter().flatten().count() >= width / 2 {
+                        ours
+                    } else {
+                        ours.iter().zip(&theirs).map(|(a, b)| a.or(*b)).collect()
+                    };
+                    let scale = match axis {
+                        Axis::Row => Some(self.dr.as_slice()),
+                        Axis::Col => None,
+                    };
+                    let root = match axis {
+                        Axis::Row => self.row_roots[index],
+                        Axis::Col => self.col_roots[index],
+                    };
+                    let Some(decoded) = decoder.decode(&known, scale)? else {
+                        continue;
+                    };
+                    if axis_root(&decoded) != root {
+                        continue;
+                    }
+
+                    for (k, cell) in theirs.into_iter().enumerate() {
+                        let Some(cell) = cell else {
+                            continue;
+                        };
+                        let (row, col) = match axis {
+                            Axis::Row => (index, k),
+                            Axis::Col => (k, index),
+                        };
+                        pending[col][row] = None;
+                        if cell == decoded[k] {
+                            self.cols[col][row] = Some(cell);
+                            learned.push((row, col));
+                        }
+                    }
+                    progress = true;
+                }
+            }
+        }
+
+        Ok(learned)
+    }
+
+    // Sets a whole row, checked against its root
+    pub fn set_row(&mut self, i: usize, row: Vec<Felt>) -> Result<()> {
+        self.set_axis(Axis::Row, i, row)
+    }
+
+    // Sets a whole column, checked against its root
+    pub fn set_col(&mut self, j: usize, col: Vec<Felt>) -> Result<()> {
+        self.set_axis(Axis::Col, j, col)
+    }
+
+    fn set_axis(&mut self, axis: Axis, index: usize, cells: Vec<Felt>) -> Result<()> {
+        let width = self.width();
+        if index >= width || cells.len() != width {
+            bail!(
+                "{:?} {} does not fit a square of width {}",
+                axis,
+                index,
+                width
+            );
+        }
+
+        let root = match axis {
+            Axis::Row => self.row_roots[index],
+            Axis::Col => self.col_roots[index],
+        };
+        if axis_root(&cells) != root {
+            return Err(RepairError::CommitmentMismatch { axis, index }.into());
+        }
+
+        self.fill(axis, index, cells);
+        Ok(())
+    }
+
+    // The incomplete axis closest to being recoverable
+    fn stuck(&self, axes: &[Axis]) -> anyhow::Error {
+        let width = self.width();
+        let (axis, index, available) = axes
+            .iter()
+            .flat_map(|&axis| (0..width).map(move |index| (axis, index)))
+            .map(|(axis, index)| {
+                let available = self.axis(axis, index).iter().flatten().count();
+                (axis, index, available)
+            })
+            .filter(|(_, _, available)| *available < width)
+            .max_by_key(|(_, _, available)| *available)
+            .unwrap_or((Axis::Row, 0, width));
+
+        RepairError::NotEnoughShares {
+            axis,
+            index,
+            available,
+            needed: width / 2,
+        }
+        .into()
+    }
+}
+
+// Erasure decoder for one axis. The code is linear, so every cell is a linear
+// function of the axis' first half; any `half` known cells pin it down.
+struct AxisDecoder {
+    encoder: Encoder,
+    code: Arc<SmallCode>,
+}
+
+impl AxisDecoder {
+    // Small widths share the encoder's cached matrix
+    fn new(half: usize) -> Result<Self> {
+        let encoder = new_encoder(half)?;
+        let code = match &encoder {
+            Encoder::Small(code) => code.clone(),
+            Encoder::Ntt(_) => Arc::new(SmallCode::new(half)?),
+        };
+        Ok(Self { encoder, code })
+    }
+
+    // Rows encode their dr-scaled first half, pass dr as `scale` for them.
+    // Returns `None` if fewer than half of the cells are known.
+    fn decode(&self, known: &[Option<Felt>], scale: Option<&[Felt]>) -> Result<Option<Vec<Felt>>> {
+        let parity = self.code.parity();
+        let half = parity.len();
+        let weight = |p: usize, c: usize| match (p < half, scale) {
+            (true, _) => {
+                if p == c {
+                    Felt::ONE
+                } else {
+                    Felt::default()
+                }
+            }
+            (false, Some(dr)) => parity[p - half][c] * dr[c],
+            (false, None) => parity[p - half][c],
+        };
+
+        // augmented system over the first `half` known cells
+        let mut system: Vec<Vec<Felt>> = known
+            .iter()
+            .enumerate()
+            .filter_map(|(p, cell)| cell.map(|cell| (p, cell)))
+            .take(half)
+            .map(|(p, cell)| {
+                let mut eq: Vec<Felt> = (0..half).map(|c| weight(p, c)).collect();
+                eq.push(cell);
+                eq
+            })
+            .collect();
+        if system.len() < half {
+            return Ok(None);
+        }
+
+        let message = solve(&mut system)?;
+        let scaled = match scale {
+            Some(dr) => scale_by_dr(&message, dr),
+            None => message.clone(),
+        };
+        let mut axis = message;
+        axis.extend(self.encoder.encode(scaled)?);
+
+        Ok(Some(axis))
+    }
+}
+
+// Gaussian elimination on an n x (n + 1) augmented matrix
+fn solve(system: &mut [Vec<Felt>]) -> Result<Vec<Felt>> {
+    let n = system.len();
+    for c in 0..n {
+        let Some(pivot) = (c..n).find(|&r| system[r][c] != Felt::default()) else {
+            bail!("singular system while decoding");
+        };
+        system.swap(c, pivot);
+
+        let Some(inv) = system[c][c].invert() else {
+            bail!("singular system while decoding");
+        };
+        system[c].iter_mut().for_each(|elem| *elem *= inv);
+
+        let pivot_row = system[c].clone();
+        for (r, eq) in system.iter_mut().enumerate() {
+            let factor = eq[c];
+            if r == c || factor == Felt::default() {
+                continue;
+            }
+            for (elem, pivot) in eq.iter_mut().zip(&pivot_row).skip(c) {
+                *elem -= factor * *pivot;
+            }
+        }
+    }
+
+    Ok(system.iter().map(|eq| eq[n]).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tree::DataSquare;
+
+    fn context() -> Context {
+        Context {
+            chain_id: "zoda-test".into(),
+            height: 7,
+            block_hash: [3; 32],
+        }
+    }
+
+    // q4 of `eds`, enough to repair it
+    fn partial(eds: &ExtendedDataSquare) -> PartialEds {
+        let width = eds.width();
+        let mut partial = PartialEds::new(
+            width,
+            eds.dr().to_vec(),
+            eds.row_roots().to_vec(),
+            eds.col_roots().to_vec(),
+            &eds.data_root().unwrap(),
+        )
+        .unwrap();
+        for row in width / 2..width {
+            for col in width / 2..width {
+                partial.set(row, col, eds.cell(row, col)).unwrap();
+            }
+        }
+        partial
+    }
+
+    #[test]
+    fn repair_keeps_leaf_strategy_and_context() {
+        let cols = (0..4)
+            .map(|c| (0..4).map(|r| Felt::new(c * 4 + r + 1)).collect())
+            .collect();
+        let eds = DataSquare::new(cols)
+            .unwrap()
+            .with_leaf_strategy(LeafStrategy::PerShare)
+            .with_context(&context())
+            .extend()
+            .unwrap();
+
+        let bound = partial(&eds)
+            .with_leaf_strategy(LeafStrategy::PerShare)
+            .with_context(&context());
+        let decoded = PartialEds::from_bytes(&bound.to_bytes()).unwrap();
+        let repaired = decoded.repair().unwrap();
+        assert_eq!(repaired.cols(), eds.cols());
+        assert_eq!(repaired.leaf_strategy(), LeafStrategy::PerShare);
+        assert_eq!(repaired.context(), eds.context());
+
+        // the axes check out, dr doesn't
+        assert!(partial(&eds).repair().is_err());
+        assert!(partial(&eds)
+            .with_leaf_strategy(LeafStrategy::PerShare)
+            .repair()
+            .is_err());
+        assert!(partial(&eds).with_context(&context()).repair().is_err());
+    }
+}
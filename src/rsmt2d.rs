@@ -0,0 +1,279 @@
+// Loader for the JSON squares Go rsmt2d marshals, `{"data_square": [...],
+// "codec": "..."}` with the extended square's shares row-major in base64.
+// rsmt2d extends 512 byte shares with another code and commits with
+// namespaced trees, so its roots have no counterpart here. What does carry
+// over is the share layout: original shares in the first quadrant, parity
+// everywhere else, and where each namespace sits. `compare` checks that a
+// square built here with one share per rsmt2d share lays them out the same.
+use anyhow::{anyhow, bail, Result};
+
+use crate::namespace::{Namespace, NAMESPACE_SIZE};
+use crate::tree::ExtendedDataSquare;
+
+// Celestia share namespaces: a version byte, then a 28 byte id
+pub const RSMT2D_NAMESPACE_SIZE: usize = 29;
+// version of the reserved namespaces for tail padding and parity
+const RESERVED_VERSION: u8 = 0xff;
+// namespace of every share outside the first quadrant
+const PARITY_NAMESPACE: [u8; RSMT2D_NAMESPACE_SIZE] = [0xff; RSMT2D_NAMESPACE_SIZE];
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Rsmt2dFixture {
+    // width of the extended square
+    pub width: usize,
+    pub codec: String,
+    // row-major
+    pub shares: Vec<Vec<u8>>,
+}
+
+// An original cell whose namespace differs between the fixture and a square
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LayoutMismatch {
+    pub row: usize,
+    pub col: usize,
+    pub fixture: Namespace,
+    pub square: Namespace,
+}
+
+impl Rsmt2dFixture {
+    // Also checks the fixture's layout: shares of one size, each with a
+    // namespace, and only parity outside the first quadrant
+    pub fn from_json(json: &str) -> Result<Self> {
+        let value: serde_json::Value = serde_json::from_str(json)?;
+        let Some(data_square) = value["data_square"].as_array() else {
+            bail!("fixture has no data_square array");
+        };
+        let codec = value["codec"].as_str().unwrap_or_default().to_string();
+        let shares = data_square
+            .iter()
+            .map(|share| {
+                let share = share
+                    .as_str()
+                    .ok_or_else(|| anyhow!("share is not a base64 string"))?;
+                decode_base64(share)
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        let width = shares.len().isqrt();
+        if width * width != shares.len() || !width.is_power_of_two() || width < 2 {
+            bail!("{} shares don't form an extended square", shares.len());
+        }
+        let share_size = shares[0].len();
+        if share_size < RSMT2D_NAMESPACE_SIZE {
+            bail!("share of {} bytes has no namespace", share_size);
+        }
+        if let Some(share) = shares.iter().find(|share| share.len() != share_size) {
+            bail!("share of {} bytes, expected {}", share.len(), share_size);
+        }
+
+        let half = width / 2;
+        for (i, share) in shares.iter().enumerate() {
+            let (row, col) = (i / width, i % width);
+            if (row >= half || col >= half) && share[..RSMT2D_NAMESPACE_SIZE] != PARITY_NAMESPACE {
+                bail!("share ({}, {}) is outside q1 but not parity", row, col);
+            }
+        }
+
+        Ok(Self {
+            width,
+            codec,
+            shares,
+        })
+    }
+
+    // Namespaces of the original shares, row-major
+    pub fn namespaces(&self) -> Result<Vec<Namespace>> {
+        let half = self.width / 2;
+        (0..half)
+            .flat_map(|row| (0..half).map(move |col| row * self.width + col))
+            .map(|i| namespace_of(&self.shares[i]))
+            .collect()
+    }
+
+    // Original cells of `eds` whose namespace isn't the fixture's
+    pub fn compare(&self, eds: &ExtendedDataSquare) -> Result<Vec<LayoutMismatch>> {
+        if eds.width() != self.width {
+            bail!(
+                "square of width {}, the fixture's is {}",
+                eds.width(),
+                self.width
+            );
+        }
+        Ok(self
+            .namespaces()?
+            .into_iter()
+            .zip(eds.namespaced_shares())
+            .filter(|(fixture, (square, ..))| fixture != square)
+            .map(|(fixture, (square, row, col, _))| LayoutMismatch {
+                row,
+                col,
+                fixture,
+                square,
+            })
+            .collect())
+    }
+}
+
+// Maps a Celestia namespace onto this crate's: version 0 ids keep their last
+// `NAMESPACE_SIZE` bytes, which needs the rest to be zero, and the reserved
+// namespaces become `Namespace::TAIL_PADDING`
+fn namespace_of(share: &[u8]) -> Result<Namespace> {
+    let (version, id) = (share[0], &share[1..RSMT2D_NAMESPACE_SIZE]);
+    match version {
+        0 => {
+            let (high, low) = id.split_at(id.len() - NAMESPACE_SIZE);
+            if high.iter().any(|&b| b != 0) {
+                bail!("namespace id doesn't fit in {} bytes", NAMESPACE_SIZE);
+            }
+            Ok(Namespace(low.try_into()?))
+        }
+        RESERVED_VERSION => Ok(Namespace::TAIL_PADDING),
+        _ => bail!("unsupported namespace version {}", version),
+    }
+}
+
+// Standard alphabet with padding, as Go's encoding/json writes []byte
+fn decode_base64(s: &str) -> Result<Vec<u8>> {
+    if !s.len().is_multiple_of(4) {
+        bail!("base64 of {} bytes isn't padded", s.len());
+    }
+    let data = s.trim_end_matches('=');
+    if s.len() - data.len() > 2 {
+        bail!("too much base64 padding");
+    }
+
+    let mut out = Vec::with_capacity(data.len() * 3 / 4);
+    let (mut acc, mut bits) = (0u32, 0);
+    for c in data.bytes() {
+        let v = match c {
+            b'A'..=b'Z' => c - b'A',
+            b'a'..=b'z' => c - b'a' + 26,
+            b'0'..=b'9' => c - b'0' + 52,
+            b'+' => 62,
+            b'/' => 63,
+            _ => bail!("invalid base64 byte {:#04x}", c),
+        };
+        acc = (acc << 6 | v as u32) & 0xfff;
+        bits += 6;
+        if bits >= 8 {
+            bits -= 8;
+            out.push((acc >> bits) as u8);
+        }
+    }
+    // the bits left over only pad the last byte
+    if acc & ((1 << bits) - 1) != 0 {
+        bail!("non-canonical base64 padding bits");
+    }
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::namespace::namespaced_share;
+    use crate::tree::{DataSquare, Felt};
+
+    const ALPHABET: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+    fn encode_base64(bytes: &[u8]) -> String {
+        let mut out = String::new();
+        for chunk in bytes.chunks(3) {
+            let n = chunk
+                .iter()
+                .enumerate()
+                .fold(0u32, |acc, (i, &b)| acc | (b as u32) << (16 - 8 * i));
+            for i in 0..4 {
+                match i <= chunk.len() {
+                    true => out.push(ALPHABET[(n >> (18 - 6 * i) & 63) as usize] as char),
+                    false => out.push('='),
+                }
+            }
+        }
+        out
+    }
+
+    // a 512 byte share as rsmt2d squares hold them, namespace first
+    fn share(namespace: [u8; RSMT2D_NAMESPACE_SIZE]) -> String {
+        let mut share = vec![0x5a; 512];
+        share[..RSMT2D_NAMESPACE_SIZE].copy_from_slice(&namespace);
+        format!("\"{}\"", encode_base64(&share))
+    }
+
+    fn user_namespace(id: u64) -> [u8; RSMT2D_NAMESPACE_SIZE] {
+        let mut namespace = [0; RSMT2D_NAMESPACE_SIZE];
+        namespace[RSMT2D_NAMESPACE_SIZE - 8..].copy_from_slice(&id.to_be_bytes());
+        namespace
+    }
+
+    fn square(namespaces: [u64; 4]) -> ExtendedDataSquare {
+        let share = |i: usize| namespaced_share(Namespace::from_u64(namespaces[i]), [0; 8]);
+        // column-major
+        let cols = vec![vec![share(0), share(2)], vec![share(1), share(3)]];
+        DataSquare::new(cols).unwrap().extend().unwrap()
+    }
+
+    #[test]
+    fn base64_matches_rfc4648() {
+        let vectors = [
+            ("", ""),
+            ("f", "Zg=="),
+            ("fo", "Zm8="),
+            ("foo", "Zm9v"),
+            ("foob", "Zm9vYg=="),
+            ("fooba", "Zm9vYmE="),
+            ("foobar", "Zm9vYmFy"),
+        ];
+        for (plain, encoded) in vectors {
+            assert_eq!(decode_base64(encoded).unwrap(), plain.as_bytes());
+            assert_eq!(encode_base64(plain.as_bytes()), encoded);
+        }
+        let bytes: Vec<u8> = (0..=255).collect();
+        assert_eq!(decode_base64(&encode_base64(&bytes)).unwrap(), bytes);
+
+        for bad in ["Zg", "Zg=", "Z===", "Zh==", "Zm9v!A==", "===="] {
+            assert!(decode_base64(bad).is_err(), "{}", bad);
+        }
+    }
+
+    #[test]
+    fn compares_fixture_layout() {
+        // 2x2 original square with namespaces 1, 2 / 3, tail padding, the
+        // rest parity, as rsmt2d marshals it
+        let mut tail = [0xff; RSMT2D_NAMESPACE_SIZE];
+        tail[RSMT2D_NAMESPACE_SIZE - 1] = 0xfe;
+        let mut shares = vec![share(PARITY_NAMESPACE); 16];
+        shares[0] = share(user_namespace(1));
+        shares[1] = share(user_namespace(2));
+        shares[4] = share(user_namespace(3));
+        shares[5] = share(tail);
+        let json = format!(
+            "{{\"data_square\": [{}], \"codec\": \"Leopard\"}}",
+            shares.join(", ")
+        );
+
+        let fixture = Rsmt2dFixture::from_json(&json).unwrap();
+        assert_eq!((fixture.width, fixture.codec.as_str()), (4, "Leopard"));
+        assert!(fixture.shares.iter().all(|share| share.len() == 512));
+        assert_eq!(
+            fixture.namespaces().unwrap(),
+            [1, 2, 3, u64::MAX].map(Namespace::from_u64)
+        );
+
+        assert!(fixture
+            .compare(&square([1, 2, 3, u64::MAX]))
+            .unwrap()
+            .is_empty());
+        let mismatches = fixture.compare(&square([2, 1, 3, u64::MAX])).unwrap();
+        assert_eq!(mismatches.len(), 2);
+        assert_eq!((mismatches[0].row, mismatches[0].col), (0, 0));
+        assert_eq!(mismatches[0].fixture, Namespace::from_u64(1));
+        let mut wider = DataSquare::new(vec![vec![Felt::default(); 4]; 4]).unwrap();
+        assert!(fixture.compare(&wider.extend().unwrap()).is_err());
+
+        // user data in a parity quadrant
+        shares[2] = share(user_namespace(4));
+        let json = format!("{{\"data_square\": [{}]}}", shares.join(", "));
+        assert!(Rsmt2dFixture::from_json(&json).is_err());
+        assert!(Rsmt2dFixture::from_json("{\"data_square\": [\"AA==\"]}").is_err());
+    }
+}
@@ -0,0 +1,729 @@
+use std::collections::HashSet;
+use std::fmt;
+use std::time::{Duration, Instant};
+
+use anyhow::{bail, Result};
+use sha2::{Digest, Sha256};
+
+use crate::decode::{skip_trailing, DecodeMode};
+use crate::proof::{Rejection, ShareProof};
+use crate::signature::{Signature, Signer};
+
+pub type PeerId = String;
+
+// How the cells to query are spread over the extended square. Which one fits
+// depends on the threat model: uniform sampling is the textbook choice, the
+// others bound how much of a single row, quadrant or column can be withheld
+// unnoticed.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SamplingStrategy {
+    // distinct cells anywhere in the square
+    #[default]
+    Uniform,
+    // at most one cell per row, in distinct rows
+    OnePerRow,
+    // the same number of cells (give or take one) from each quadrant
+    StratifiedByQuadrant,
+    // whole columns: `samples` distinct columns, every cell of each
+    ColumnsOnly,
+}
+
+impl SamplingStrategy {
+    pub fn id(&self) -> u8 {
+        match self {
+            Self::Uniform => 0,
+            Self::OnePerRow => 1,
+            Self::StratifiedByQuadrant => 2,
+            Self::ColumnsOnly => 3,
+        }
+    }
+
+    pub fn from_id(id: u8) -> Result<Self> {
+        match id {
+            0 => Ok(Self::Uniform),
+            1 => Ok(Self::OnePerRow),
+            2 => Ok(Self::StratifiedByQuadrant),
+            3 => Ok(Self::ColumnsOnly),
+            _ => bail!("unknown sampling strategy {}", id),
+        }
+    }
+}
+
+// Serves shares to the sampler, typically a connection to a peer
+pub trait ShareProvider {
+    fn peer_id(&self) -> PeerId;
+    // Providers enforcing a deadline should fail with `Timeout` once it passes
+    fn fetch(&mut self, request: &ShareRequest) -> Result<ShareResponse>;
+    // The peer's public key, if it's known. Responses from a peer with a key
+    // have to be signed with it, see `ShareResponse::check_signed`.
+    fn public_key(&self) -> Option<Vec<u8>> {
+        None
+    }
+}
+
+// A query for one share, tagged with the sampling session's nonce
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ShareRequest {
+    pub row: usize,
+    pub col: usize,
+    pub nonce: [u8; 32],
+}
+
+// A share proof answering a `ShareRequest`. The nonce has to be echoed back, so
+// responses recorded in an earlier session are rejected. An echoed nonce alone
+// doesn't stop a gateway from answering a fresh request from its cache, proofs
+// are the same every time; a signature of the serving peer over the nonce and
+// the share does, see `check_signed`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ShareResponse {
+    pub nonce: [u8; 32],
+    pub proof: ShareProof,
+    pub signature: Option<Signature>,
+}
+
+impl ShareResponse {
+    pub fn new(nonce: [u8; 32], proof: ShareProof) -> Self {
+        Self {
+            nonce,
+            proof,
+            signature: None,
+        }
+    }
+
+    // Signs `digest` with the serving peer's key
+    pub fn sign(mut self, signer: &(impl Signer + ?Sized)) -> Result<Self> {
+        self.signature = Some(signer.sign(&self.digest())?);
+        Ok(self)
+    }
+
+    // What the serving peer signs: the nonce, the cell and its share,
+    // domain separated from the other signed messages
+    pub fn digest(&self) -> [u8; 32] {
+        let mut hasher = Sha256::new();
+        hasher.update(b"zoda-rs/share-response/v1");
+        hasher.update(self.nonce);
+        hasher.update((self.proof.row() as u64).to_be_bytes());
+        hasher.update((self.proof.col() as u64).to_be_bytes());
+        hasher.update(self.proof.share.val().to_be_bytes());
+        hasher.finalize().into()
+    }
+
+    pub fn verify(&self, request: &ShareRequest, width: usize, data_root: &[u8; 32]) -> bool {
+        self.check(request, width, data_root).is_ok()
+    }
+
+    pub fn check(
+        &self,
+        request: &ShareRequest,
+        width: usize,
+        data_root: &[u8; 32],
+    ) -> Result<(), Rejection> {
+        if self.nonce != request.nonce {
+            return Err(Rejection::NonceMismatch);
+        }
+        if self.proof.row() != request.row
+            || self.proof.col() != request.col
+            || self.proof.cell_proof.width != width
+        {
+            return Err(Rejection::WrongPosition);
+        }
+        if let Some(signature) = &self.signature {
+            signature
+                .verify(&self.digest())
+                .map_err(|_| Rejection::BadSignature)?;
+        }
+        self.proof.check(data_root)
+    }
+
+    // Like `check`, and the response has to be signed by `public_key`
+    pub fn check_signed(
+        &self,
+        request: &ShareRequest,
+        width: usize,
+        data_root: &[u8; 32],
+        public_key: &[u8],
+    ) -> Result<(), Rejection> {
+        match &self.signature {
+            None => return Err(Rejection::Unsigned),
+            Some(signature) if signature.public_key != public_key => {
+                return Err(Rejection::BadSignature)
+            }
+            Some(_) => {}
+        }
+        self.check(request, width, data_root)
+    }
+}
+
+// Returned (inside anyhow::Error) by a `ShareProvider` whose peer didn't
+// answer in time, reported to the `PeerScorer` as `SampleResult::Timeout`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Timeout;
+
+impl fmt::Display for Timeout {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "peer timed out")
+    }
+}
+
+impl std::error::Error for Timeout {}
+
+// Receives the outcome of every query as it happens, e.g. to feed a peer
+// reputation system
+pub trait PeerScorer {
+    fn report(&mut self, peer: &PeerId, result: &SampleResult);
+}
+
+impl<F: FnMut(&PeerId, &SampleResult)> PeerScorer for F {
+    fn report(&mut self, peer: &PeerId, result: &SampleResult) {
+        self(peer, result)
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SamplingReport {
+    // (row, col) of every share that verified
+    pub verified: Vec<(usize, usize)>,
+    // (row, col) of every share that couldn't be fetched or didn't verify
+    pub failed: Vec<(usize, usize)>,
+}
+
+impl SamplingReport {
+    pub fn is_available(&self) -> bool {
+        self.failed.is_empty()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SampleResult {
+    Valid,
+    // a response was returned, but for another session or cell, or not under
+    // the data root
+    InvalidProof(Rejection),
+    // the provider failed with `Timeout`
+    Timeout,
+    // the provider returned any other error
+    FetchFailed(String),
+}
+
+// One query of a sampling session
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AuditEntry {
+    pub row: usize,
+    pub col: usize,
+    pub peer: PeerId,
+    pub result: SampleResult,
+    // time spent in `ShareProvider::fetch`
+    pub latency: Duration,
+    // see `ShareProof::byte_len`, `None` if nothing was returned
+    pub proof_size: Option<usize>,
+}
+
+// Picks cells of a square from a seed and checks them against its data root
+pub struct Sampler {
+    data_root: [u8; 32],
+    // width of the extended square
+    width: usize,
+    samples: usize,
+    strategy: SamplingStrategy,
+    seed: [u8; 32],
+    // number of `sample` calls so far, each one is a session with its own nonce
+    session: u64,
+    // only kept once enabled with `with_audit_log`
+    audit_log: Option<Vec<AuditEntry>>,
+    scorer: Option<Box<dyn PeerScorer>>,
+    // cells flagged by fraud reports or heatmaps, see `with_hotspots`
+    hotspots: Vec<(usize, usize)>,
+    hotspot_fraction: f64,
+    // cells verified in any session, they aren't queried again
+    verified: HashSet<(usize, usize)>,
+}
+
+impl Sampler {
+    // `seed` has to be unpredictable to the peers being sampled
+    pub fn new(data_root: [u8; 32], width: usize, samples: usize, seed: [u8; 32]) -> Result<Self> {
+        if width < 2 || !width.is_power_of_two() {
+            bail!("extended width must be a power of two, got {}", width);
+        }
+
+        Ok(Self {
+            data_root,
+            width,
+            samples,
+            strategy: SamplingStrategy::default(),
+            seed,
+            session: 0,
+            audit_log: None,
+            scorer: None,
+            hotspots: Vec::new(),
+            hotspot_fraction: 0.0,
+            verified: HashSet::new(),
+        })
+    }
+
+    // Picks up where `state` left off, e.g. after the process was killed:
+    // the same cells are picked, those verified before are not queried again
+    // and session nonces aren't reused. Fails if the state's confidence isn't
+    // what its cells give.
+    pub fn resume(state: &SamplerState) -> Result<Self> {
+        let mut sampler = Self::new(state.data_root, state.width, state.samples, state.seed)?
+            .with_strategy(state.strategy);
+        let width = sampler.width;
+        if let Some((row, col)) = state
+            .verified
+            .iter()
+            .find(|&&(row, col)| row >= width || col >= width)
+        {
+            bail!("cell ({}, {}) out of range for width {}", row, col, width);
+        }
+        sampler.session = state.session;
+        sampler.verified = state.verified.iter().copied().collect();
+
+        let confidence = sampler.achieved_confidence();
+        if confidence != state.confidence {
+            bail!(
+                "state claims confidence {}, its cells give {}",
+                state.confidence,
+                confidence
+            );
+        }
+        Ok(sampler)
+    }
+
+    // Everything `resume` needs, to be stored per data root
+    pub fn state(&self) -> SamplerState {
+        let mut verified: Vec<(usize, usize)> = self.verified.iter().copied().collect();
+        verified.sort_unstable();
+        SamplerState {
+            data_root: self.data_root,
+            width: self.width,
+            samples: self.samples,
+            strategy: self.strategy,
+            seed: self.seed,
+            session: self.session,
+            verified,
+            confidence: self.achieved_confidence(),
+        }
+    }
+
+    pub fn with_strategy(mut self, strategy: SamplingStrategy) -> Self {
+        self.strategy = strategy;
+        self
+    }
+
+    // Records every query made by `sample`, see `audit_log`
+    pub fn with_audit_log(mut self) -> Self {
+        self.audit_log = Some(Vec::new());
+        self
+    }
+
+    // Reports each query's outcome to `scorer`
+    pub fn with_peer_scorer(mut self, scorer: impl PeerScorer + 'static) -> Self {
+        self.scorer = Some(Box::new(scorer));
+        self
+    }
+
+    // Spends `fraction` of the samples on distinct cells out of `cells`, e.g.
+    // from fraud reports or `AvailabilityHeatmap::hotspots`. The rest are
+    // still picked by the strategy and are all `confidence` counts on, an
+    // adversary who knows the hotspots can withhold elsewhere. Cells outside
+    // the square are ignored.
+    pub fn with_hotspots(mut self, cells: Vec<(usize, usize)>, fraction: f64) -> Self {
+        let width = self.width;
+        let mut cells: Vec<(usize, usize)> = cells
+            .into_iter()
+            .filter(|&(row, col)| row < width && col < width)
+            .collect();
+        cells.sort_unstable();
+        cells.dedup();
+        self.hotspots = cells;
+        self.hotspot_fraction = fraction.clamp(0.0, 1.0);
+        self
+    }
+
+    // Samples spent on hotspots, at most one per hotspot
+    pub fn hotspot_samples(&self) -> usize {
+        let biased = (self.samples as f64 * self.hotspot_fraction).round() as usize;
+        biased.min(self.hotspots.len())
+    }
+
+    // Samples picked by the strategy
+    pub fn core_samples(&self) -> usize {
+        self.samples - self.hotspot_samples()
+    }
+
+    // Probability that the core samples catch a square that can't be
+    // repaired, which takes withholding at least a (width / 2 + 1) square of
+    // cells, or columns for `ColumnsOnly`. Exact for `Uniform` and
+    // `ColumnsOnly`, the other strategies are treated as uniform.
+    pub fn confidence(&self) -> f64 {
+        let draws = match self.strategy {
+            SamplingStrategy::ColumnsOnly => self.core_samples().min(self.width),
+            _ => self.core_samples(),
+        };
+        self.confidence_of(draws)
+    }
+
+    // Like `confidence`, counting only the core samples verified so far
+    pub fn achieved_confidence(&self) -> f64 {
+        let core = self.core_coordinates(self.core_samples());
+        let draws = match self.strategy {
+            SamplingStrategy::ColumnsOnly => core
+                .chunks(self.width)
+                .filter(|column| column.iter().all(|cell| self.verified.contains(cell)))
+                .count(),
+            _ => core
+                .iter()
+                .filter(|cell| self.verified.contains(cell))
+                .count(),
+        };
+        self.confidence_of(draws)
+    }
+
+    fn confidence_of(&self, draws: usize) -> f64 {
+        let (total, withheld) = match self.strategy {
+            SamplingStrategy::ColumnsOnly => (self.width, self.width / 2 + 1),
+            _ => (self.width * self.width, (self.width / 2 + 1).pow(2)),
+        };
+        // every draw misses the withheld cells, without replacement
+        let miss: f64 = (0..draws.min(total))
+            .map(|i| (total - withheld).saturating_sub(i) as f64 / (total - i) as f64)
+            .product();
+        1.0 - miss
+    }
+
+    // Entries of all sessions so far, `None` unless enabled
+    pub fn audit_log(&self) -> Option<&[AuditEntry]> {
+        self.audit_log.as_deref()
+    }
+
+    // Returns the entries recorded so far and starts a new log
+    pub fn take_audit_log(&mut self) -> Option<Vec<AuditEntry>> {
+        self.audit_log.as_mut().map(std::mem::take)
+    }
+
+    pub fn strategy(&self) -> SamplingStrategy {
+        self.strategy
+    }
+
+    pub fn data_root(&self) -> &[u8; 32] {
+        &self.data_root
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    // The (row, col) cells to query, deterministic in the seed: the core
+    // samples, then the hotspot samples
+    pub fn coordinates(&self) -> Vec<(usize, usize)> {
+        let mut coords = self.core_coordinates(self.core_samples());
+
+        let hotspots = self.hotspot_samples();
+        if hotspots > 0 {
+            let seed: [u8; 32] = Sha256::new()
+                .chain_update(self.seed)
+                .chain_update(b"hotspots")
+                .finalize()
+                .into();
+            let mut rng = SeedStream::new(&seed);
+            let core: HashSet<(usize, usize)> = coords.iter().copied().collect();
+            let mut left: Vec<(usize, usize)> = self
+                .hotspots
+                .iter()
+                .filter(|cell| !core.contains(cell))
+                .copied()
+                .collect();
+            for _ in 0..hotspots.min(left.len()) {
+                coords.push(left.swap_remove(rng.below(left.len())));
+            }
+        }
+
+        coords
+    }
+
+    fn core_coordinates(&self, samples: usize) -> Vec<(usize, usize)> {
+        let width = self.width;
+        let mut rng = SeedStream::new(&self.seed);
+        let mut coords = Vec::new();
+
+        match self.strategy {
+            SamplingStrategy::Uniform => {
+                let samples = samples.min(width * width);
+                let mut seen = HashSet::new();
+                while coords.len() < samples {
+                    let cell = (rng.below(width), rng.below(width));
+                    if seen.insert(cell) {
+                        coords.push(cell);
+                    }
+                }
+            }
+            SamplingStrategy::OnePerRow => {
+                let samples = samples.min(width);
+                let mut seen = HashSet::new();
+                while coords.len() < samples {
+                    let row = rng.below(width);
+                    if seen.insert(row) {
+                        coords.push((row, rng.below(width)));
+                    }
+                }
+            }
+            SamplingStrategy::StratifiedByQuadrant => {
+                let half = width / 2;
+                let samples = samples.min(width * width);
+                let mut seen = HashSet::new();
+                let mut quadrant = 0;
+                while coords.len() < samples {
+                    let row = (quadrant & 1) * half + rng.below(half);
+                    let col = (quadrant >> 1) * half + rng.below(half);
+                    if seen.insert((row, col)) {
+                        coords.push((row, col));
+                        quadrant = (quadrant + 1) & 3;
+                    }
+                }
+            }
+            SamplingStrategy::ColumnsOnly => {
+                let samples = samples.min(width);
+                let mut seen = HashSet::new();
+                while seen.len() < samples {
+                    let col = rng.below(width);
+                    if seen.insert(col) {
+                        coords.extend((0..width).map(|row| (row, col)));
+                    }
+                }
+            }
+        }
+
+        coords
+    }
+
+    // Nonce of the next `sample` call
+    pub fn session_nonce(&self) -> [u8; 32] {
+        let mut hasher = Sha256::new();
+        hasher.update(self.seed);
+        hasher.update(b"session");
+        hasher.update(self.session.to_be_bytes());
+        hasher.finalize().into()
+    }
+
+    // Queries every coordinate not verified yet, spreading them over `peers`
+    // round-robin. Cells verified in earlier sessions are reported as
+    // verified without being queried.
+    pub fn sample(&mut self, peers: &mut [&mut dyn ShareProvider]) -> Result<SamplingReport> {
+        if peers.is_empty() {
+            bail!("no peers to sample from");
+        }
+        let nonce = self.session_nonce();
+        self.session += 1;
+
+        let mut report = SamplingReport::default();
+        let (done, todo): (Vec<_>, Vec<_>) = self
+            .coordinates()
+            .into_iter()
+            .partition(|cell| self.verified.contains(cell));
+        report.verified = done;
+        for (i, (row, col)) in todo.into_iter().enumerate() {
+            let peer = &mut peers[i % peers.len()];
+            let request = ShareRequest { row, col, nonce };
+            let start = Instant::now();
+            let fetched = peer.fetch(&request);
+            let latency = start.elapsed();
+
+            let (result, proof_size) = match &fetched {
+                Ok(response) => {
+                    let checked = match peer.public_key() {
+                        Some(key) => {
+                            response.check_signed(&request, self.width, &self.data_root, &key)
+                        }
+                        None => response.check(&request, self.width, &self.data_root),
+                    };
+                    let result = match checked {
+                        Ok(()) => SampleResult::Valid,
+                        Err(rejection) => {
+                            #[cfg(feature = "tracing")]
+                            tracing::warn!(
+                                peer = %peer.peer_id(),
+                                row,
+                                col,
+                                reason = %rejection,
+                                "rejected share proof"
+                            );
+                            SampleResult::InvalidProof(rejection)
+                        }
+                    };
+                    (result, Some(response.proof.byte_len()))
+                }
+                Err(err) if err.is::<Timeout>() => (SampleResult::Timeout, None),
+                Err(err) => (SampleResult::FetchFailed(err.to_string()), None),
+            };
+            if let Some(scorer) = &mut self.scorer {
+                scorer.report(&peer.peer_id(), &result);
+            }
+            match result {
+                SampleResult::Valid => {
+                    self.verified.insert((row, col));
+                    report.verified.push((row, col));
+                }
+                _ => report.failed.push((row, col)),
+            }
+
+            if let Some(log) = &mut self.audit_log {
+                log.push(AuditEntry {
+                    row,
+                    col,
+                    peer: peer.peer_id(),
+                    result,
+                    latency,
+                    proof_size,
+                });
+            }
+        }
+
+        Ok(report)
+    }
+}
+
+// encoded `SamplerState` without its cells
+const STATE_LEN: usize = 93;
+
+// A sampler's progress on one data root, see `Sampler::state`
+#[derive(Debug, Clone, PartialEq)]
+pub struct SamplerState {
+    pub data_root: [u8; 32],
+    // width of the extended square
+    pub width: usize,
+    // what the cells are picked with, see `Sampler::new`
+    pub samples: usize,
+    pub strategy: SamplingStrategy,
+    pub seed: [u8; 32],
+    // sessions started so far
+    pub session: u64,
+    // (row, col) of every verified cell, sorted
+    pub verified: Vec<(usize, usize)>,
+    // `Sampler::achieved_confidence` when the state was taken
+    pub confidence: f64,
+}
+
+impl SamplerState {
+    // data root (32) | width (4) | samples (4) | strategy (1) | seed (32)
+    // | session (8) | confidence (8, IEEE 754) | cell count (4)
+    // | (row (4) | col (4))*
+    pub fn encode(&self) -> Vec<u8> {
+        let mut out = Vec::with_capacity(STATE_LEN + 8 * self.verified.len());
+        out.extend_from_slice(&self.data_root);
+        out.extend_from_slice(&(self.width as u32).to_be_bytes());
+        out.extend_from_slice(&(self.samples as u32).to_be_bytes());
+        out.push(self.strategy.id());
+        out.extend_from_slice(&self.seed);
+        out.extend_from_slice(&self.session.to_be_bytes());
+        out.extend_from_slice(&self.confidence.to_bits().to_be_bytes());
+        out.extend_from_slice(&(self.verified.len() as u32).to_be_bytes());
+        for &(row, col) in &self.verified {
+            out.extend_from_slice(&(row as u32).to_be_bytes());
+            out.extend_from_slice(&(col as u32).to_be_bytes());
+        }
+        out
+    }
+
+    pub fn decode(bytes: &[u8]) -> Result<Self> {
+        Ok(Self::decode_with_mode(bytes, DecodeMode::Strict)?.0)
+    }
+
+    // Also returns the warnings for whatever a lenient decode skipped
+    pub fn decode_with_mode(bytes: &[u8], mode: DecodeMode) -> Result<(Self, Vec<String>)> {
+        if bytes.len() < STATE_LEN {
+            bail!("unexpected end of input");
+        }
+        let data_root = bytes[..32].try_into()?;
+        let width = u32::from_be_bytes(bytes[32..36].try_into()?) as usize;
+        let samples = u32::from_be_bytes(bytes[36..40].try_into()?) as usize;
+        let strategy = SamplingStrategy::from_id(bytes[40])?;
+        let seed = bytes[41..73].try_into()?;
+        let session = u64::from_be_bytes(bytes[73..81].try_into()?);
+        let confidence = f64::from_bits(u64::from_be_bytes(bytes[81..89].try_into()?));
+        let count = u32::from_be_bytes(bytes[89..93].try_into()?) as usize;
+        let rest = &bytes[STATE_LEN..];
+        let Some(cells) = count.checked_mul(8).and_then(|len| rest.get(..len)) else {
+            bail!("expected {} cells, got {} bytes", count, rest.len());
+        };
+        let mut warnings = Vec::new();
+        skip_trailing(mode, rest.len() - cells.len(), &mut warnings)?;
+        let verified = cells
+            .chunks_exact(8)
+            .map(|cell| {
+                let row = u32::from_be_bytes(cell[..4].try_into().unwrap());
+                let col = u32::from_be_bytes(cell[4..].try_into().unwrap());
+                (row as usize, col as usize)
+            })
+            .collect();
+
+        let state = Self {
+            data_root,
+            width,
+            samples,
+            strategy,
+            seed,
+            session,
+            verified,
+            confidence,
+        };
+        Ok((state, warnings))
+    }
+}
+
+// Uniform indices expanded from a seed with sha256 in counter mode
+pub(crate) struct SeedStream {
+    seed: [u8; 32],
+    counter: u64,
+}
+
+impl SeedStream {
+    pub(crate) fn new(seed: &[u8; 32]) -> Self {
+        Self {
+            seed: *seed,
+            counter: 0,
+        }
+    }
+
+    // modulo bias is below 2^-50 for any width the crate supports
+    pub(crate) fn below(&mut self, n: usize) -> usize {
+        let mut hasher = Sha256::new();
+        hasher.update(self.seed);
+        hasher.update(self.counter.to_be_bytes());
+        self.counter += 1;
+
+        let digest = hasher.finalize();
+        let value = u64::from_be_bytes(digest[..8].try_into().unwrap());
+        (value % n as u64) as usize
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn resume_keeps_cells_and_confidence() {
+        let mut sampler = Sampler::new([1; 32], 16, 12, [7; 32])
+            .unwrap()
+            .with_strategy(SamplingStrategy::OnePerRow);
+        let coords = sampler.coordinates();
+        sampler.verified.extend(&coords[..5]);
+        sampler.session = 2;
+        let confidence = sampler.achieved_confidence();
+        assert!(confidence > 0.0);
+
+        let state = SamplerState::decode(&sampler.state().encode()).unwrap();
+        assert_eq!(state, sampler.state());
+        let resumed = Sampler::resume(&state).unwrap();
+        assert_eq!(resumed.coordinates(), coords);
+        assert_eq!(resumed.achieved_confidence(), confidence);
+        assert_eq!(resumed.session_nonce(), sampler.session_nonce());
+
+        let mut forged = state.clone();
+        forged.confidence = 0.99;
+        assert!(Sampler::resume(&forged).is_err());
+        let mut other_seed = state;
+        other_seed.seed = [8; 32];
+        assert!(Sampler::resume(&other_seed).is_err());
+    }
+}
@@ -0,0 +1,183 @@
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+use std::thread;
+
+use anyhow::Result;
+use sha2::{Digest, Sha256};
+
+use crate::sampling::{Sampler, SamplerState, SamplingStrategy, ShareProvider};
+
+// Where sampling of one root stands
+#[derive(Debug, Clone, PartialEq)]
+pub enum RootStatus {
+    // added, not sampled yet
+    Pending,
+    // every sample verified, see `Sampler::achieved_confidence`
+    Available { confidence: f64 },
+    // some samples couldn't be fetched or didn't verify, they are retried by
+    // the next `run`
+    Unavailable { confidence: f64, failed: usize },
+    // no peers could be reached for the root, also retried
+    Error(String),
+}
+
+struct Root {
+    state: SamplerState,
+    status: RootStatus,
+}
+
+// Samples many recent roots at once, as a syncing light client does. Roots
+// are keyed by height and sampled newest first, with at most `max_concurrent`
+// in flight. Progress is kept as `SamplerState`s, so retries only query the
+// cells still missing.
+pub struct SamplingManager {
+    samples: usize,
+    strategy: SamplingStrategy,
+    max_concurrent: usize,
+    // the seed of each root's sampler is derived from it
+    seed: [u8; 32],
+    roots: BTreeMap<u64, Root>,
+}
+
+impl SamplingManager {
+    // `seed` has to be unpredictable to the peers being sampled
+    pub fn new(samples: usize, seed: [u8; 32]) -> Self {
+        Self {
+            samples,
+            strategy: SamplingStrategy::default(),
+            max_concurrent: 4,
+            seed,
+            roots: BTreeMap::new(),
+        }
+    }
+
+    pub fn with_strategy(mut self, strategy: SamplingStrategy) -> Self {
+        self.strategy = strategy;
+        self
+    }
+
+    // Roots sampled at the same time, at least one
+    pub fn with_max_concurrent(mut self, max_concurrent: usize) -> Self {
+        self.max_concurrent = max_concurrent.max(1);
+        self
+    }
+
+    // Starts tracking the root at `height`, `width` being the extended
+    // width. A root already tracked at that height is replaced.
+    pub fn add_root(&mut self, height: u64, data_root: [u8; 32], width: usize) {
+        let seed: [u8; 32] = Sha256::new()
+            .chain_update(self.seed)
+            .chain_update(data_root)
+            .finalize()
+            .into();
+        let state = SamplerState {
+            data_root,
+            width,
+            samples: self.samples,
+            strategy: self.strategy,
+            seed,
+            session: 0,
+            verified: Vec::new(),
+            confidence: 0.0,
+        };
+        let status = RootStatus::Pending;
+        self.roots.insert(height, Root { state, status });
+    }
+
+    // Stops tracking roots below `height`
+    pub fn prune_below(&mut self, height: u64) {
+        self.roots = self.roots.split_off(&height);
+    }
+
+    pub fn status(&self, height: u64) -> Option<&RootStatus> {
+        self.roots.get(&height).map(|root| &root.status)
+    }
+
+    // (height, data root, status) of every tracked root, newest first
+    pub fn statuses(&self) -> Vec<(u64, [u8; 32], RootStatus)> {
+        self.roots
+            .iter()
+            .rev()
+            .map(|(&height, root)| (height, root.state.data_root, root.status.clone()))
+            .collect()
+    }
+
+    // The newest root tracked
+    pub fn latest(&self) -> Option<u64> {
+        self.roots.keys().next_back().copied()
+    }
+
+    // Samples every root that isn't available yet, newest first. `connect`
+    // returns the peers to sample a root from, it is called on the worker
+    // sampling that root.
+    pub fn run<F>(&mut self, connect: F)
+    where
+        F: Fn(u64, &[u8; 32]) -> Result<Vec<Box<dyn ShareProvider>>> + Sync,
+    {
+        // oldest first, workers pop from the back
+        let jobs: Vec<(u64, SamplerState)> = self
+            .roots
+            .iter()
+            .filter(|(_, root)| !matches!(root.status, RootStatus::Available { .. }))
+            .map(|(&height, root)| (height, root.state.clone()))
+            .collect();
+        let workers = self.max_concurrent.min(jobs.len());
+        let jobs = Mutex::new(jobs);
+        let done = Mutex::new(Vec::new());
+
+        thread::scope(|scope| {
+            for _ in 0..workers {
+                scope.spawn(|| loop {
+                    let Some((height, state)) = lock(&jobs).pop() else {
+                        return;
+                    };
+                    let outcome = self.sample_root(height, &state, &connect);
+                    lock(&done).push((height, outcome));
+                });
+            }
+        });
+
+        for (height, outcome) in lock(&done).drain(..) {
+            let Some(root) = self.roots.get_mut(&height) else {
+                continue;
+            };
+            match outcome {
+                Ok((state, status)) => {
+                    root.state = state;
+                    root.status = status;
+                }
+                Err(err) => root.status = RootStatus::Error(format!("{:#}", err)),
+            }
+        }
+    }
+
+    fn sample_root<F>(
+        &self,
+        height: u64,
+        state: &SamplerState,
+        connect: &F,
+    ) -> Result<(SamplerState, RootStatus)>
+    where
+        F: Fn(u64, &[u8; 32]) -> Result<Vec<Box<dyn ShareProvider>>>,
+    {
+        let mut sampler = Sampler::resume(state)?;
+
+        let mut providers = connect(height, &state.data_root)?;
+        let mut peers: Vec<&mut dyn ShareProvider> = providers
+            .iter_mut()
+            .map(|peer| &mut **peer as &mut dyn ShareProvider)
+            .collect();
+        let report = sampler.sample(&mut peers)?;
+
+        let confidence = sampler.achieved_confidence();
+        let status = match report.failed.len() {
+            0 => RootStatus::Available { confidence },
+            failed => RootStatus::Unavailable { confidence, failed },
+        };
+        Ok((sampler.state(), status))
+    }
+}
+
+fn lock<T>(mutex: &Mutex<T>) -> std::sync::MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(|e| e.into_inner())
+}
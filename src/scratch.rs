@@ -0,0 +1,52 @@
+use crate::matrix::transpose_tiled_into;
+use crate::tree::Felt;
+
+// Pool of felt buffers reused for the temporary matrices of `extend()`, so
+// repeated extensions don't go back to the allocator for every row and column
+#[derive(Default)]
+pub(crate) struct ScratchPool {
+    bufs: Vec<Vec<Felt>>,
+}
+
+impl ScratchPool {
+    // An empty buffer with room for at least `capacity` felts
+    pub(crate) fn take(&mut self, capacity: usize) -> Vec<Felt> {
+        match self.bufs.pop() {
+            Some(mut buf) => {
+                buf.clear();
+                buf.reserve(capacity);
+                buf
+            }
+            None => Vec::with_capacity(capacity),
+        }
+    }
+
+    pub(crate) fn copy(&mut self, matrix: &[Vec<Felt>]) -> Vec<Vec<Felt>> {
+        matrix
+            .iter()
+            .map(|axis| {
+                let mut buf = self.take(axis.len());
+                buf.extend_from_slice(axis);
+                buf
+            })
+            .collect()
+    }
+
+    pub(crate) fn transpose(&mut self, matrix: &[Vec<Felt>]) -> Vec<Vec<Felt>> {
+        let len = matrix.first().map(|axis| axis.len()).unwrap_or_default();
+        let mut transposed: Vec<Vec<Felt>> = (0..len)
+            .map(|_| {
+                let mut buf = self.take(matrix.len());
+                buf.resize(matrix.len(), Felt::default());
+                buf
+            })
+            .collect();
+        transpose_tiled_into(matrix, &mut transposed);
+        transposed
+    }
+
+    // Hands the buffers of a matrix back to the pool
+    pub(crate) fn recycle(&mut self, matrix: Vec<Vec<Felt>>) {
+        self.bufs.extend(matrix);
+    }
+}
@@ -0,0 +1,209 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::time::Instant;
+
+use anyhow::{bail, Result};
+
+use crate::proof_cache::ProofCache;
+use crate::sampling::{PeerId, ShareRequest, ShareResponse};
+use crate::signature::Signer;
+use crate::tree::ExtendedDataSquare;
+
+// Answers sampling requests for one square
+pub struct SampleServer<'a> {
+    cache: ProofCache<'a>,
+    // signs every response, if set
+    signer: Option<Box<dyn Signer + Send + Sync + 'a>>,
+}
+
+impl<'a> SampleServer<'a> {
+    // Keeps up to `cache_capacity` recently served proofs
+    pub fn new(eds: &'a ExtendedDataSquare, cache_capacity: usize) -> Self {
+        Self {
+            cache: ProofCache::new(eds, cache_capacity),
+            signer: None,
+        }
+    }
+
+    // Signs responses with `signer`, for samplers that know this server's key
+    pub fn with_signer(mut self, signer: impl Signer + Send + Sync + 'a) -> Self {
+        self.signer = Some(Box::new(signer));
+        self
+    }
+
+    pub fn eds(&self) -> &'a ExtendedDataSquare {
+        self.cache.eds()
+    }
+
+    pub fn serve(&self, request: &ShareRequest) -> Result<ShareResponse> {
+        let width = self.eds().width();
+        if request.row >= width || request.col >= width {
+            bail!(
+                "cell ({}, {}) out of range for width {}",
+                request.row,
+                request.col,
+                width
+            );
+        }
+        let response = ShareResponse::new(
+            request.nonce,
+            self.cache.prove_share(request.row, request.col)?,
+        );
+        match &self.signer {
+            Some(signer) => response.sign(signer.as_ref()),
+            None => Ok(response),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RateLimit {
+    // requests a client can make in a burst
+    pub burst: u32,
+    // requests a client regains per second
+    pub per_second: f64,
+    // limit on the proof bytes of one reply, see `ShareProof::byte_len`
+    pub max_response_size: usize,
+    // clients tracked at once, further new clients are turned away until
+    // some bucket refills
+    pub max_clients: usize,
+}
+
+impl Default for RateLimit {
+    fn default() -> Self {
+        Self {
+            burst: 64,
+            per_second: 16.0,
+            max_response_size: 64 * 1024,
+            max_clients: 4096,
+        }
+    }
+}
+
+// Returned (inside anyhow::Error) when a client is over its rate limit
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RateLimited;
+
+impl fmt::Display for RateLimited {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "rate limit exceeded")
+    }
+}
+
+impl std::error::Error for RateLimited {}
+
+struct Bucket {
+    tokens: f64,
+    updated: Instant,
+}
+
+// A `SampleServer` with a token bucket per client, for endpoints open to
+// anyone. Requests are charged one token each, whether or not they succeed.
+pub struct RateLimitedServer<'a> {
+    server: SampleServer<'a>,
+    limit: RateLimit,
+    buckets: HashMap<PeerId, Bucket>,
+}
+
+impl<'a> RateLimitedServer<'a> {
+    pub fn new(server: SampleServer<'a>, limit: RateLimit) -> Self {
+        Self {
+            server,
+            limit,
+            buckets: HashMap::new(),
+        }
+    }
+
+    pub fn server(&self) -> &SampleServer<'a> {
+        &self.server
+    }
+
+    pub fn limit(&self) -> &RateLimit {
+        &self.limit
+    }
+
+    pub fn serve(&mut self, client: &PeerId, request: &ShareRequest) -> Result<ShareResponse> {
+        self.serve_at(client, request, Instant::now())
+    }
+
+    pub fn serve_at(
+        &mut self,
+        client: &PeerId,
+        request: &ShareRequest,
+        now: Instant,
+    ) -> Result<ShareResponse> {
+        Ok(self.serve_many_at(client, &[*request], now)?.remove(0))
+    }
+
+    pub fn serve_many(
+        &mut self,
+        client: &PeerId,
+        requests: &[ShareRequest],
+    ) -> Result<Vec<ShareResponse>> {
+        self.serve_many_at(client, requests, Instant::now())
+    }
+
+    // Answers all of `requests` or none, if the client has the tokens and the
+    // proofs fit in `max_response_size`
+    pub fn serve_many_at(
+        &mut self,
+        client: &PeerId,
+        requests: &[ShareRequest],
+        now: Instant,
+    ) -> Result<Vec<ShareResponse>> {
+        self.take(client, requests.len(), now)?;
+
+        let mut size = 0;
+        let mut responses = Vec::with_capacity(requests.len());
+        for request in requests {
+            let response = self.server.serve(request)?;
+            size += response.proof.byte_len();
+            if size > self.limit.max_response_size {
+                bail!("response exceeds {} bytes", self.limit.max_response_size);
+            }
+            responses.push(response);
+        }
+        Ok(responses)
+    }
+
+    // Tokens `client` has left at `now`
+    pub fn tokens(&self, client: &PeerId, now: Instant) -> f64 {
+        match self.buckets.get(client) {
+            Some(bucket) => self.refilled(bucket, now),
+            None => self.limit.burst as f64,
+        }
+    }
+
+    fn refilled(&self, bucket: &Bucket, now: Instant) -> f64 {
+        let elapsed = now.saturating_duration_since(bucket.updated).as_secs_f64();
+        (bucket.tokens + elapsed * self.limit.per_second).min(self.limit.burst as f64)
+    }
+
+    fn take(&mut self, client: &PeerId, count: usize, now: Instant) -> Result<()> {
+        if !self.buckets.contains_key(client) && self.buckets.len() >= self.limit.max_clients {
+            // a full bucket tracks nothing a fresh one wouldn't
+            let burst = self.limit.burst as f64;
+            let per_second = self.limit.per_second;
+            self.buckets.retain(|_, bucket| {
+                let elapsed = now.saturating_duration_since(bucket.updated).as_secs_f64();
+                bucket.tokens + elapsed * per_second < burst
+            });
+            if self.buckets.len() >= self.limit.max_clients {
+                return Err(RateLimited.into());
+            }
+        }
+
+        let tokens = self.tokens(client, now);
+        let bucket = self.buckets.entry(client.clone()).or_insert(Bucket {
+            tokens,
+            updated: now,
+        });
+        bucket.updated = now;
+        bucket.tokens = tokens;
+        if tokens < count as f64 {
+            return Err(RateLimited.into());
+        }
+        bucket.tokens -= count as f64;
+        Ok(())
+    }
+}
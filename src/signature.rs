@@ -0,0 +1,134 @@
+use anyhow::{bail, Result};
+use sha2::{Digest, Sha256};
+
+// Signature schemes a block producer (or sampler) can sign with. Verifying
+// needs the matching cargo feature, `ed25519` or `secp256k1`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SignatureScheme {
+    Ed25519,
+    // ECDSA over secp256k1, keys SEC1 compressed
+    Secp256k1,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Signature {
+    pub scheme: SignatureScheme,
+    pub public_key: Vec<u8>,
+    pub signature: Vec<u8>,
+}
+
+impl Signature {
+    // Checks the signature over a 32 byte message digest
+    pub fn verify(&self, message: &[u8; 32]) -> Result<()> {
+        match self.scheme {
+            SignatureScheme::Ed25519 => self.verify_ed25519(message),
+            SignatureScheme::Secp256k1 => self.verify_secp256k1(message),
+        }
+    }
+
+    #[cfg(feature = "ed25519")]
+    fn verify_ed25519(&self, message: &[u8; 32]) -> Result<()> {
+        use ed25519_dalek::{Verifier, VerifyingKey};
+
+        let key = VerifyingKey::from_bytes(self.public_key.as_slice().try_into()?)?;
+        let signature = ed25519_dalek::Signature::from_slice(&self.signature)?;
+        key.verify(message, &signature)?;
+        Ok(())
+    }
+
+    #[cfg(not(feature = "ed25519"))]
+    fn verify_ed25519(&self, _message: &[u8; 32]) -> Result<()> {
+        bail!("built without the ed25519 feature");
+    }
+
+    #[cfg(feature = "secp256k1")]
+    fn verify_secp256k1(&self, message: &[u8; 32]) -> Result<()> {
+        use k256::ecdsa::signature::Verifier;
+        use k256::ecdsa::VerifyingKey;
+
+        let key = VerifyingKey::from_sec1_bytes(&self.public_key)?;
+        let signature = k256::ecdsa::Signature::from_slice(&self.signature)?;
+        key.verify(message, &signature)?;
+        Ok(())
+    }
+
+    #[cfg(not(feature = "secp256k1"))]
+    fn verify_secp256k1(&self, _message: &[u8; 32]) -> Result<()> {
+        bail!("built without the secp256k1 feature");
+    }
+}
+
+// Anything that can sign a message digest, e.g. a key held in memory or by
+// an HSM
+pub trait Signer {
+    fn sign(&self, message: &[u8; 32]) -> Result<Signature>;
+}
+
+#[cfg(feature = "ed25519")]
+impl Signer for ed25519_dalek::SigningKey {
+    fn sign(&self, message: &[u8; 32]) -> Result<Signature> {
+        use ed25519_dalek::Signer as _;
+
+        Ok(Signature {
+            scheme: SignatureScheme::Ed25519,
+            public_key: self.verifying_key().to_bytes().to_vec(),
+            signature: self.try_sign(message)?.to_bytes().to_vec(),
+        })
+    }
+}
+
+#[cfg(feature = "secp256k1")]
+impl Signer for k256::ecdsa::SigningKey {
+    fn sign(&self, message: &[u8; 32]) -> Result<Signature> {
+        use k256::ecdsa::signature::Signer as _;
+
+        let signature: k256::ecdsa::Signature = self.try_sign(message)?;
+        Ok(Signature {
+            scheme: SignatureScheme::Secp256k1,
+            public_key: self
+                .verifying_key()
+                .to_encoded_point(true)
+                .as_bytes()
+                .to_vec(),
+            signature: signature.to_bytes().to_vec(),
+        })
+    }
+}
+
+// A data root signed by the block producer, holding it accountable for the
+// square it committed to
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct SignedRoot {
+    pub data_root: [u8; 32],
+    pub signature: Signature,
+}
+
+impl SignedRoot {
+    pub fn sign(data_root: &[u8; 32], signer: &impl Signer) -> Result<Self> {
+        Ok(Self {
+            data_root: *data_root,
+            signature: signer.sign(&Self::message(data_root))?,
+        })
+    }
+
+    // What gets signed, domain separated so the signature can't be passed
+    // off as one over anything else
+    pub fn message(data_root: &[u8; 32]) -> [u8; 32] {
+        let mut hasher = Sha256::new();
+        hasher.update(b"zoda-rs/signed-root/v1");
+        hasher.update(data_root);
+        hasher.finalize().into()
+    }
+
+    pub fn verify(&self) -> Result<()> {
+        self.signature.verify(&Self::message(&self.data_root))
+    }
+
+    // Like `verify`, additionally requiring the signature to be by `public_key`
+    pub fn verify_producer(&self, public_key: &[u8]) -> Result<()> {
+        if self.signature.public_key != public_key {
+            bail!("data root is not signed by the expected producer");
+        }
+        self.verify()
+    }
+}
@@ -0,0 +1,101 @@
+use anyhow::{bail, Result};
+use rs_merkle::{algorithms::Sha256, MerkleProof, MerkleTree};
+
+use crate::proof::ShareProof;
+use crate::tree::ExtendedDataSquare;
+
+// Several squares committed to at once, e.g. one per blobspace of a block.
+// The super root is the Merkle root over the data roots of the squares, in order.
+pub struct SquareSet {
+    squares: Vec<ExtendedDataSquare>,
+    data_roots: Vec<[u8; 32]>,
+    tree: MerkleTree<Sha256>,
+}
+
+impl SquareSet {
+    pub fn new(squares: Vec<ExtendedDataSquare>) -> Result<Self> {
+        if squares.is_empty() {
+            bail!("a square set needs at least one square");
+        }
+
+        let data_roots = squares
+            .iter()
+            .map(|eds| eds.data_root())
+            .collect::<Result<Vec<_>>>()?;
+        let tree = MerkleTree::<Sha256>::from_leaves(&data_roots);
+
+        Ok(Self {
+            squares,
+            data_roots,
+            tree,
+        })
+    }
+
+    pub fn len(&self) -> usize {
+        self.squares.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.squares.is_empty()
+    }
+
+    pub fn squares(&self) -> &[ExtendedDataSquare] {
+        &self.squares
+    }
+
+    pub fn data_roots(&self) -> &[[u8; 32]] {
+        &self.data_roots
+    }
+
+    pub fn super_root(&self) -> Result<[u8; 32]> {
+        match self.tree.root() {
+            Some(r) => Ok(r),
+            None => bail!("failed to get super root"),
+        }
+    }
+
+    // Proves the share at (row, col) of square `square` up to the super root
+    pub fn prove_share(&self, square: usize, row: usize, col: usize) -> Result<SetShareProof> {
+        let Some(eds) = self.squares.get(square) else {
+            bail!("square {} out of range for {} squares", square, self.len());
+        };
+        let share_proof = eds.prove_share(row, col)?;
+        let path = self.tree.proof(&[square]).proof_hashes().to_vec();
+
+        Ok(SetShareProof {
+            share_proof,
+            data_root: self.data_roots[square],
+            square,
+            square_count: self.len(),
+            path,
+        })
+    }
+}
+
+// Proof chaining a share to the super root of a square set:
+// share -> row root -> data root -> super root
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SetShareProof {
+    pub share_proof: ShareProof,
+    pub data_root: [u8; 32],
+    // position of the square in the set
+    pub square: usize,
+    pub square_count: usize,
+    pub path: Vec<[u8; 32]>,
+}
+
+impl SetShareProof {
+    pub fn verify(&self, super_root: &[u8; 32]) -> bool {
+        if self.square >= self.square_count {
+            return false;
+        }
+
+        self.share_proof.verify(&self.data_root)
+            && MerkleProof::<Sha256>::new(self.path.clone()).verify(
+                *super_root,
+                &[self.square],
+                &[self.data_root],
+                self.square_count,
+            )
+    }
+}
@@ -0,0 +1,33 @@
+use std::time::Instant;
+
+#[cfg(feature = "counting-alloc")]
+pub use crate::counting_alloc::CountingAllocator;
+
+// Where the time of a single `extend()` went
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ExtendStats {
+    // Reed-Solomon encoding of q3, q2 and q4
+    pub encode_ms: f64,
+    // deriving dr and scaling q1/q3 by it
+    pub dr_ms: f64,
+    // hashing the x_tree and y_tree commitments
+    pub hash_ms: f64,
+    // row/column trees and the data root
+    pub tree_ms: f64,
+    // heap allocations made during extension, only known when
+    // `CountingAllocator` (feature `counting-alloc`) is the global allocator
+    pub allocs: Option<u64>,
+}
+
+pub(crate) fn elapsed_ms(start: Instant) -> f64 {
+    start.elapsed().as_secs_f64() * 1000.0
+}
+
+// Total allocations so far, or None if `CountingAllocator` isn't in use
+pub fn allocation_count() -> Option<u64> {
+    #[cfg(feature = "counting-alloc")]
+    return crate::counting_alloc::allocation_count();
+
+    #[cfg(not(feature = "counting-alloc"))]
+    None
+}
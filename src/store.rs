@@ -0,0 +1,252 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{bail, Result};
+use sha2::{Digest, Sha256};
+
+use crate::codecs::BINIUS_RS_128;
+use crate::hashers::SHA256;
+use crate::tree::{ExtendedDataSquare, Felt, LeafStrategy};
+use crate::wire::{Header, Reader, VERSION};
+
+// Persistent storage for extended squares, keyed by data root
+pub trait EdsStore {
+    // Stores the square and returns its data root
+    fn put(&mut self, eds: &ExtendedDataSquare) -> Result<[u8; 32]>;
+    fn get(&self, data_root: &[u8; 32]) -> Result<Option<ExtendedDataSquare>>;
+    // Returns whether a square was removed
+    fn remove(&mut self, data_root: &[u8; 32]) -> Result<bool>;
+    fn contains(&self, data_root: &[u8; 32]) -> bool;
+}
+
+// Keeps serialized squares in memory
+#[derive(Default)]
+pub struct MemStore {
+    squares: HashMap<[u8; 32], Vec<u8>>,
+}
+
+impl EdsStore for MemStore {
+    fn put(&mut self, eds: &ExtendedDataSquare) -> Result<[u8; 32]> {
+        let data_root = eds.data_root()?;
+        self.squares.insert(data_root, eds.to_bytes());
+        Ok(data_root)
+    }
+
+    fn get(&self, data_root: &[u8; 32]) -> Result<Option<ExtendedDataSquare>> {
+        self.squares
+            .get(data_root)
+            .map(|bytes| ExtendedDataSquare::from_bytes(bytes))
+            .transpose()
+    }
+
+    fn remove(&mut self, data_root: &[u8; 32]) -> Result<bool> {
+        Ok(self.squares.remove(data_root).is_some())
+    }
+
+    fn contains(&self, data_root: &[u8; 32]) -> bool {
+        self.squares.contains_key(data_root)
+    }
+}
+
+// File backed store with content-addressed share dedup. Shares are stored per
+// column, as a single felt is smaller than its digest: padding and other
+// repeated columns end up on disk once, whichever square they belong to.
+//
+// Layout under the root directory:
+//   squares/<data root>  header | dr | one share digest per column
+//   shares/<digest>      the column's felts
+//
+// Reference counts are rebuilt from the square manifests when the store is
+// opened, so there is no separate index that can drift out of sync.
+pub struct FileStore {
+    root: PathBuf,
+    refs: HashMap<[u8; 32], usize>,
+}
+
+impl FileStore {
+    pub fn open(root: impl AsRef<Path>) -> Result<Self> {
+        let root = root.as_ref().to_path_buf();
+        fs::create_dir_all(root.join("squares"))?;
+        fs::create_dir_all(root.join("shares"))?;
+
+        let mut store = Self {
+            root,
+            refs: HashMap::new(),
+        };
+        for entry in fs::read_dir(store.root.join("squares"))? {
+            let manifest = Manifest::decode(&fs::read(entry?.path())?)?;
+            for digest in manifest.shares {
+                *store.refs.entry(digest).or_default() += 1;
+            }
+        }
+
+        Ok(store)
+    }
+
+    // Number of distinct share blobs on disk
+    pub fn share_count(&self) -> usize {
+        self.refs.len()
+    }
+
+    fn square_path(&self, data_root: &[u8; 32]) -> PathBuf {
+        self.root.join("squares").join(to_hex(data_root))
+    }
+
+    fn share_path(&self, digest: &[u8; 32]) -> PathBuf {
+        self.root.join("shares").join(to_hex(digest))
+    }
+
+    fn release(&mut self, digest: &[u8; 32]) -> Result<()> {
+        let Some(count) = self.refs.get_mut(digest) else {
+            return Ok(());
+        };
+        *count -= 1;
+        if *count == 0 {
+            self.refs.remove(digest);
+            fs::remove_file(self.share_path(digest))?;
+        }
+        Ok(())
+    }
+}
+
+impl EdsStore for FileStore {
+    fn put(&mut self, eds: &ExtendedDataSquare) -> Result<[u8; 32]> {
+        let data_root = eds.data_root()?;
+        if self.contains(&data_root) {
+            return Ok(data_root);
+        }
+
+        let mut shares = Vec::with_capacity(eds.width());
+        for col in eds.cols() {
+            let bytes = felt_bytes(col);
+            let digest: [u8; 32] = Sha256::digest(&bytes).into();
+            if !self.refs.contains_key(&digest) {
+                fs::write(self.share_path(&digest), bytes)?;
+            }
+            *self.refs.entry(digest).or_default() += 1;
+            shares.push(digest);
+        }
+
+        let manifest = Manifest {
+            width: eds.width(),
+            leaf_strategy: eds.leaf_strategy(),
+            dr: eds.dr().to_vec(),
+            shares,
+        };
+        fs::write(self.square_path(&data_root), manifest.encode())?;
+
+        Ok(data_root)
+    }
+
+    fn get(&self, data_root: &[u8; 32]) -> Result<Option<ExtendedDataSquare>> {
+        let path = self.square_path(data_root);
+        if !path.exists() {
+            return Ok(None);
+        }
+
+        let manifest = Manifest::decode(&fs::read(path)?)?;
+        let mut cols = Vec::with_capacity(manifest.width);
+        for digest in &manifest.shares {
+            let bytes = fs::read(self.share_path(digest))?;
+            let actual: [u8; 32] = Sha256::digest(&bytes).into();
+            if actual != *digest {
+                bail!("share {} is corrupted", to_hex(digest));
+            }
+
+            let mut reader = Reader::new(&bytes);
+            cols.push(reader.felts(manifest.width)?);
+            reader.finish()?;
+        }
+
+        let eds =
+            ExtendedDataSquare::from_extended_cols_with(cols, manifest.dr, manifest.leaf_strategy)?;
+        if eds.data_root()? != *data_root {
+            bail!(
+                "stored square does not match data root {}",
+                to_hex(data_root)
+            );
+        }
+
+        Ok(Some(eds))
+    }
+
+    fn remove(&mut self, data_root: &[u8; 32]) -> Result<bool> {
+        let path = self.square_path(data_root);
+        if !path.exists() {
+            return Ok(false);
+        }
+
+        let manifest = Manifest::decode(&fs::read(&path)?)?;
+        fs::remove_file(path)?;
+        for digest in &manifest.shares {
+            self.release(digest)?;
+        }
+
+        Ok(true)
+    }
+
+    fn contains(&self, data_root: &[u8; 32]) -> bool {
+        self.square_path(data_root).exists()
+    }
+}
+
+struct Manifest {
+    width: usize,
+    leaf_strategy: LeafStrategy,
+    dr: Vec<Felt>,
+    shares: Vec<[u8; 32]>,
+}
+
+impl Manifest {
+    fn encode(&self) -> Vec<u8> {
+        let header = Header {
+            version: VERSION,
+            codec_id: BINIUS_RS_128,
+            hasher_id: SHA256,
+            width: self.width,
+            leaf_strategy: self.leaf_strategy,
+            context: None,
+        };
+        let mut out = Vec::with_capacity(header.len() + self.dr.len() * 16 + self.width * 32);
+
+        header.write(&mut out);
+        out.extend_from_slice(&felt_bytes(&self.dr));
+        for digest in &self.shares {
+            out.extend_from_slice(digest);
+        }
+
+        out
+    }
+
+    fn decode(bytes: &[u8]) -> Result<Self> {
+        let mut reader = Reader::new(bytes);
+        let header = Header::read(&mut reader)?;
+
+        let (width, leaf_strategy) = (header.width, header.leaf_strategy);
+        let dr = reader.felts(width / 2)?;
+        let mut shares = Vec::with_capacity(width);
+        for _ in 0..width {
+            shares.push(reader.take(32)?.try_into()?);
+        }
+        reader.finish()?;
+
+        Ok(Self {
+            width,
+            leaf_strategy,
+            dr,
+            shares,
+        })
+    }
+}
+
+fn felt_bytes(felts: &[Felt]) -> Vec<u8> {
+    felts
+        .iter()
+        .flat_map(|elem| elem.val().to_be_bytes())
+        .collect()
+}
+
+fn to_hex(bytes: &[u8; 32]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
@@ -0,0 +1,331 @@
+use std::io::{Read, Seek, SeekFrom};
+use std::ops::Range;
+
+use anyhow::{bail, Result};
+use rs_merkle::{algorithms::Sha256, Hasher};
+
+use crate::params::Context;
+use crate::proof::{Axis, CellProof, RootProof};
+use crate::tree::{axis_root, leaf_hash, ExtendedDataSquare, Felt};
+use crate::wire::{Header, Reader};
+
+// A source of Merkle leaves that lives outside of memory (e.g. on disk)
+pub trait LeafSource {
+    fn leaf_count(&self) -> usize;
+    // Feeds the leaf hashes in `range` to `sink`, in order
+    fn stream(&mut self, range: Range<usize>, sink: &mut dyn FnMut([u8; 32])) -> Result<()>;
+}
+
+// Computes the proof path for leaf `index` without materializing the tree.
+// Only one sibling subtree is held at a time, and each is folded with a stack
+// of at most O(log n) nodes. The resulting path matches rs_merkle's proofs.
+pub fn stream_proof(source: &mut impl LeafSource, index: usize) -> Result<Vec<[u8; 32]>> {
+    let n = source.leaf_count();
+    if index >= n {
+        bail!("leaf {} out of range for {} leaves", index, n);
+    }
+
+    let mut path = Vec::new();
+    let mut level = 0;
+    while (1 << level) < n {
+        let sibling = (index >> level) ^ 1;
+        let start = sibling << level;
+        // siblings past the end don't exist, the node is promoted instead
+        if start < n {
+            let end = (start + (1 << level)).min(n);
+            path.push(subtree_root(source, start..end)?);
+        }
+        level += 1;
+    }
+
+    Ok(path)
+}
+
+fn subtree_root(source: &mut impl LeafSource, range: Range<usize>) -> Result<[u8; 32]> {
+    // (height, hash) of completed perfect subtrees, left to right
+    let mut stack: Vec<(usize, [u8; 32])> = Vec::new();
+    source.stream(range, &mut |leaf| {
+        let mut node = (0, leaf);
+        while let Some(&(height, left)) = stack.last() {
+            if height != node.0 {
+                break;
+            }
+            stack.pop();
+            node = (height + 1, Sha256::concat_and_hash(&left, Some(&node.1)));
+        }
+        stack.push(node);
+    })?;
+
+    // fold the remaining, smaller subtrees from the right
+    let mut root = match stack.pop() {
+        Some((_, root)) => root,
+        None => bail!("empty subtree"),
+    };
+    while let Some((_, left)) = stack.pop() {
+        root = Sha256::concat_and_hash(&left, Some(&root));
+    }
+
+    Ok(root)
+}
+
+// Streams the cells of one row or column straight out of a serialized square
+pub struct SerializedAxisSource<'a, R> {
+    reader: &'a mut R,
+    // byte offset of the first cell
+    cells_offset: u64,
+    width: usize,
+    axis: Axis,
+    index: usize,
+}
+
+impl<'a, R: Read + Seek> SerializedAxisSource<'a, R> {
+    pub fn new(reader: &'a mut R, axis: Axis, index: usize) -> Result<Self> {
+        reader.seek(SeekFrom::Start(0))?;
+        let mut prefix = Vec::with_capacity(Header::MAX_LEN);
+        reader
+            .by_ref()
+            .take(Header::MAX_LEN as u64)
+            .read_to_end(&mut prefix)?;
+
+        let header = Header::read(&mut Reader::new(&prefix))?;
+        let width = header.width;
+        if index >= width {
+            bail!("index {} out of range for width {}", index, width);
+        }
+
+        let cells_offset = (header.len() + width / 2 * 16) as u64;
+
+        Ok(Self {
+            reader,
+            cells_offset,
+            width,
+            axis,
+            index,
+        })
+    }
+
+    fn read_felt(&mut self) -> Result<Felt> {
+        let mut buf = [0u8; 16];
+        self.reader.read_exact(&mut buf)?;
+        Ok(Felt::new(u128::from_be_bytes(buf)))
+    }
+}
+
+impl<R: Read + Seek> LeafSource for SerializedAxisSource<'_, R> {
+    fn leaf_count(&self) -> usize {
+        self.width
+    }
+
+    fn stream(&mut self, range: Range<usize>, sink: &mut dyn FnMut([u8; 32])) -> Result<()> {
+        // cells are stored column-major, so a column is contiguous and a row is strided
+        for i in range {
+            let (row, col) = match self.axis {
+                Axis::Row => (self.index, i),
+                Axis::Col => (i, self.index),
+            };
+            let offset = self.cells_offset + ((col * self.width + row) * 16) as u64;
+            self.reader.seek(SeekFrom::Start(offset))?;
+            sink(leaf_hash(&self.read_felt()?));
+        }
+        Ok(())
+    }
+}
+
+// Proves a cell of a serialized square without loading the square into memory
+pub fn prove_cell_from_reader<R: Read + Seek>(
+    reader: &mut R,
+    axis: Axis,
+    row: usize,
+    col: usize,
+) -> Result<CellProof> {
+    let (index, leaf_index) = match axis {
+        Axis::Row => (row, col),
+        Axis::Col => (col, row),
+    };
+
+    let mut source = SerializedAxisSource::new(reader, axis, index)?;
+    let width = source.leaf_count();
+    let path = stream_proof(&mut source, leaf_index)?;
+
+    Ok(CellProof {
+        axis,
+        row,
+        col,
+        width,
+        path,
+    })
+}
+
+// One row (or column) of an extended square on its way to a peer: its cells,
+// serialized as 16 big-endian bytes each, and the proof of its root
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AxisChunk {
+    pub index: usize,
+    pub bytes: Vec<u8>,
+    pub proof: RootProof,
+}
+
+impl AxisChunk {
+    pub fn axis(&self) -> Axis {
+        self.proof.axis
+    }
+}
+
+// Rows of a square, serialized and proven one at a time as they are pulled.
+// Nothing is produced ahead of the consumer, so a slow peer holds back the
+// sender instead of piling up buffered rows.
+pub struct RowStream<'a> {
+    eds: &'a ExtendedDataSquare,
+    next: usize,
+}
+
+impl Iterator for RowStream<'_> {
+    type Item = Result<AxisChunk>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let index = self.next;
+        if index >= self.eds.width() {
+            return None;
+        }
+        self.next += 1;
+
+        let bytes = self
+            .eds
+            .row(index)
+            .iter()
+            .flat_map(|elem| elem.val().to_be_bytes())
+            .collect();
+        Some(self.eds.prove_row_root(index).map(|proof| AxisChunk {
+            index,
+            bytes,
+            proof,
+        }))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = self.eds.width() - self.next;
+        (remaining, Some(remaining))
+    }
+}
+
+impl ExactSizeIterator for RowStream<'_> {}
+
+impl ExtendedDataSquare {
+    // Streams the square row by row, for pushing it to a peer without
+    // serializing it as a whole
+    pub fn stream_rows(&self) -> RowStream<'_> {
+        RowStream { eds: self, next: 0 }
+    }
+}
+
+// Receiving end of `stream_rows`: rows and columns from a peer are checked
+// against the data root as they arrive, so a bad chunk is rejected (and its
+// sender can be blamed) right away instead of after the whole transfer.
+pub struct EdsReceiver {
+    data_root: [u8; 32],
+    dr: Vec<Felt>,
+    context: Option<Context>,
+    // column-major, `None` until received
+    cols: Vec<Vec<Option<Felt>>>,
+    missing: usize,
+}
+
+impl EdsReceiver {
+    // Expects a square of extended `width` with the given `dr` (sent along
+    // with the header) and data root
+    pub fn new(width: usize, dr: Vec<Felt>, data_root: &[u8; 32]) -> Result<Self> {
+        if width < 2 || !width.is_power_of_two() {
+            bail!("extended width must be a power of two, got {}", width);
+        }
+        if dr.len() != width / 2 {
+            bail!("expected {} dr elements, got {}", width / 2, dr.len());
+        }
+
+        Ok(Self {
+            data_root: *data_root,
+            dr,
+            context: None,
+            cols: vec![vec![None; width]; width],
+            missing: width * width,
+        })
+    }
+
+    // The square was extended under `context`, see `Context`
+    pub fn with_context(mut self, context: &Context) -> Self {
+        self.context = Some(context.clone());
+        self
+    }
+
+    pub fn width(&self) -> usize {
+        self.cols.len()
+    }
+
+    // Number of cells not received yet
+    pub fn missing(&self) -> usize {
+        self.missing
+    }
+
+    pub fn is_complete(&self) -> bool {
+        self.missing == 0
+    }
+
+    // Verifies a row or column against the data root and stores its cells
+    pub fn receive(&mut self, chunk: &AxisChunk) -> Result<()> {
+        let width = self.width();
+        let proof = &chunk.proof;
+        if proof.width != width || proof.index != chunk.index || chunk.index >= width {
+            bail!(
+                "{:?} {} does not fit a square of width {}",
+                chunk.axis(),
+                chunk.index,
+                width
+            );
+        }
+
+        let mut reader = Reader::new(&chunk.bytes);
+        let cells = reader.felts(width)?;
+        reader.finish()?;
+        if !proof.verify(&axis_root(&cells), &self.data_root) {
+            bail!(
+                "{:?} {} is not committed to by the data root",
+                chunk.axis(),
+                chunk.index
+            );
+        }
+
+        for (k, cell) in cells.into_iter().enumerate() {
+            let slot = match chunk.axis() {
+                Axis::Row => &mut self.cols[k][chunk.index],
+                Axis::Col => &mut self.cols[chunk.index][k],
+            };
+            if slot.replace(cell).is_none() {
+                self.missing -= 1;
+            }
+        }
+
+        Ok(())
+    }
+
+    // Assembles and fully validates the received square
+    pub fn finish(self) -> Result<ExtendedDataSquare> {
+        if !self.is_complete() {
+            bail!("{} cells have not been received", self.missing);
+        }
+
+        let cols = self
+            .cols
+            .into_iter()
+            .map(|col| col.into_iter().flatten().collect())
+            .collect();
+        let mut eds = ExtendedDataSquare::from_extended_cols(cols, self.dr)?;
+        if let Some(context) = &self.context {
+            eds = eds.with_context(context);
+        }
+        eds.validate()?;
+        if eds.data_root()? != self.data_root {
+            bail!("received square does not match the data root");
+        }
+
+        Ok(eds)
+    }
+}
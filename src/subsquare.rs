@@ -0,0 +1,195 @@
+use std::collections::BTreeMap;
+
+use anyhow::{bail, Result};
+use rs_merkle::{algorithms::Sha256, MerkleProof};
+
+use crate::proof::Axis;
+use crate::tree::{leaf_hash, ExtendedDataSquare, Felt};
+use crate::wire::{DecodeMode, Reader};
+
+// A set of shares pulled out of a square, together with the multiproofs
+// tying them to its data root, to answer batched sample requests in one go
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SubSquare {
+    // width of the extended square
+    pub width: usize,
+    // sorted by row
+    pub rows: Vec<SubRow>,
+    // multiproof of the rows' roots under the data root
+    pub root_path: Vec<[u8; 32]>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SubRow {
+    pub row: usize,
+    pub row_root: [u8; 32],
+    // (col, cell), sorted by col
+    pub cells: Vec<(usize, Felt)>,
+    // multiproof of the cells under the row root
+    pub path: Vec<[u8; 32]>,
+}
+
+impl ExtendedDataSquare {
+    // Extracts the shares at `coords` (row, col). Duplicates are dropped.
+    pub fn extract(&self, coords: &[(usize, usize)]) -> Result<SubSquare> {
+        let width = self.width();
+        let mut by_row: BTreeMap<usize, Vec<usize>> = BTreeMap::new();
+        for &(row, col) in coords {
+            if row >= width || col >= width {
+                bail!("cell ({}, {}) out of range for width {}", row, col, width);
+            }
+            by_row.entry(row).or_default().push(col);
+        }
+
+        let rows = by_row
+            .into_iter()
+            .map(|(row, mut cols)| {
+                cols.sort_unstable();
+                cols.dedup();
+                let tree = self.cached_axis_tree(Axis::Row, row);
+                SubRow {
+                    row,
+                    row_root: self.row_roots()[row],
+                    cells: cols.iter().map(|&col| (col, self.cell(row, col))).collect(),
+                    path: tree.proof(&cols).proof_hashes().to_vec(),
+                }
+            })
+            .collect::<Vec<_>>();
+
+        let row_indices: Vec<usize> = rows.iter().map(|row| row.row).collect();
+        let root_path = self.data_proof(&row_indices);
+
+        Ok(SubSquare {
+            width,
+            rows,
+            root_path,
+        })
+    }
+}
+
+impl SubSquare {
+    pub fn len(&self) -> usize {
+        self.rows.iter().map(|row| row.cells.len()).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn get(&self, row: usize, col: usize) -> Option<Felt> {
+        let row = &self.rows[self.rows.binary_search_by_key(&row, |r| r.row).ok()?];
+        let col = row.cells.binary_search_by_key(&col, |c| c.0).ok()?;
+        Some(row.cells[col].1)
+    }
+
+    pub fn verify(&self, data_root: &[u8; 32]) -> bool {
+        let sorted = |indices: &[usize]| indices.windows(2).all(|w| w[0] < w[1]);
+
+        let row_indices: Vec<usize> = self.rows.iter().map(|row| row.row).collect();
+        if self.rows.is_empty()
+            || !sorted(&row_indices)
+            || row_indices.iter().any(|&row| row >= self.width)
+        {
+            return false;
+        }
+
+        for row in &self.rows {
+            let cols: Vec<usize> = row.cells.iter().map(|cell| cell.0).collect();
+            if cols.is_empty() || !sorted(&cols) || cols.iter().any(|&col| col >= self.width) {
+                return false;
+            }
+
+            let leaves: Vec<[u8; 32]> = row.cells.iter().map(|cell| leaf_hash(&cell.1)).collect();
+            if !MerkleProof::<Sha256>::new(row.path.clone()).verify(
+                row.row_root,
+                &cols,
+                &leaves,
+                self.width,
+            ) {
+                return false;
+            }
+        }
+
+        let row_roots: Vec<[u8; 32]> = self.rows.iter().map(|row| row.row_root).collect();
+        MerkleProof::<Sha256>::new(self.root_path.clone()).verify(
+            *data_root,
+            &row_indices,
+            &row_roots,
+            2 * self.width,
+        )
+    }
+
+    // width u32 | row count u32 | rows | root path
+    // row: index u32 | root | cell count u32 | (col u32 | felt)* | path
+    // path: hash count u32 | hashes
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut out = Vec::new();
+        write_u32(&mut out, self.width);
+        write_u32(&mut out, self.rows.len());
+        for row in &self.rows {
+            write_u32(&mut out, row.row);
+            out.extend_from_slice(&row.row_root);
+            write_u32(&mut out, row.cells.len());
+            for (col, cell) in &row.cells {
+                write_u32(&mut out, *col);
+                out.extend_from_slice(&cell.val().to_be_bytes());
+            }
+            write_path(&mut out, &row.path);
+        }
+        write_path(&mut out, &self.root_path);
+
+        out
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        Ok(Self::from_bytes_with_mode(bytes, DecodeMode::Strict)?.0)
+    }
+
+    // Also returns the warnings for whatever a lenient decode skipped
+    pub fn from_bytes_with_mode(bytes: &[u8], mode: DecodeMode) -> Result<(Self, Vec<String>)> {
+        let mut reader = Reader::with_mode(bytes, mode);
+        let width = reader.u32()? as usize;
+
+        let row_count = reader.u32()? as usize;
+        let mut rows = Vec::new();
+        for _ in 0..row_count {
+            let row = reader.u32()? as usize;
+            let row_root = reader.take(32)?.try_into()?;
+            let cell_count = reader.u32()? as usize;
+            let mut cells = Vec::new();
+            for _ in 0..cell_count {
+                cells.push((reader.u32()? as usize, reader.felt()?));
+            }
+            let path = read_path(&mut reader)?;
+            rows.push(SubRow {
+                row,
+                row_root,
+                cells,
+                path,
+            });
+        }
+        let root_path = read_path(&mut reader)?;
+        reader.finish()?;
+
+        let sub = Self {
+            width,
+            rows,
+            root_path,
+        };
+        Ok((sub, reader.into_warnings()))
+    }
+}
+
+fn write_u32(out: &mut Vec<u8>, n: usize) {
+    out.extend_from_slice(&(n as u32).to_be_bytes());
+}
+
+fn write_path(out: &mut Vec<u8>, path: &[[u8; 32]]) {
+    write_u32(out, path.len());
+    path.iter().for_each(|hash| out.extend_from_slice(hash));
+}
+
+fn read_path(reader: &mut Reader) -> Result<Vec<[u8; 32]>> {
+    let len = reader.u32()? as usize;
+    (0..len).map(|_| Ok(reader.take(32)?.try_into()?)).collect()
+}
@@ -0,0 +1,270 @@
+use std::thread;
+use std::time::Duration;
+
+use anyhow::{bail, Result};
+use sha2::{Digest, Sha256};
+
+use crate::repair::PartialEds;
+use crate::sampling::{
+    PeerId, Sampler, SamplingReport, SeedStream, ShareProvider, ShareRequest, ShareResponse,
+    Timeout,
+};
+use crate::serve::SampleServer;
+use crate::tree::{ExtendedDataSquare, Felt};
+
+// Ways to break a square, see `corrupt`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Corruption {
+    // flips bit `bit` (0 is the least significant) of one cell
+    FlipBit {
+        row: usize,
+        col: usize,
+        bit: u32,
+    },
+    // swaps two cells, given as (row, col)
+    SwapShares {
+        a: (usize, usize),
+        b: (usize, usize),
+    },
+    // replaces the parity half of row `i` with values that are not its extension
+    BadParityRow(usize),
+}
+
+// Returns a copy of `eds` with `corruption` applied. All trees and roots are
+// recomputed over the corrupted cells, like a dishonest producer would, so
+// the result is well formed but fails validation and fraud checks. `dr` is
+// kept as is.
+pub fn corrupt(eds: &ExtendedDataSquare, corruption: Corruption) -> Result<ExtendedDataSquare> {
+    let width = eds.width();
+    let in_range = |(row, col): (usize, usize)| row < width && col < width;
+    let mut cols = eds.cols().to_vec();
+
+    match corruption {
+        Corruption::FlipBit { row, col, bit } => {
+            if !in_range((row, col)) || bit >= 128 {
+                bail!("bit {} of cell ({}, {}) out of range", bit, row, col);
+            }
+            cols[col][row] = Felt::new(cols[col][row].val() ^ (1 << bit));
+        }
+        Corruption::SwapShares { a, b } => {
+            if !in_range(a) || !in_range(b) {
+                bail!("cells {:?} and {:?} out of range", a, b);
+            }
+            let cell = cols[a.1][a.0];
+            cols[a.1][a.0] = cols[b.1][b.0];
+            cols[b.1][b.0] = cell;
+        }
+        Corruption::BadParityRow(i) => {
+            if i >= width {
+                bail!("row {} out of range for width {}", i, width);
+            }
+            for col in &mut cols[width / 2..] {
+                col[i] = Felt::new(col[i].val() ^ 1);
+            }
+        }
+    }
+
+    ExtendedDataSquare::from_extended_cols_with(cols, eds.dr().to_vec(), eds.leaf_strategy())
+}
+
+// How requests to one server travel over a `MockNetwork`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Link {
+    // slept before every reply
+    pub latency: Duration,
+    // requests slower than this fail with `Timeout` after waiting it out
+    pub timeout: Duration,
+    // chance in [0, 1] that a request is lost, which the client sees as a
+    // `Timeout`
+    pub loss: f64,
+}
+
+impl Default for Link {
+    fn default() -> Self {
+        Self {
+            latency: Duration::ZERO,
+            timeout: Duration::from_secs(1),
+            loss: 0.0,
+        }
+    }
+}
+
+// What a server on a `MockNetwork` does with the requests that reach it
+#[derive(Clone)]
+pub enum Behavior<'a> {
+    Honest,
+    // never answers
+    Offline,
+    // flips a bit of every share it serves, leaving the proof as is
+    CorruptShares,
+    // answers from a tampered copy of the square, e.g. one made with
+    // `corrupt`: every share comes with a proof that checks out up to a
+    // data root, just not the requested one
+    Forged(&'a ExtendedDataSquare),
+    // answers from an older square, as a server that missed the latest one
+    // would
+    Stale(&'a ExtendedDataSquare),
+    // never answers for these columns, serves the rest honestly
+    WithholdCols(Vec<usize>),
+}
+
+// A `SampleServer` behind a simulated link
+pub struct MockServer<'a> {
+    id: PeerId,
+    server: SampleServer<'a>,
+    behavior: Behavior<'a>,
+    link: Link,
+    rng: SeedStream,
+    requests: usize,
+}
+
+impl<'a> MockServer<'a> {
+    pub fn id(&self) -> &PeerId {
+        &self.id
+    }
+
+    pub fn behavior(&self) -> &Behavior<'a> {
+        &self.behavior
+    }
+
+    pub fn set_behavior(&mut self, behavior: Behavior<'a>) {
+        self.behavior = behavior;
+    }
+
+    pub fn set_link(&mut self, link: Link) {
+        self.link = link;
+    }
+
+    // Requests received so far, lost ones included
+    pub fn requests(&self) -> usize {
+        self.requests
+    }
+
+    fn lost(&mut self) -> bool {
+        const SCALE: usize = 1 << 20;
+        self.link.loss > 0.0 && (self.rng.below(SCALE) as f64) < self.link.loss * SCALE as f64
+    }
+}
+
+impl ShareProvider for MockServer<'_> {
+    fn peer_id(&self) -> PeerId {
+        self.id.clone()
+    }
+
+    fn fetch(&mut self, request: &ShareRequest) -> Result<ShareResponse> {
+        self.requests += 1;
+        let silent = match &self.behavior {
+            Behavior::Offline => true,
+            Behavior::WithholdCols(cols) => cols.contains(&request.col),
+            _ => false,
+        };
+        if self.lost() || silent || self.link.latency > self.link.timeout {
+            thread::sleep(self.link.timeout);
+            return Err(Timeout.into());
+        }
+        thread::sleep(self.link.latency);
+
+        match &self.behavior {
+            Behavior::Forged(eds) | Behavior::Stale(eds) => Ok(ShareResponse::new(
+                request.nonce,
+                eds.prove_share(request.row, request.col)?,
+            )),
+            Behavior::CorruptShares => {
+                let mut response = self.server.serve(request)?;
+                response.proof.share = Felt::new(response.proof.share.val() ^ 1);
+                Ok(response)
+            }
+            _ => self.server.serve(request),
+        }
+    }
+}
+
+// In-process stand-in for a network of sampling servers, to test sampling
+// and repair end to end without sockets. Clients are `Sampler`s and
+// `PartialEds`es, each request goes to the servers in the order they were
+// added. Losses are drawn from `seed`, so runs are reproducible.
+pub struct MockNetwork<'a> {
+    seed: [u8; 32],
+    servers: Vec<MockServer<'a>>,
+}
+
+impl<'a> MockNetwork<'a> {
+    pub fn new(seed: [u8; 32]) -> Self {
+        Self {
+            seed,
+            servers: Vec::new(),
+        }
+    }
+
+    pub fn add_server(
+        &mut self,
+        id: impl Into<PeerId>,
+        server: SampleServer<'a>,
+        behavior: Behavior<'a>,
+        link: Link,
+    ) -> Result<()> {
+        let id = id.into();
+        if self.server(&id).is_some() {
+            bail!("server {} already added", id);
+        }
+        let mut hasher = Sha256::new();
+        hasher.update(self.seed);
+        hasher.update(id.as_bytes());
+        let rng = SeedStream::new(&hasher.finalize().into());
+
+        self.servers.push(MockServer {
+            id,
+            server,
+            behavior,
+            link,
+            rng,
+            requests: 0,
+        });
+        Ok(())
+    }
+
+    pub fn servers(&self) -> &[MockServer<'a>] {
+        &self.servers
+    }
+
+    pub fn server(&self, id: &str) -> Option<&MockServer<'a>> {
+        self.servers.iter().find(|server| server.id == id)
+    }
+
+    pub fn server_mut(&mut self, id: &str) -> Option<&mut MockServer<'a>> {
+        self.servers.iter_mut().find(|server| server.id == id)
+    }
+
+    // One sampling session of `sampler` against every server, see
+    // `Sampler::sample`
+    pub fn sample(&mut self, sampler: &mut Sampler) -> Result<SamplingReport> {
+        let mut peers: Vec<&mut dyn ShareProvider> = self
+            .servers
+            .iter_mut()
+            .map(|server| server as &mut dyn ShareProvider)
+            .collect();
+        sampler.sample(&mut peers)
+    }
+
+    // Fetches every cell `partial` is missing, trying the servers in turn
+    // until one answers with a share that verifies against its data root.
+    // Returns the number of cells filled in.
+    pub fn fetch_missing(&mut self, partial: &mut PartialEds, nonce: [u8; 32]) -> Result<usize> {
+        let (width, data_root) = (partial.width(), *partial.data_root());
+        let mut filled = 0;
+        for (row, col) in partial.want_list().cells.iter() {
+            let request = ShareRequest { row, col, nonce };
+            for server in &mut self.servers {
+                let Ok(response) = server.fetch(&request) else {
+                    continue;
+                };
+                if response.verify(&request, width, &data_root) {
+                    partial.set(row, col, response.proof.share)?;
+                    filled += 1;
+                    break;
+                }
+            }
+        }
+        Ok(filled)
+    }
+}
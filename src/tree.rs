@@ -1,22 +1,285 @@
+use std::collections::BTreeMap;
+use std::fmt;
+use std::mem::size_of;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Mutex, OnceLock};
+use std::time::Instant;
+
 use anyhow::{bail, Result};
-use binius_core::linear_code::LinearCode;
-use binius_core::reed_solomon::reed_solomon::ReedSolomonCode;
-use binius_field::BinaryField128b;
-use rs_merkle::{algorithms::Sha256, Hasher, MerkleTree};
+use rs_merkle::{algorithms::Sha256, MerkleTree};
 use sha2::Digest;
 
-pub type Felt = BinaryField128b;
+use crate::budget::{run_budgeted, Budget, Progress};
+use crate::encoder::Encoder;
+use crate::memory::tree_bytes;
+use crate::namespace::{check_tail_padding, Namespace};
+use crate::params::{Context, Limits, ZodaParams};
+use crate::proof::{Axis, CellProof, Rejection, RootProof, ShareProof};
+use crate::scratch::ScratchPool;
+use crate::stats::{allocation_count, elapsed_ms, ExtendStats};
+
+pub(crate) use crate::encoder::new_encoder;
+pub use crate::encoder::SMALL_WIDTH;
+pub use crate::felt::{derive_dr_into, dr_element, leaf_hash, Felt, Share, SHARE_SIZE};
+pub use crate::matrix::{transpose, transpose_and_flatten};
+
+// Rows `ExtendedDataSquare::validate_prioritized` checks first
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum Priority {
+    #[default]
+    None,
+    Rows(Vec<usize>),
+    // the rows holding the namespace's original shares
+    Namespace(Namespace),
+}
+
+// One step of `ExtendedDataSquare::validate`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Check {
+    Dr,
+    // the axis' parity half is the extension of its first half
+    Axis(Axis, usize),
+}
+
+// Returned (inside anyhow::Error) when an operation was aborted through its
+// cancellation flag. Callers can tell it apart with `err.is::<Cancelled>()`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Cancelled;
+
+impl fmt::Display for Cancelled {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "operation cancelled")
+    }
+}
+
+impl std::error::Error for Cancelled {}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExtendPhase {
+    // encoding q1 columns into q3
+    EncodeColumns,
+    // hashing (q1, q3) into the x_tree
+    CommitColumns,
+    // encoding dr-scaled rows into q2 and q4
+    EncodeRows,
+    // hashing the y_tree
+    CommitRows,
+    // row/column trees and the data root
+    AxisTrees,
+}
+
+// Receives progress updates while a square is extended. `fraction` is in
+// [0, 1] within the current phase. With the `parallel` feature this is called
+// from worker threads, so updates within a phase may arrive slightly out of order.
+pub trait ProgressSink: Sync {
+    fn report(&self, phase: ExtendPhase, fraction: f64);
+}
+
+impl<F: Fn(ExtendPhase, f64) + Sync> ProgressSink for F {
+    fn report(&self, phase: ExtendPhase, fraction: f64) {
+        self(phase, fraction)
+    }
+}
+
+fn no_progress(_: ExtendPhase, _: f64) {}
+
+// Forwards only the start and end of each phase, see `ExtendConfig::deterministic`
+struct PhaseBoundaries<'a>(&'a dyn ProgressSink);
+
+impl ProgressSink for PhaseBoundaries<'_> {
+    fn report(&self, phase: ExtendPhase, fraction: f64) {
+        if fraction == 0.0 || fraction == 1.0 {
+            self.0.report(phase, fraction)
+        }
+    }
+}
+
+// Counts finished steps of a phase and forwards them to the sink
+pub(crate) struct PhaseProgress<'a> {
+    sink: &'a dyn ProgressSink,
+    phase: ExtendPhase,
+    done: AtomicUsize,
+    total: usize,
+}
+
+impl<'a> PhaseProgress<'a> {
+    fn start(sink: &'a dyn ProgressSink, phase: ExtendPhase, total: usize) -> Self {
+        sink.report(phase, 0.0);
+        Self {
+            sink,
+            phase,
+            done: AtomicUsize::new(0),
+            total,
+        }
+    }
+
+    fn step(&self) {
+        let done = self.done.fetch_add(1, Ordering::Relaxed) + 1;
+        self.sink
+            .report(self.phase, done as f64 / self.total.max(1) as f64);
+    }
+
+    fn finish(&self) {
+        self.sink.report(self.phase, 1.0);
+    }
+}
+
+// flag for the non-cancelable entry points, never set
+static NEVER: AtomicBool = AtomicBool::new(false);
+
+pub(crate) fn check_cancel(cancel: &AtomicBool) -> Result<()> {
+    if cancel.load(Ordering::Relaxed) {
+        return Err(Cancelled.into());
+    }
+    Ok(())
+}
+
+// Knobs for extension. The thread count never changes the result: parallel
+// paths collect their results in input order, so roots are bit-identical to
+// the sequential path.
+#[derive(Debug, Clone)]
+pub struct ExtendConfig {
+    // size of the thread pool used with the `parallel` feature, defaults to rayon's global pool
+    pub threads: Option<usize>,
+    // Forbids any path whose output could depend on scheduling or thread
+    // count, as consensus code needs. Such paths check this flag and fall
+    // back; today the only one is progress reported from worker threads,
+    // which is cut down to the start and end of each phase.
+    pub deterministic: bool,
+}
+
+impl Default for ExtendConfig {
+    fn default() -> Self {
+        Self {
+            threads: None,
+            deterministic: true,
+        }
+    }
+}
+
+// What a leaf of the x_tree and y_tree commits to. Per-felt leaves can open
+// a single cell; per-share leaves hash a whole row (the share handed to a
+// node) at once, making the trees `width` times smaller and faster to build
+// but every opening a full row. The batched strategies sit in between, a leaf
+// hashes 4 or 16 consecutive felts of a row. Changes dr, so it is part of
+// `ZodaParams`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum LeafStrategy {
+    #[default]
+    PerFelt,
+    PerShare,
+    Per4Felts,
+    Per16Felts,
+}
+
+// Sizes of a commitment tree over the `2 * width` rows of (q1, q3), see
+// `LeafStrategy::report`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LeafReport {
+    pub leaves: usize,
+    pub tree_bytes: usize,
+    // opening a single felt, data and hashes
+    pub felt_opening_bytes: usize,
+    // opening a whole row
+    pub share_opening_bytes: usize,
+}
+
+impl LeafStrategy {
+    pub fn id(&self) -> u8 {
+        match self {
+            Self::PerFelt => 0,
+            Self::PerShare => 1,
+            Self::Per4Felts => 2,
+            Self::Per16Felts => 3,
+        }
+    }
+
+    pub fn from_id(id: u8) -> Result<Self> {
+        match id {
+            0 => Ok(Self::PerFelt),
+            1 => Ok(Self::PerShare),
+            2 => Ok(Self::Per4Felts),
+            3 => Ok(Self::Per16Felts),
+            _ => bail!("unknown leaf strategy {}", id),
+        }
+    }
+
+    // Felts hashed into one leaf of a row of `width` felts. A row narrower
+    // than a batch is a single leaf, as with `PerShare`.
+    pub fn felts_per_leaf(&self, width: usize) -> usize {
+        let batch = match self {
+            Self::PerFelt => 1,
+            Self::PerShare => width,
+            Self::Per4Felts => 4,
+            Self::Per16Felts => 16,
+        };
+        batch.min(width).max(1)
+    }
+
+    // Tree and proof sizes for an original square of `width`
+    pub fn report(&self, width: usize) -> LeafReport {
+        let rows = 2 * width;
+        let batch = self.felts_per_leaf(width);
+        let leaves = rows * width.div_ceil(batch);
+        let path = |leaves: usize| 32 * leaves.next_power_of_two().trailing_zeros() as usize;
+        LeafReport {
+            leaves,
+            tree_bytes: tree_bytes(leaves),
+            felt_opening_bytes: batch * SHARE_SIZE + path(leaves),
+            // a row's leaves are contiguous and aligned, its subtree root
+            // only needs the path above it
+            share_opening_bytes: width * SHARE_SIZE + path(rows),
+        }
+    }
+}
+
+// The Reed-Solomon code every row and column is extended with: binius's
+// `ReedSolomonCode` over BinaryField128b, evaluated with its additive NTT on
+// the subspace spanned by the first `log_length` canonical basis elements,
+// see `EVALUATION_DOMAIN`. Everything needed to rebuild the encoder outside
+// this crate.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CodeParams {
+    pub log_dimension: usize,
+    pub log_inv_rate: usize,
+    pub dimension: usize,
+    // codeword length, the extended width
+    pub length: usize,
+    pub evaluation_domain: &'static str,
+}
+
+impl CodeParams {
+    // (numerator, denominator)
+    pub fn rate(&self) -> (usize, usize) {
+        (1, 1 << self.log_inv_rate)
+    }
+}
+
+// Identifies the evaluation domain and twiddle factors of the encoder: binius
+// additive NTT, novel polynomial basis, canonical subspace of the tower field,
+// twiddles derived from the domain rather than configured
+pub const EVALUATION_DOMAIN: &str = "binius-additive-ntt/novel-basis/canonical-subspace";
 
 pub struct DataSquare {
-    encoder: ReedSolomonCode<Felt>,
+    encoder: Encoder,
     q1_cols: Vec<Vec<Felt>>,
     width: usize,
+    // digest of the context dr is bound to
+    context: Option<[u8; 32]>,
+    leaf_strategy: LeafStrategy,
+    // temporary matrices, reused across extend() calls
+    scratch: ScratchPool,
 }
 
 pub struct ExtendedDataSquare {
     cols: Vec<Vec<Felt>>,
-    rows: Vec<Vec<Felt>>,
+    // row-major copy of `cols`, only kept when asked for via `materialize_rows`
+    rows: Option<Vec<Vec<Felt>>>,
     dr: Vec<Felt>,
+    // digest of the context dr is bound to
+    context: Option<[u8; 32]>,
+    // of the x_tree and y_tree
+    leaf_strategy: LeafStrategy,
 
     // over columns of (q1, q3)
     x_tree: MerkleTree<Sha256>,
@@ -24,80 +287,841 @@ pub struct ExtendedDataSquare {
     y_tree: MerkleTree<Sha256>,
     // over all quadrants (todo: what representation?)
     // z_tree: MerkleTree<Sha256>,
+    row_roots: Vec<[u8; 32]>,
+    col_roots: Vec<[u8; 32]>,
+    // over row_roots followed by col_roots
+    data_tree: MerkleTree<Sha256>,
+
+    // per-axis trees, kept around so repeated proofs don't rebuild them
+    row_trees: Vec<OnceLock<MerkleTree<Sha256>>>,
+    col_trees: Vec<OnceLock<MerkleTree<Sha256>>>,
+
+    // set once the square is final and its parity may be pruned
+    prunable: bool,
 
-    //TODO: row_roots, col_roots
+    // only present for squares extended locally
+    stats: Option<ExtendStats>,
+}
+
+// A borrowed row of an extended square. Squares are stored column-major, so
+// unless rows were materialized this reads one cell from each column.
+#[derive(Clone, Copy)]
+pub struct RowView<'a> {
+    cols: &'a [Vec<Felt>],
+    index: usize,
+    materialized: Option<&'a [Felt]>,
+}
+
+impl<'a> RowView<'a> {
+    pub fn index(&self) -> usize {
+        self.index
+    }
+
+    pub fn len(&self) -> usize {
+        self.cols.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.cols.is_empty()
+    }
+
+    pub fn get(&self, col: usize) -> Felt {
+        match self.materialized {
+            Some(row) => row[col],
+            None => self.cols[col][self.index],
+        }
+    }
+
+    // Contiguous slice of the row, only available once rows are materialized
+    pub fn as_slice(&self) -> Option<&'a [Felt]> {
+        self.materialized
+    }
+
+    pub fn iter(&self) -> impl ExactSizeIterator<Item = Felt> + 'a {
+        let view = *self;
+        (0..view.len()).map(move |j| view.get(j))
+    }
+
+    pub fn to_vec(&self) -> Vec<Felt> {
+        match self.materialized {
+            Some(row) => row.to_vec(),
+            None => self.iter().collect(),
+        }
+    }
 }
 
 impl ExtendedDataSquare {
-    fn from_cols(
-        q1: Vec<Vec<Felt>>,
-        q2: Vec<Vec<Felt>>,
-        q3: Vec<Vec<Felt>>,
-        q4: Vec<Vec<Felt>>,
+    // `leaves` are the leaf hashes of every cell of `cols`, see `leaf_cols`
+    pub(crate) fn from_cols(
+        cols: Vec<Vec<Felt>>,
+        leaves: Vec<Vec<[u8; 32]>>,
         dr: Vec<Felt>,
         x_tree: MerkleTree<Sha256>,
         y_tree: MerkleTree<Sha256>,
-    ) -> Self {
-        // step 1: combine q1 and q3
-        let mut left_cols = q1.clone();
-        for col in left_cols.iter_mut().zip(q3) {
-            col.0.extend(col.1);
-        }
+    ) -> Result<Self> {
+        let rows = transpose(&cols);
+        let row_leaves = transpose(&leaves);
 
-        // step 2: combine q2 and q4
-        let mut right_cols = q2.clone();
-        for col in right_cols.iter_mut().zip(q4) {
-            col.0.extend(col.1);
-        }
+        let axes = |axes: &'_ [Vec<Felt>], leaves: &'_ [Vec<[u8; 32]>]| {
+            let pairs: Vec<_> = axes.iter().zip(leaves).collect();
+            map_ordered(&pairs, |(axis, leaves)| {
+                OnceLock::from(axis_tree_from_leaves(axis, leaves))
+            })
+        };
+        let row_trees: Vec<OnceLock<MerkleTree<Sha256>>> = axes(&rows, &row_leaves);
+        let col_trees: Vec<OnceLock<MerkleTree<Sha256>>> = axes(&cols, &leaves);
 
-        // step 3: combine left and right cols
-        let mut cols = left_cols.clone();
-        cols.extend(right_cols);
+        let cached_root = |tree: &OnceLock<MerkleTree<Sha256>>| {
+            tree.get().and_then(|tree| tree.root()).unwrap_or_default()
+        };
+        let row_roots: Vec<[u8; 32]> = row_trees.iter().map(cached_root).collect();
+        let col_roots: Vec<[u8; 32]> = col_trees.iter().map(cached_root).collect();
 
-        let rows = transpose(cols.clone());
+        let mut data_leaves = row_roots.clone();
+        data_leaves.extend_from_slice(&col_roots);
+        let data_tree = MerkleTree::<Sha256>::from_leaves(&data_leaves);
 
-        Self {
+        Ok(Self {
             cols,
-            rows,
+            rows: None,
             dr,
+            context: None,
+            leaf_strategy: LeafStrategy::default(),
             x_tree,
             y_tree,
+            row_roots,
+            col_roots,
+            data_tree,
+            row_trees,
+            col_trees,
+            prunable: false,
+            stats: None,
+        })
+    }
+
+    // Wraps an already extended square given as columns, recomputing all trees locally
+    pub(crate) fn from_extended_cols(cols: Vec<Vec<Felt>>, dr: Vec<Felt>) -> Result<Self> {
+        Self::from_extended_cols_with(cols, dr, LeafStrategy::default())
+    }
+
+    pub(crate) fn from_extended_cols_with(
+        cols: Vec<Vec<Felt>>,
+        dr: Vec<Felt>,
+        leaf_strategy: LeafStrategy,
+    ) -> Result<Self> {
+        let width = cols.len();
+        if width < 2 || !width.is_power_of_two() {
+            bail!("extended width must be a power of two, got {}", width);
+        }
+        if cols.iter().any(|col| col.len() != width) {
+            bail!("ExtendedDataSquare must be square");
         }
+
+        let half = width / 2;
+        if dr.len() != half {
+            bail!("expected {} dr elements, got {}", half, dr.len());
+        }
+
+        let leaves = leaf_cols(&cols);
+        let x_tree = x_tree(&cols[..half], &leaves[..half], leaf_strategy);
+
+        let mut q1_dr_cols: Vec<Vec<Felt>> = cols[..half]
+            .iter()
+            .map(|col| col[..half].to_vec())
+            .collect();
+        for (col, dr_i) in q1_dr_cols.iter_mut().zip(dr.iter()) {
+            col.iter_mut().for_each(|elem| *elem *= *dr_i);
+        }
+        let y_tree = y_tree(&q1_dr_cols, &cols[half..], &leaves[half..], leaf_strategy);
+
+        let mut eds = Self::from_cols(cols, leaves, dr, x_tree, y_tree)?;
+        eds.leaf_strategy = leaf_strategy;
+        Ok(eds)
+    }
+
+    // Wraps a square that was extended elsewhere (e.g. by the block producer).
+    // All trees are recomputed locally, the square is fully validated and its
+    // data root has to match the provided one.
+    pub fn import(
+        cols: Vec<Vec<Felt>>,
+        dr: Vec<Felt>,
+        params: &ZodaParams,
+        data_root: &[u8; 32],
+    ) -> Result<Self> {
+        params.check_supported()?;
+        if cols.len() != params.extended_width() {
+            bail!(
+                "expected {} columns, got {}",
+                params.extended_width(),
+                cols.len()
+            );
+        }
+
+        let mut eds = Self::from_extended_cols_with(cols, dr, params.leaf_strategy)?;
+        eds.context = params.context;
+        eds.validate()?;
+
+        if eds.data_root()? != *data_root {
+            bail!("data root mismatch");
+        }
+
+        Ok(eds)
+    }
+
+    // Checks that the square is a valid extension: dr is derived from the x_tree
+    // root, q3 is the column extension of q1, q2 is the row extension of the
+    // dr-scaled q1 and q4 is consistent with both q2 and q3.
+    pub fn validate(&self) -> Result<()> {
+        self.validate_cancelable(&NEVER)
+    }
+
+    // Like `validate`, but returns `Cancelled` soon after `cancel` is set
+    pub fn validate_cancelable(&self, cancel: &AtomicBool) -> Result<()> {
+        let encoder = new_encoder(self.width() / 2)?;
+        for check in self.validation_checks() {
+            check_cancel(cancel)?;
+            self.run_check(check, &encoder)?;
+        }
+        Ok(())
+    }
+
+    // Like `validate`, but stops once `budget` runs out. `start` is the
+    // `verified` count of the previous partial call, 0 on the first.
+    pub fn validate_with_budget(&self, budget: &Budget, start: usize) -> Result<Progress> {
+        let encoder = new_encoder(self.width() / 2)?;
+        let checks = self.validation_checks();
+        run_budgeted(checks.len(), start, budget, |i| {
+            self.run_check(checks[i], &encoder)
+        })
+    }
+
+    // Like `validate`, but checks the rows `priority` names right after dr and
+    // calls `on_row` as each one checks out, so their shares can be served
+    // before the rest of the square is validated
+    pub fn validate_prioritized(
+        &self,
+        priority: &Priority,
+        mut on_row: impl FnMut(usize),
+    ) -> Result<()> {
+        let encoder = new_encoder(self.width() / 2)?;
+        let rows = self.priority_rows(priority)?;
+        let first: Vec<Check> = std::iter::once(Check::Dr)
+            .chain(rows.iter().map(|&row| Check::Axis(Axis::Row, row)))
+            .collect();
+        let rest = self
+            .validation_checks()
+            .into_iter()
+            .filter(|check| !first.contains(check));
+
+        for check in first.iter().copied().chain(rest) {
+            self.run_check(check, &encoder)?;
+            if let Check::Axis(Axis::Row, row) = check {
+                on_row(row);
+            }
+        }
+        Ok(())
+    }
+
+    fn priority_rows(&self, priority: &Priority) -> Result<Vec<usize>> {
+        let mut rows = match priority {
+            Priority::None => Vec::new(),
+            Priority::Rows(rows) => rows.clone(),
+            Priority::Namespace(namespace) => self
+                .namespaced_shares()
+                .filter(|(ns, ..)| ns == namespace)
+                .map(|(_, row, ..)| row)
+                .collect(),
+        };
+        if let Some(row) = rows.iter().find(|&&row| row >= self.width()) {
+            bail!("row {} out of range for width {}", row, self.width());
+        }
+        // first mention wins
+        let mut seen = vec![false; self.width()];
+        rows.retain(|&row| !std::mem::replace(&mut seen[row], true));
+        Ok(rows)
+    }
+
+    // The steps of `validate`, in order: dr, then q1's columns and rows, then
+    // the rows and columns through q4
+    fn validation_checks(&self) -> Vec<Check> {
+        let (half, width) = (self.width() / 2, self.width());
+        let axes = |axis, range: std::ops::Range<usize>| range.map(move |i| Check::Axis(axis, i));
+
+        std::iter::once(Check::Dr)
+            .chain(axes(Axis::Col, 0..half))
+            .chain(axes(Axis::Row, 0..half))
+            .chain(axes(Axis::Row, half..width))
+            .chain(axes(Axis::Col, half..width))
+            .collect()
+    }
+
+    fn run_check(&self, check: Check, encoder: &Encoder) -> Result<()> {
+        let half = self.width() / 2;
+        let (axis, index) = match check {
+            Check::Dr => {
+                let Some(x_root) = self.x_root() else {
+                    bail!("failed to get tree commitment");
+                };
+                if self.dr.len() != half {
+                    bail!("dr of {} felts for width {}", self.dr.len(), self.width());
+                }
+                let context = self.context.as_ref();
+                if let Some(index) =
+                    (0..half).find(|&i| dr_element(&x_root, context, i) != self.dr[i])
+                {
+                    return Err(Rejection::DrMismatch { index }.into());
+                }
+                return Ok(());
+            }
+            Check::Axis(axis, index) => (axis, index),
+        };
+
+        let consistent = match axis {
+            Axis::Row => {
+                let row = self.row(index).to_vec();
+                encoder.encode(scale_by_dr(&row[..half], &self.dr))? == row[half..]
+            }
+            Axis::Col => {
+                let col = &self.cols[index];
+                encoder.encode(col[..half].to_vec())? == col[half..]
+            }
+        };
+        match (consistent, axis) {
+            (true, _) => Ok(()),
+            (false, _) if index >= half => bail!("q4 is inconsistent at {:?} {}", axis, index),
+            (false, Axis::Col) => bail!("column {} of q3 is not the extension of q1", index),
+            (false, Axis::Row) => bail!("row {} of q2 is not the extension of q1", index),
+        }
+    }
+
+    pub fn width(&self) -> usize {
+        self.cols.len()
+    }
+
+    // Marks the square as final, signalling that its parity can be pruned
+    pub fn mark_prunable(&mut self) {
+        self.prunable = true;
+    }
+
+    pub fn is_prunable(&self) -> bool {
+        self.prunable
+    }
+
+    // Phase timings of the `extend()` call that produced this square
+    pub fn extend_stats(&self) -> Option<&ExtendStats> {
+        self.stats.as_ref()
+    }
+
+    // Drops the parity quadrants and returns the original data square
+    pub fn shrink(&self) -> Result<DataSquare> {
+        let half = self.width() / 2;
+        let q1_cols = self.cols[..half]
+            .iter()
+            .map(|col| col[..half].to_vec())
+            .collect();
+
+        let mut square = DataSquare::new(q1_cols)?;
+        square.context = self.context;
+        square.leaf_strategy = self.leaf_strategy;
+        Ok(square)
+    }
+
+    pub fn row(&self, index: usize) -> RowView<'_> {
+        RowView {
+            cols: &self.cols,
+            index,
+            materialized: self.rows.as_ref().map(|rows| rows[index].as_slice()),
+        }
+    }
+
+    pub fn rows(&self) -> impl ExactSizeIterator<Item = RowView<'_>> {
+        (0..self.width()).map(|i| self.row(i))
+    }
+
+    pub fn col(&self, index: usize) -> &[Felt] {
+        &self.cols[index]
+    }
+
+    pub fn cols(&self) -> &[Vec<Felt>] {
+        &self.cols
+    }
+
+    pub fn cell(&self, row: usize, col: usize) -> Felt {
+        self.cols[col][row]
+    }
+
+    // Keeps a row-major copy of the square next to the columns. Doubles the
+    // memory footprint, but makes row access contiguous for servers that
+    // mostly read by row.
+    pub fn materialize_rows(&mut self) {
+        if self.rows.is_none() {
+            self.rows = Some(transpose(&self.cols));
+        }
+    }
+
+    // Drops the row-major copy made by `materialize_rows`
+    pub fn drop_rows(&mut self) {
+        self.rows = None;
+    }
+
+    pub fn has_materialized_rows(&self) -> bool {
+        self.rows.is_some()
+    }
+
+    // Approximate heap bytes held by the square, including cached trees
+    pub fn memory_usage(&self) -> usize {
+        let felt = size_of::<Felt>();
+        let cells = self.cols.iter().map(|col| col.len() * felt).sum::<usize>();
+        let rows = if self.rows.is_some() { cells } else { 0 };
+        let roots = (self.row_roots.len() + self.col_roots.len()) * 32;
+        let trees = [&self.x_tree, &self.y_tree, &self.data_tree]
+            .into_iter()
+            .chain(
+                self.row_trees
+                    .iter()
+                    .chain(&self.col_trees)
+                    .flat_map(OnceLock::get),
+            )
+            .map(|tree| tree_bytes(tree.leaves_len()))
+            .sum::<usize>();
+
+        cells + rows + self.dr.len() * felt + roots + trees
+    }
+
+    pub fn leaf_strategy(&self) -> LeafStrategy {
+        self.leaf_strategy
+    }
+
+    // Digest of the context the square is bound to
+    pub fn context(&self) -> Option<&[u8; 32]> {
+        self.context.as_ref()
+    }
+
+    // Binds a decoded square to `context` so `validate` checks dr against it
+    pub fn with_context(mut self, context: &Context) -> Self {
+        self.context = Some(context.digest());
+        self
+    }
+
+    // Like `with_context`, from a digest kept with a stored square
+    pub(crate) fn with_context_digest(mut self, context: Option<[u8; 32]>) -> Self {
+        self.context = context;
+        self
+    }
+
+    pub fn dr(&self) -> &[Felt] {
+        &self.dr
+    }
+
+    pub fn x_root(&self) -> Option<[u8; 32]> {
+        self.x_tree.root()
+    }
+
+    pub fn y_root(&self) -> Option<[u8; 32]> {
+        self.y_tree.root()
+    }
+
+    // Leaves of the x_tree and the y_tree
+    pub(crate) fn commitment_leaves(&self) -> (Vec<[u8; 32]>, Vec<[u8; 32]>) {
+        (
+            self.x_tree.leaves().unwrap_or_default(),
+            self.y_tree.leaves().unwrap_or_default(),
+        )
+    }
+
+    pub fn row_roots(&self) -> &[[u8; 32]] {
+        &self.row_roots
+    }
+
+    pub fn col_roots(&self) -> &[[u8; 32]] {
+        &self.col_roots
+    }
+
+    pub fn data_root(&self) -> Result<[u8; 32]> {
+        match self.data_tree.root() {
+            Some(r) => Ok(r),
+            None => bail!("failed to get data root"),
+        }
+    }
+
+    // Proves the inclusion of row root `i` under the data root
+    pub fn prove_row_root(&self, i: usize) -> Result<RootProof> {
+        self.prove_root(Axis::Row, i)
+    }
+
+    // Proves the inclusion of column root `j` under the data root
+    pub fn prove_col_root(&self, j: usize) -> Result<RootProof> {
+        self.prove_root(Axis::Col, j)
+    }
+
+    // Checks that q4 is both the row extension of (dr-scaled) q3 and the column
+    // extension of q2. Returns the first offending row or column of q4, if any.
+    pub fn verify_q4_consistency(&self) -> Result<Option<(Axis, usize)>> {
+        self.q4_consistency(&NEVER)
+    }
+
+    fn q4_consistency(&self, cancel: &AtomicBool) -> Result<Option<(Axis, usize)>> {
+        let half = self.width() / 2;
+        let encoder = new_encoder(half)?;
+
+        for (i, row) in self.rows().skip(half).enumerate() {
+            check_cancel(cancel)?;
+            let row = row.to_vec();
+            if encoder.encode(scale_by_dr(&row[..half], &self.dr))? != row[half..] {
+                return Ok(Some((Axis::Row, half + i)));
+            }
+        }
+
+        for (j, col) in self.cols[half..].iter().enumerate() {
+            check_cancel(cancel)?;
+            if encoder.encode(col[..half].to_vec())? != col[half..] {
+                return Ok(Some((Axis::Col, half + j)));
+            }
+        }
+
+        Ok(None)
+    }
+
+    // Proves the inclusion of the cell at (row, col) under its row root
+    pub fn prove_cell(&self, row: usize, col: usize) -> Result<CellProof> {
+        self.prove_cell_on(Axis::Row, row, col)
+    }
+
+    // Proves the inclusion of the cell at (row, col) under its column root
+    pub fn prove_cell_in_col(&self, row: usize, col: usize) -> Result<CellProof> {
+        self.prove_cell_on(Axis::Col, row, col)
+    }
+
+    // Releases the cached row and column trees. Proofs keep working but will
+    // rebuild (and re-cache) the trees they need.
+    pub fn drop_proof_cache(&mut self) {
+        self.row_trees
+            .iter_mut()
+            .for_each(|tree| *tree = OnceLock::new());
+        self.col_trees
+            .iter_mut()
+            .for_each(|tree| *tree = OnceLock::new());
+    }
+
+    fn prove_cell_on(&self, axis: Axis, row: usize, col: usize) -> Result<CellProof> {
+        let width = self.width();
+        if row >= width || col >= width {
+            bail!("cell ({}, {}) out of range for width {}", row, col, width);
+        }
+
+        let (tree, leaf_index) = match axis {
+            Axis::Row => (self.cached_axis_tree(axis, row), col),
+            Axis::Col => (self.cached_axis_tree(axis, col), row),
+        };
+        let path = tree.proof(&[leaf_index]).proof_hashes().to_vec();
+
+        Ok(CellProof {
+            axis,
+            row,
+            col,
+            width,
+            path,
+        })
+    }
+
+    // Proves the share at (row, col) all the way up to the data root
+    pub fn prove_share(&self, row: usize, col: usize) -> Result<ShareProof> {
+        let cell_proof = self.prove_cell(row, col)?;
+        let root_proof = self.prove_row_root(row)?;
+
+        Ok(ShareProof {
+            share: self.cell(row, col),
+            cell_proof,
+            row_root: self.row_roots[row],
+            root_proof,
+        })
+    }
+
+    // The tree of a row or column, built on first use
+    pub(crate) fn cached_axis_tree(&self, axis: Axis, index: usize) -> &MerkleTree<Sha256> {
+        match axis {
+            Axis::Row => self.row_trees[index].get_or_init(|| axis_tree(&self.row(index).to_vec())),
+            Axis::Col => self.col_trees[index].get_or_init(|| axis_tree(&self.cols[index])),
+        }
+    }
+
+    // Multiproof of data tree leaves (row roots, then column roots)
+    pub(crate) fn data_proof(&self, leaf_indices: &[usize]) -> Vec<[u8; 32]> {
+        self.data_tree.proof(leaf_indices).proof_hashes().to_vec()
+    }
+
+    fn prove_root(&self, axis: Axis, index: usize) -> Result<RootProof> {
+        let width = self.width();
+        if index >= width {
+            bail!("index {} out of range for width {}", index, width);
+        }
+
+        let leaf_index = match axis {
+            Axis::Row => index,
+            Axis::Col => width + index,
+        };
+        let path = self.data_tree.proof(&[leaf_index]).proof_hashes().to_vec();
+
+        Ok(RootProof {
+            axis,
+            index,
+            width,
+            path,
+        })
     }
 }
 
 impl DataSquare {
+    pub fn new(q1_cols: Vec<Vec<Felt>>) -> Result<Self> {
+        Self::try_new(q1_cols)
+    }
+
+    // Validates the shape and width of the square before building it
+    pub fn try_new(q1_cols: Vec<Vec<Felt>>) -> Result<Self> {
+        Self::try_new_with_limits(q1_cols, &Limits::default())
+    }
+
+    pub fn try_new_with_limits(q1_cols: Vec<Vec<Felt>>, limits: &Limits) -> Result<Self> {
+        let width = q1_cols.len();
+        if !width.is_power_of_two() {
+            bail!("width must be a power of two, got {}", width);
+        }
+        limits.check(width, SHARE_SIZE)?;
+        if let Some(j) = q1_cols.iter().position(|col| col.len() != width) {
+            bail!(
+                "DataSquare must be square, column {} has {} cells for width {}",
+                j,
+                q1_cols[j].len(),
+                width
+            );
+        }
+        check_tail_padding(&q1_cols)?;
+
+        Ok(Self::new_unchecked(q1_cols))
+    }
+
+    // Builds a square from column-major shares of `SHARE_SIZE` bytes each
+    pub fn try_from_shares(shares: &[Vec<u8>]) -> Result<Self> {
+        Self::try_from_shares_with_limits(shares, &Limits::default())
+    }
+
+    pub fn try_from_shares_with_limits(shares: &[Vec<u8>], limits: &Limits) -> Result<Self> {
+        let width = shares.len().isqrt();
+        if width * width != shares.len() {
+            bail!("{} shares do not form a square", shares.len());
+        }
+        limits.check(width, SHARE_SIZE)?;
+        if let Some(i) = shares.iter().position(|share| share.len() != SHARE_SIZE) {
+            bail!(
+                "share {} is {} bytes, expected {}",
+                i,
+                shares[i].len(),
+                SHARE_SIZE
+            );
+        }
+
+        let q1_cols = shares
+            .chunks(width.max(1))
+            .map(|col| {
+                col.iter()
+                    .map(|share| Felt::new(u128::from_be_bytes(share[..].try_into().unwrap())))
+                    .collect()
+            })
+            .collect();
+
+        Self::try_new_with_limits(q1_cols, limits)
+    }
+
+    // Skips validation. Panics if the width has no encoder, misshapen columns
+    // fail later during extension.
+    pub fn new_unchecked(q1_cols: Vec<Vec<Felt>>) -> Self {
+        let width = q1_cols.len();
+        let encoder = new_encoder(width).expect("no encoder for width");
+
+        Self {
+            encoder,
+            q1_cols,
+            width,
+            context: None,
+            leaf_strategy: LeafStrategy::default(),
+            scratch: ScratchPool::default(),
+        }
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    // The original shares, column-major
+    pub fn cols(&self) -> &[Vec<Felt>] {
+        &self.q1_cols
+    }
+
+    pub fn code_params(&self) -> CodeParams {
+        CodeParams {
+            log_dimension: self.encoder.log_dim(),
+            log_inv_rate: self.encoder.log_inv_rate(),
+            dimension: self.encoder.dim(),
+            length: self.encoder.len(),
+            evaluation_domain: EVALUATION_DOMAIN,
+        }
+    }
+
+    // Binds the extension to `context`, see `Context`
+    pub fn with_context(mut self, context: &Context) -> Self {
+        self.context = Some(context.digest());
+        self
+    }
+
+    // Like `with_context`, from a digest kept with a stored square
+    pub(crate) fn with_context_digest(mut self, context: Option<[u8; 32]>) -> Self {
+        self.context = context;
+        self
+    }
+
+    // Digest of the context the extension is bound to
+    pub fn context(&self) -> Option<&[u8; 32]> {
+        self.context.as_ref()
+    }
+
+    pub fn with_leaf_strategy(mut self, leaf_strategy: LeafStrategy) -> Self {
+        self.leaf_strategy = leaf_strategy;
+        self
+    }
+
+    pub fn leaf_strategy(&self) -> LeafStrategy {
+        self.leaf_strategy
+    }
+
+    // Extends the square on a dedicated pool of `config.threads` threads
+    pub fn extend_with(&mut self, config: &ExtendConfig) -> Result<ExtendedDataSquare> {
+        self.extend_with_config(config, &no_progress)
+    }
+
+    // Like `extend_with`, reporting progress as `config` allows
+    pub fn extend_with_config(
+        &mut self,
+        config: &ExtendConfig,
+        progress: &dyn ProgressSink,
+    ) -> Result<ExtendedDataSquare> {
+        let boundaries = PhaseBoundaries(progress);
+        let progress: &dyn ProgressSink = match config.deterministic && cfg!(feature = "parallel") {
+            true => &boundaries,
+            false => progress,
+        };
+
+        #[cfg(feature = "parallel")]
+        if let Some(threads) = config.threads {
+            let pool = rayon::ThreadPoolBuilder::new()
+                .num_threads(threads)
+                .build()?;
+            return pool.install(|| self.extend_inner(&NEVER, progress));
+        }
+
+        self.extend_inner(&NEVER, progress)
+    }
+
     // Extend the data square using Reed-Solomon encoding
     pub fn extend(&mut self) -> Result<ExtendedDataSquare> {
-        let q3_cols = self.create_q3()?;
-        let x_tree = self.create_tree(transpose(&self.q1_cols), transpose(&q3_cols))?;
+        self.extend_cancelable(&NEVER)
+    }
+
+    // Like `extend`, but returns `Cancelled` soon after `cancel` is set
+    pub fn extend_cancelable(&mut self, cancel: &AtomicBool) -> Result<ExtendedDataSquare> {
+        self.extend_inner(cancel, &no_progress)
+    }
+
+    // Like `extend`, reporting each phase's progress to `progress`. Updates
+    // within a phase may arrive out of order, see `extend_with_config`.
+    pub fn extend_with_progress(
+        &mut self,
+        progress: &dyn ProgressSink,
+    ) -> Result<ExtendedDataSquare> {
+        self.extend_inner(&NEVER, progress)
+    }
+
+    fn extend_inner(
+        &mut self,
+        cancel: &AtomicBool,
+        progress: &dyn ProgressSink,
+    ) -> Result<ExtendedDataSquare> {
+        let mut stats = ExtendStats::default();
+        let allocs_before = allocation_count();
+        let mut scratch = std::mem::take(&mut self.scratch);
+
+        let start = Instant::now();
+        let phase = PhaseProgress::start(progress, ExtendPhase::EncodeColumns, self.width);
+        let q3_cols = self.encode_all(&self.q1_cols, cancel, &phase)?;
+        phase.finish();
+        stats.encode_ms += elapsed_ms(start);
+
+        check_cancel(cancel)?;
+        let start = Instant::now();
+        let phase = PhaseProgress::start(progress, ExtendPhase::CommitColumns, 1);
+        let mut cols: Vec<Vec<Felt>> = self
+            .q1_cols
+            .iter()
+            .zip(&q3_cols)
+            .map(|(q1, q3)| [q1.as_slice(), q3].concat())
+            .collect();
+        // hashed once, reused by the axis trees
+        let mut leaves = leaf_cols(&cols);
+        let x_tree = x_tree(&cols, &leaves, self.leaf_strategy);
+        phase.finish();
+        stats.hash_ms += elapsed_ms(start);
         let root = match x_tree.root() {
             Some(r) => r,
             None => bail!("failed to get tree commitment"),
         };
 
+        let start = Instant::now();
         let dr = self.create_dr(&root);
 
-        let mut q1_dr_cols = self.q1_cols.clone();
-        let mut q3_dr_cols = q3_cols.clone();
+        let mut q1_dr_cols = scratch.copy(&self.q1_cols);
+        let mut q3_dr_cols = scratch.copy(&q3_cols);
         self.multiply_dr(&mut q1_dr_cols, &dr);
         self.multiply_dr(&mut q3_dr_cols, &dr);
+        stats.dr_ms += elapsed_ms(start);
 
-        let q2_rows = self.extend_quadrant(&q1_dr_cols)?;
-        let q4_rows = self.extend_quadrant(&q3_dr_cols)?;
+        let start = Instant::now();
+        let phase = PhaseProgress::start(progress, ExtendPhase::EncodeRows, 2 * self.width);
+        let q2_rows = self.extend_quadrant(&q1_dr_cols, &mut scratch, cancel, &phase)?;
+        let q4_rows = self.extend_quadrant(&q3_dr_cols, &mut scratch, cancel, &phase)?;
+        scratch.recycle(q3_dr_cols);
+        phase.finish();
+        stats.encode_ms += elapsed_ms(start);
 
-        let y_tree = self.create_tree(q1_dr_cols.clone(), transpose(&q2_rows))?;
+        check_cancel(cancel)?;
+        let start = Instant::now();
+        let phase = PhaseProgress::start(progress, ExtendPhase::CommitRows, 1);
+        let right_cols: Vec<Vec<Felt>> = transpose(&q2_rows)
+            .into_iter()
+            .zip(transpose(&q4_rows))
+            .map(|(q2, q4)| [q2, q4].concat())
+            .collect();
+        let right_leaves = leaf_cols(&right_cols);
+        let y_tree = y_tree(&q1_dr_cols, &right_cols, &right_leaves, self.leaf_strategy);
+        scratch.recycle(q1_dr_cols);
+        cols.extend(right_cols);
+        leaves.extend(right_leaves);
+        phase.finish();
+        stats.hash_ms += elapsed_ms(start);
 
-        let eds = ExtendedDataSquare::from_cols(
-            self.q1_cols.clone(),
-            transpose(&q2_rows),
-            q3_cols,
-            transpose(&q4_rows),
-            dr,
-            x_tree,
-            y_tree,
-        );
+        let start = Instant::now();
+        let phase = PhaseProgress::start(progress, ExtendPhase::AxisTrees, 1);
+        let mut eds = ExtendedDataSquare::from_cols(cols, leaves, dr, x_tree, y_tree)?;
+        phase.finish();
+        stats.tree_ms += elapsed_ms(start);
+
+        stats.allocs = allocs_before
+            .zip(allocation_count())
+            .map(|(before, after)| after - before);
+        eds.stats = Some(stats);
+        eds.context = self.context;
+        eds.leaf_strategy = self.leaf_strategy;
+        self.scratch = scratch;
 
+        check_cancel(cancel)?;
         Ok(eds)
     }
 
@@ -108,13 +1132,40 @@ impl DataSquare {
     }
 
     pub fn create_q3(&self) -> Result<Vec<Vec<Felt>>> {
-        let mut q3: Vec<Vec<Felt>> = Vec::new();
-        for col in self.q1_cols.iter() {
-            let extended_col = col.clone();
-            let new_col = self.encoder.encode(extended_col)?;
-            q3.push(new_col);
+        let phase = PhaseProgress::start(&no_progress, ExtendPhase::EncodeColumns, self.width);
+        self.encode_all(&self.q1_cols, &NEVER, &phase)
+    }
+
+    // Encodes every row (or column) in `axes`, checking `cancel` before each one
+    fn encode_all(
+        &self,
+        axes: &[Vec<Felt>],
+        cancel: &AtomicBool,
+        progress: &PhaseProgress,
+    ) -> Result<Vec<Vec<Felt>>> {
+        let encoded = map_ordered(axes, |axis| -> Result<Vec<Felt>> {
+            check_cancel(cancel)?;
+            let mut encoded = Vec::new();
+            self.encode_into(axis, &mut encoded)?;
+            progress.step();
+            Ok(encoded)
+        });
+        encoded.into_iter().collect()
+    }
+
+    // Writes the parity of `axis` to `out`, `axis.len()` felts. The encoder
+    // takes the message by value and returns only the parity, so `out` holds
+    // the one copy of the axis it needs and is then replaced by the parity.
+    pub(crate) fn encode_into(&self, axis: &[Felt], out: &mut Vec<Felt>) -> Result<()> {
+        out.clear();
+        // parity of zeros is zeros, padding doesn't need to hit the encoder
+        if is_zero(axis) {
+            out.resize(axis.len(), Felt::default());
+            return Ok(());
         }
-        Ok(q3)
+        out.extend_from_slice(axis);
+        *out = self.encoder.encode(std::mem::take(out))?;
+        Ok(())
     }
 
     pub fn create_tree(
@@ -122,63 +1173,336 @@ impl DataSquare {
         matrix_1: &[Vec<Felt>],
         matrix_2: &[Vec<Felt>],
     ) -> Result<MerkleTree<Sha256>> {
-        // OH: "this is kinda retarded, we are already looking at all the elements when we transpose, and then we flatten anyways"
-        let repr = matrix_1.iter().chain(matrix_2.iter()).collect::<Vec<_>>();
+        Ok(build_tree(matrix_1, matrix_2, self.leaf_strategy))
+    }
 
-        let merkle_leaves: Vec<[u8; 32]> = repr
-            .iter()
-            .flatten()
-            .map(|elem| Sha256::hash(elem.val().to_be_bytes().as_ref()))
-            .collect();
+    pub fn create_dr(&self, tree_commitment: &[u8; 32]) -> Vec<Felt> {
+        derive_dr_with_context(tree_commitment, self.context.as_ref(), self.width)
+    }
 
-        Ok(MerkleTree::<Sha256>::from_leaves(&merkle_leaves))
+    pub(crate) fn extend_quadrant(
+        &self,
+        column_data: &[Vec<Felt>],
+        scratch: &mut ScratchPool,
+        cancel: &AtomicBool,
+        progress: &PhaseProgress,
+    ) -> Result<Vec<Vec<Felt>>> {
+        let rows = scratch.transpose(column_data);
+        let extended_quadrant = self.encode_all(&rows, cancel, progress);
+        scratch.recycle(rows);
+        extended_quadrant
     }
+}
 
-    pub fn create_dr(&self, tree_commitment: &[u8; 32]) -> Vec<Felt> {
-        let mut dr: Vec<Felt> = Vec::new();
-        for dr_i in 0..self.width {
-            let mut hasher = sha2::Sha256::new();
-            hasher.update(tree_commitment);
-            hasher.update(dr_i.to_be_bytes());
-            let digest = hasher.finalize();
-            // truncate digest to 128 bits to make it into a felt
-            // todo: don't make so nested
-            dr.push(Felt::new(u128::from_be_bytes(
-                digest[0..16].try_into().unwrap(),
-            )));
-        }
-        dr
+// Default cap on the original width, see `Limits`
+pub const MAX_WIDTH: usize = 1 << 10;
+
+// Data root of an all-padding square of the given original width. Every row
+// and column of the extension is zero, so the root only depends on the width
+// and is computed straight from the zero axis root, then cached.
+pub fn empty_data_root(width: usize, share_size: usize) -> Result<[u8; 32]> {
+    static ROOTS: Mutex<BTreeMap<usize, [u8; 32]>> = Mutex::new(BTreeMap::new());
+
+    if !width.is_power_of_two() {
+        bail!("width must be a power of two, got {}", width);
+    }
+    if share_size != SHARE_SIZE {
+        bail!(
+            "felt squares use {}-byte shares, got {}",
+            SHARE_SIZE,
+            share_size
+        );
+    }
+
+    let mut roots = ROOTS.lock().unwrap_or_else(|e| e.into_inner());
+    if let Some(root) = roots.get(&width) {
+        return Ok(*root);
+    }
+
+    let axis_root = axis_root(&vec![Felt::default(); 2 * width]);
+    let data_leaves = vec![axis_root; 4 * width];
+    let Some(root) = MerkleTree::<Sha256>::from_leaves(&data_leaves).root() else {
+        bail!("failed to get data root");
+    };
+    roots.insert(width, root);
+
+    Ok(root)
+}
+
+pub fn derive_dr(tree_commitment: &[u8; 32], width: usize) -> Vec<Felt> {
+    derive_dr_with_context(tree_commitment, None, width)
+}
+
+// Like `derive_dr`, with the context digest absorbed right after the commitment
+pub fn derive_dr_with_context(
+    tree_commitment: &[u8; 32],
+    context: Option<&[u8; 32]>,
+    width: usize,
+) -> Vec<Felt> {
+    let mut dr = vec![Felt::default(); width];
+    derive_dr_into(tree_commitment, context, &mut dr);
+    dr
+}
+
+// Multiplies each element of a row with the dr entry of its column
+pub(crate) fn scale_by_dr(row: &[Felt], dr: &[Felt]) -> Vec<Felt> {
+    row.iter()
+        .zip(dr.iter())
+        .map(|(elem, dr_i)| *elem * *dr_i)
+        .collect()
+}
+
+// Maps `f` over `items`, in parallel with the `parallel` feature. Results are
+// always collected in input order, so the output never depends on the thread count.
+pub(crate) fn map_ordered<T: Sync, U: Send>(
+    items: &[T],
+    f: impl Fn(&T) -> U + Sync + Send,
+) -> Vec<U> {
+    #[cfg(feature = "parallel")]
+    {
+        use rayon::prelude::*;
+        items.par_iter().map(f).collect()
     }
 
-    pub(crate) fn extend_quadrant(&self, column_data: &[Vec<Felt>]) -> Result<Vec<Vec<Felt>>> {
-        let mut extended_quadrant: Vec<Vec<Felt>> = Vec::new();
-        for row in transpose(column_data).iter() {
-            let encoded = self.encoder.encode(row.clone())?;
-            extended_quadrant.push(encoded)
+    #[cfg(not(feature = "parallel"))]
+    items.iter().map(f).collect()
+}
+
+pub(crate) fn build_tree(
+    matrix_1: &[Vec<Felt>],
+    matrix_2: &[Vec<Felt>],
+    leaf_strategy: LeafStrategy,
+) -> MerkleTree<Sha256> {
+    // OH: "this is kinda retarded, we are already looking at all the elements when we transpose, and then we flatten anyways"
+    let repr = matrix_1.iter().chain(matrix_2.iter()).collect::<Vec<_>>();
+
+    let merkle_leaves: Vec<[u8; 32]> = match leaf_strategy {
+        LeafStrategy::PerFelt => {
+            map_ordered(&repr, |axis| axis.iter().map(leaf_hash).collect::<Vec<_>>())
+                .into_iter()
+                .flatten()
+                .collect()
+        }
+        _ => {
+            let batch = leaf_strategy.felts_per_leaf(repr.first().map_or(0, |axis| axis.len()));
+            map_ordered(&repr, |axis| {
+                axis.chunks(batch).map(share_leaf_hash).collect::<Vec<_>>()
+            })
+            .into_iter()
+            .flatten()
+            .collect()
         }
+    };
+
+    MerkleTree::<Sha256>::from_leaves(&merkle_leaves)
+}
 
-        Ok(extended_quadrant)
+// Leaf hashes of every cell of `cols`, column-major. Each cell is hashed once
+// and the hashes are shared by every tree it is a leaf of: q1 and q3 are
+// leaves of the x_tree, q2 of the y_tree, and all of them of the axis trees.
+pub(crate) fn leaf_cols(cols: &[Vec<Felt>]) -> Vec<Vec<[u8; 32]>> {
+    map_ordered(cols, |col| col.iter().map(leaf_hash).collect())
+}
+
+// x_tree: rows of q1 followed by rows of q3, from the left half's columns and
+// their leaves
+pub(crate) fn x_tree(
+    left_cols: &[Vec<Felt>],
+    left_leaves: &[Vec<[u8; 32]>],
+    leaf_strategy: LeafStrategy,
+) -> MerkleTree<Sha256> {
+    match leaf_strategy {
+        LeafStrategy::PerFelt => {
+            let rows = left_leaves.first().map(Vec::len).unwrap_or_default();
+            let leaves: Vec<[u8; 32]> = (0..rows)
+                .flat_map(|row| left_leaves.iter().map(move |col| col[row]))
+                .collect();
+            MerkleTree::<Sha256>::from_leaves(&leaves)
+        }
+        _ => build_tree(&transpose(left_cols), &[], leaf_strategy),
     }
 }
 
-pub fn transpose(matrix: &[Vec<Felt>]) -> Vec<Vec<Felt>> {
-    let mut transposed = Vec::new();
-    for i in 0..matrix.len() {
-        let mut row = Vec::new();
-        for col in matrix.iter() {
-            row.push(col[i]);
+// y_tree: dr-scaled columns of q1 followed by columns of q2, the top halves of
+// the right half's columns
+pub(crate) fn y_tree(
+    q1_dr_cols: &[Vec<Felt>],
+    right_cols: &[Vec<Felt>],
+    right_leaves: &[Vec<[u8; 32]>],
+    leaf_strategy: LeafStrategy,
+) -> MerkleTree<Sha256> {
+    let half = q1_dr_cols.len();
+    match leaf_strategy {
+        LeafStrategy::PerFelt => {
+            let mut leaves: Vec<[u8; 32]> = leaf_cols(q1_dr_cols).concat();
+            leaves.extend(right_leaves.iter().flat_map(|col| &col[..half]));
+            MerkleTree::<Sha256>::from_leaves(&leaves)
         }
-        transposed.push(row);
+        _ => {
+            let q2_cols: Vec<Vec<Felt>> =
+                right_cols.iter().map(|col| col[..half].to_vec()).collect();
+            build_tree(q1_dr_cols, &q2_cols, leaf_strategy)
+        }
+    }
+}
+
+// Leaf over a whole share: sha256 of its felts' big-endian bytes
+pub fn share_leaf_hash(share: &[Felt]) -> [u8; 32] {
+    let mut hasher = sha2::Sha256::new();
+    for elem in share {
+        hasher.update(elem.val().to_be_bytes());
+    }
+    hasher.finalize().into()
+}
+
+pub(crate) fn is_zero(axis: &[Felt]) -> bool {
+    axis.iter().all(|elem| *elem == Felt::default())
+}
+
+// Merkle tree over a single row or column of the extended square
+pub fn axis_tree(axis: &[Felt]) -> MerkleTree<Sha256> {
+    // all-zero axes share one tree per width
+    static ZERO_TREES: Mutex<BTreeMap<usize, MerkleTree<Sha256>>> = Mutex::new(BTreeMap::new());
+
+    if is_zero(axis) {
+        let mut trees = ZERO_TREES.lock().unwrap_or_else(|e| e.into_inner());
+        return trees
+            .entry(axis.len())
+            .or_insert_with(|| build_axis_tree(axis))
+            .clone();
+    }
+    build_axis_tree(axis)
+}
+
+// Like `axis_tree`, with the leaves already hashed
+fn axis_tree_from_leaves(axis: &[Felt], leaves: &[[u8; 32]]) -> MerkleTree<Sha256> {
+    if is_zero(axis) {
+        return axis_tree(axis);
     }
-    transposed
+    MerkleTree::<Sha256>::from_leaves(leaves)
+}
+
+fn build_axis_tree(axis: &[Felt]) -> MerkleTree<Sha256> {
+    let leaves: Vec<[u8; 32]> = axis.iter().map(leaf_hash).collect();
+    MerkleTree::<Sha256>::from_leaves(&leaves)
 }
 
-pub fn transpose_and_flatten(matrix: &[Vec<Felt>]) -> Vec<Felt> {
-    let mut transposed = Vec::new();
-    for i in 0..matrix.len() {
-        for col in matrix.iter() {
-            transposed.push(col[i]);
+pub fn axis_root(axis: &[Felt]) -> [u8; 32] {
+    axis_tree(axis).root().unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    fn square(width: usize) -> DataSquare {
+        let cols = (0..width)
+            .map(|c| {
+                (0..width)
+                    .map(|r| Felt::new(((c * width + r) as u128 + 1) * 0x9e37_79b9_7f4a_7c15))
+                    .collect()
+            })
+            .collect();
+        DataSquare::new(cols).unwrap()
+    }
+
+    // trivially true without the `parallel` feature, run with `--features parallel`
+    #[test]
+    #[cfg_attr(not(feature = "parallel"), ignore = "needs the parallel feature")]
+    fn roots_do_not_depend_on_thread_count() {
+        let sequential = square(8)
+            .extend_with(&ExtendConfig {
+                threads: Some(1),
+                deterministic: true,
+            })
+            .unwrap();
+        assert_eq!(
+            square(8).extend().unwrap().data_root().unwrap(),
+            sequential.data_root().unwrap()
+        );
+
+        for threads in [2, 3, 8] {
+            let parallel = square(8)
+                .extend_with(&ExtendConfig {
+                    threads: Some(threads),
+                    deterministic: true,
+                })
+                .unwrap();
+            assert_eq!(
+                parallel.data_root().unwrap(),
+                sequential.data_root().unwrap()
+            );
+            assert_eq!(parallel.x_root(), sequential.x_root());
+            assert_eq!(parallel.y_root(), sequential.y_root());
+        }
+    }
+
+    #[test]
+    #[cfg_attr(not(feature = "parallel"), ignore = "needs the parallel feature")]
+    fn deterministic_progress_does_not_depend_on_thread_count() {
+        let reports = |threads| {
+            let reports = Mutex::new(Vec::new());
+            let sink = |phase, fraction| reports.lock().unwrap().push((phase, fraction));
+            let config = ExtendConfig {
+                threads: Some(threads),
+                deterministic: true,
+            };
+            square(8).extend_with_config(&config, &sink).unwrap();
+            reports.into_inner().unwrap()
+        };
+
+        let sequential = reports(1);
+        assert!(sequential
+            .iter()
+            .all(|&(_, fraction)| fraction == 0.0 || fraction == 1.0));
+        for threads in [2, 3, 8] {
+            assert_eq!(reports(threads), sequential);
         }
     }
-    transposed
+
+    #[test]
+    fn try_new_rejects_misplaced_tail_padding() {
+        use crate::namespace::namespaced_share;
+        let share = |ns: Namespace| namespaced_share(ns, [0; 8]);
+        let (data, pad) = (
+            share(Namespace::from_u64(1)),
+            share(Namespace::TAIL_PADDING),
+        );
+        // column-major, so (0, 1) is the second column's first share
+        assert!(DataSquare::try_new(vec![vec![data, data], vec![data, pad]]).is_ok());
+        assert!(DataSquare::try_new(vec![vec![data, pad], vec![data, pad]]).is_ok());
+        assert!(DataSquare::try_new(vec![vec![data, pad], vec![pad, data]]).is_err());
+        assert!(DataSquare::try_new(vec![vec![data, data], vec![pad, data]]).is_err());
+
+        assert!(DataSquare::try_from_shares(&vec![vec![0; SHARE_SIZE]; 3]).is_err());
+        assert!(DataSquare::try_from_shares(&vec![vec![0; SHARE_SIZE + 1]; 4]).is_err());
+        assert!(DataSquare::try_from_shares(&vec![vec![0; SHARE_SIZE]; 4]).is_ok());
+    }
+
+    #[test]
+    fn validate_rejects_wrong_dr() {
+        let eds = square(4).extend().unwrap();
+        let x_root = eds.x_root().unwrap();
+        let mut hasher = sha2::Sha256::new();
+        hasher.update(x_root);
+        hasher.update(3u64.to_be_bytes());
+        let digest = hasher.finalize();
+        assert_eq!(
+            dr_element(&x_root, None, 3),
+            Felt::new(u128::from_be_bytes(digest[..16].try_into().unwrap()))
+        );
+
+        let mut short = square(4).extend().unwrap();
+        short.dr.pop();
+        assert!(short.validate().is_err());
+
+        let mut wrong = eds;
+        wrong.dr[1] += Felt::new(1);
+        let err = wrong.validate().unwrap_err();
+        assert_eq!(
+            err.downcast_ref::<Rejection>(),
+            Some(&Rejection::DrMismatch { index: 1 })
+        );
+    }
 }
@@ -0,0 +1,262 @@
+// Version 1 of the public API. The types re-exported here and the byte
+// formats below are frozen: a chain can commit to them and keep verifying
+// the same bytes across releases, whatever happens to the modules behind
+// them. Any change to a format goes into a new `v2` next to this one.
+//
+// All integers are big-endian, felts are 16 bytes, hashes 32.
+//
+// Share proof:
+//   format (1) = 1 | width (4) | row (4) | col (4) | share (16) | row root (32)
+//   | cell path len (1) | cell path | root path len (1) | root path
+// Certificate:
+//   format (1) = 1 | data root (32) | width (4) | sample count (4)
+//   | samples, each a share proof prefixed by its length (4)
+//   | producer (1), 0 or 1, then if 1:
+//     data root (32) | scheme (1) | key len (2) | key | sig len (2) | sig
+// Square: the `wire` layout, written with square version 2 and read back
+// from versions 1 and 2 only.
+//
+// A lenient decode (see `DecodeMode`) skips bytes appended after a share
+// proof or a certificate; the format byte always has to match.
+use anyhow::{bail, Result};
+
+pub use crate::certificate::ZodaCertificate;
+use crate::decode::skip_trailing;
+pub use crate::decode::DecodeMode;
+pub use crate::felt::{Felt, Share, SHARE_SIZE};
+pub use crate::namespace::Namespace;
+pub use crate::proof::{Axis, CellProof, RootProof, ShareProof};
+pub use crate::signature::{Signature, SignatureScheme, SignedRoot};
+#[cfg(feature = "prover")]
+pub use crate::tree::{DataSquare, ExtendedDataSquare};
+
+pub const SHARE_PROOF_FORMAT: u8 = 1;
+pub const CERTIFICATE_FORMAT: u8 = 1;
+#[cfg(feature = "prover")]
+pub const SQUARE_VERSION: u8 = 2;
+
+pub fn encode_share_proof(proof: &ShareProof) -> Vec<u8> {
+    let mut out = Vec::with_capacity(1 + 12 + 16 + 32 + 2 + proof.byte_len());
+    out.push(SHARE_PROOF_FORMAT);
+    out.extend_from_slice(&(proof.cell_proof.width as u32).to_be_bytes());
+    out.extend_from_slice(&(proof.row() as u32).to_be_bytes());
+    out.extend_from_slice(&(proof.col() as u32).to_be_bytes());
+    out.extend_from_slice(&proof.share.val().to_be_bytes());
+    out.extend_from_slice(&proof.row_root);
+    for path in [&proof.cell_proof.path, &proof.root_proof.path] {
+        out.push(path.len() as u8);
+        path.iter().for_each(|hash| out.extend_from_slice(hash));
+    }
+    out
+}
+
+pub fn decode_share_proof(bytes: &[u8]) -> Result<ShareProof> {
+    Ok(decode_share_proof_with_mode(bytes, DecodeMode::Strict)?.0)
+}
+
+// Also returns the warnings for whatever a lenient decode skipped
+pub fn decode_share_proof_with_mode(
+    bytes: &[u8],
+    mode: DecodeMode,
+) -> Result<(ShareProof, Vec<String>)> {
+    let mut warnings = Vec::new();
+    let proof = read_share_proof(bytes, mode, &mut warnings)?;
+    Ok((proof, warnings))
+}
+
+fn read_share_proof(
+    bytes: &[u8],
+    mode: DecodeMode,
+    warnings: &mut Vec<String>,
+) -> Result<ShareProof> {
+    let mut reader = Reader(bytes);
+    let format = reader.u8()?;
+    if format != SHARE_PROOF_FORMAT {
+        bail!("unsupported share proof format {}", format);
+    }
+    let width = reader.u32()? as usize;
+    let row = reader.u32()? as usize;
+    let col = reader.u32()? as usize;
+    let share = Felt::new(u128::from_be_bytes(reader.array()?));
+    let row_root = reader.array()?;
+    let cell_path = reader.path()?;
+    let root_path = reader.path()?;
+    skip_trailing(mode, reader.0.len(), warnings)?;
+
+    Ok(ShareProof {
+        share,
+        cell_proof: CellProof {
+            axis: Axis::Row,
+            row,
+            col,
+            width,
+            path: cell_path,
+        },
+        row_root,
+        root_proof: RootProof {
+            axis: Axis::Row,
+            index: row,
+            width,
+            path: root_path,
+        },
+    })
+}
+
+pub fn encode_certificate(certificate: &ZodaCertificate) -> Vec<u8> {
+    let mut out = vec![CERTIFICATE_FORMAT];
+    encode_certificate_body(certificate, &mut out);
+    out
+}
+
+// Everything after the format byte, shared with later formats
+pub(crate) fn encode_certificate_body(certificate: &ZodaCertificate, out: &mut Vec<u8>) {
+    out.extend_from_slice(&certificate.data_root);
+    out.extend_from_slice(&(certificate.width as u32).to_be_bytes());
+    out.extend_from_slice(&(certificate.samples.len() as u32).to_be_bytes());
+    for sample in &certificate.samples {
+        let proof = encode_share_proof(sample);
+        out.extend_from_slice(&(proof.len() as u32).to_be_bytes());
+        out.extend_from_slice(&proof);
+    }
+
+    match &certificate.producer {
+        None => out.push(0),
+        Some(producer) => {
+            out.push(1);
+            out.extend_from_slice(&producer.data_root);
+            let signature = &producer.signature;
+            out.push(match signature.scheme {
+                SignatureScheme::Ed25519 => 1,
+                SignatureScheme::Secp256k1 => 2,
+            });
+            for field in [&signature.public_key, &signature.signature] {
+                out.extend_from_slice(&(field.len() as u16).to_be_bytes());
+                out.extend_from_slice(field);
+            }
+        }
+    }
+}
+
+// Only parses, `ZodaCertificate::verify` checks the contents
+pub fn decode_certificate(bytes: &[u8]) -> Result<ZodaCertificate> {
+    Ok(decode_certificate_with_mode(bytes, DecodeMode::Strict)?.0)
+}
+
+// Also returns the warnings for whatever a lenient decode skipped
+pub fn decode_certificate_with_mode(
+    bytes: &[u8],
+    mode: DecodeMode,
+) -> Result<(ZodaCertificate, Vec<String>)> {
+    match bytes.first() {
+        Some(&CERTIFICATE_FORMAT) => {
+            let mut warnings = Vec::new();
+            let certificate = decode_certificate_body(&bytes[1..], mode, &mut warnings)?;
+            Ok((certificate, warnings))
+        }
+        Some(format) => bail!("unsupported certificate format {}", format),
+        None => bail!("unexpected end of input"),
+    }
+}
+
+pub(crate) fn decode_certificate_body(
+    bytes: &[u8],
+    mode: DecodeMode,
+    warnings: &mut Vec<String>,
+) -> Result<ZodaCertificate> {
+    let mut reader = Reader(bytes);
+    let data_root = reader.array()?;
+    let width = reader.u32()? as usize;
+
+    let count = reader.u32()? as usize;
+    // every sample takes at least its length prefix
+    if count > reader.0.len() / 4 {
+        bail!("{} samples don't fit in {} bytes", count, reader.0.len());
+    }
+    let mut samples = Vec::with_capacity(count);
+    for _ in 0..count {
+        let len = reader.u32()? as usize;
+        samples.push(read_share_proof(reader.take(len)?, mode, warnings)?);
+    }
+
+    let producer = match reader.u8()? {
+        0 => None,
+        1 => {
+            let data_root = reader.array()?;
+            let scheme = match reader.u8()? {
+                1 => SignatureScheme::Ed25519,
+                2 => SignatureScheme::Secp256k1,
+                id => bail!("unknown signature scheme {}", id),
+            };
+            let public_key = reader.bytes()?;
+            let signature = reader.bytes()?;
+            Some(SignedRoot {
+                data_root,
+                signature: Signature {
+                    scheme,
+                    public_key,
+                    signature,
+                },
+            })
+        }
+        flag => bail!("invalid producer flag {}", flag),
+    };
+    skip_trailing(mode, reader.0.len(), warnings)?;
+
+    Ok(ZodaCertificate {
+        data_root,
+        width,
+        samples,
+        producer,
+    })
+}
+
+#[cfg(feature = "prover")]
+pub fn encode_square(eds: &ExtendedDataSquare) -> Vec<u8> {
+    eds.to_bytes_with_version(SQUARE_VERSION)
+}
+
+#[cfg(feature = "prover")]
+pub fn decode_square(bytes: &[u8]) -> Result<ExtendedDataSquare> {
+    // the version follows the 4 byte magic
+    match bytes.get(4) {
+        Some(1 | 2) => ExtendedDataSquare::from_bytes(bytes),
+        Some(version) => bail!("square version {} is not part of v1", version),
+        None => bail!("unexpected end of input"),
+    }
+}
+
+struct Reader<'a>(&'a [u8]);
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, n: usize) -> Result<&'a [u8]> {
+        if self.0.len() < n {
+            bail!("unexpected end of input");
+        }
+        let (head, tail) = self.0.split_at(n);
+        self.0 = tail;
+        Ok(head)
+    }
+
+    fn array<const N: usize>(&mut self) -> Result<[u8; N]> {
+        Ok(self.take(N)?.try_into()?)
+    }
+
+    fn u8(&mut self) -> Result<u8> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn u32(&mut self) -> Result<u32> {
+        Ok(u32::from_be_bytes(self.array()?))
+    }
+
+    fn path(&mut self) -> Result<Vec<[u8; 32]>> {
+        let len = self.u8()?;
+        (0..len).map(|_| self.array()).collect()
+    }
+
+    // length-prefixed with 2 bytes
+    fn bytes(&mut self) -> Result<Vec<u8>> {
+        let len = u16::from_be_bytes(self.array()?) as usize;
+        Ok(self.take(len)?.to_vec())
+    }
+}
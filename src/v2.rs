@@ -0,0 +1,53 @@
+// Version 2 of the public API, frozen like `v1`. Squares and share proofs are
+// unchanged from `v1`; certificates are bound to the parameters they were
+// made under.
+//
+// Certificate:
+//   format (1) = 2 | params digest (32) | the `v1` certificate after its
+//   format byte
+// The digest is `ZodaParams::digest` of the square's parameters.
+use anyhow::{bail, Result};
+
+pub use crate::v1::{
+    decode_share_proof, decode_share_proof_with_mode, encode_share_proof, Axis, CellProof,
+    DecodeMode, Felt, Namespace, RootProof, Share, ShareProof, Signature, SignatureScheme,
+    SignedRoot, ZodaCertificate, SHARE_PROOF_FORMAT, SHARE_SIZE,
+};
+#[cfg(feature = "prover")]
+pub use crate::v1::{decode_square, encode_square, DataSquare, ExtendedDataSquare, SQUARE_VERSION};
+
+use crate::v1::{decode_certificate_body, encode_certificate_body};
+
+pub const CERTIFICATE_FORMAT: u8 = 2;
+
+pub fn encode_certificate(certificate: &ZodaCertificate, params_digest: &[u8; 32]) -> Vec<u8> {
+    let mut out = vec![CERTIFICATE_FORMAT];
+    out.extend_from_slice(params_digest);
+    encode_certificate_body(certificate, &mut out);
+    out
+}
+
+// The certificate and its params digest. Only parses, see `VersionedVerifier`
+pub fn decode_certificate(bytes: &[u8]) -> Result<(ZodaCertificate, [u8; 32])> {
+    let (certificate, params_digest, _) = decode_certificate_with_mode(bytes, DecodeMode::Strict)?;
+    Ok((certificate, params_digest))
+}
+
+// Also returns the warnings for whatever a lenient decode skipped
+pub fn decode_certificate_with_mode(
+    bytes: &[u8],
+    mode: DecodeMode,
+) -> Result<(ZodaCertificate, [u8; 32], Vec<String>)> {
+    match bytes.first() {
+        Some(&CERTIFICATE_FORMAT) => {}
+        Some(format) => bail!("unsupported certificate format {}", format),
+        None => bail!("unexpected end of input"),
+    }
+    if bytes.len() < 33 {
+        bail!("unexpected end of input");
+    }
+    let params_digest = bytes[1..33].try_into()?;
+    let mut warnings = Vec::new();
+    let certificate = decode_certificate_body(&bytes[33..], mode, &mut warnings)?;
+    Ok((certificate, params_digest, warnings))
+}
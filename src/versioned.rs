@@ -0,0 +1,154 @@
+// Verifying certificates across upgrades. Nodes keep accepting certificates
+// encoded by the previous release, each checked by the rules of the format it
+// was encoded in.
+use anyhow::{bail, Result};
+
+use crate::certificate::ZodaCertificate;
+use crate::{v1, v2};
+
+// The public API version a certificate was encoded under, its first byte
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum ZodaVersion {
+    V1,
+    V2,
+}
+
+impl ZodaVersion {
+    pub const CURRENT: Self = Self::V2;
+
+    pub fn id(&self) -> u8 {
+        match self {
+            Self::V1 => v1::CERTIFICATE_FORMAT,
+            Self::V2 => v2::CERTIFICATE_FORMAT,
+        }
+    }
+
+    pub fn from_id(id: u8) -> Result<Self> {
+        match id {
+            v1::CERTIFICATE_FORMAT => Ok(Self::V1),
+            v2::CERTIFICATE_FORMAT => Ok(Self::V2),
+            _ => bail!("unknown zoda version {}", id),
+        }
+    }
+
+    // Version of an encoded certificate
+    pub fn of(bytes: &[u8]) -> Result<Self> {
+        match bytes.first() {
+            Some(&id) => Self::from_id(id),
+            None => bail!("unexpected end of input"),
+        }
+    }
+}
+
+// Encodes under the current version
+pub fn encode_certificate(certificate: &ZodaCertificate, params_digest: &[u8; 32]) -> Vec<u8> {
+    v2::encode_certificate(certificate, params_digest)
+}
+
+// Accepts certificates of every version from `min_version` on. V1
+// certificates don't say which parameters they were made under, so only
+// their proofs are checked; raise `min_version` to V2 once no node produces
+// them anymore.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VersionedVerifier {
+    // `ZodaParams::digest` of the parameters in use
+    params_digest: [u8; 32],
+    min_version: ZodaVersion,
+}
+
+impl VersionedVerifier {
+    pub fn new(params_digest: [u8; 32]) -> Self {
+        Self {
+            params_digest,
+            min_version: ZodaVersion::V1,
+        }
+    }
+
+    pub fn with_min_version(mut self, min_version: ZodaVersion) -> Self {
+        self.min_version = min_version;
+        self
+    }
+
+    // Decodes and verifies an encoded certificate, returning it with the
+    // version it was encoded under
+    pub fn verify(&self, bytes: &[u8]) -> Result<(ZodaCertificate, ZodaVersion)> {
+        let version = ZodaVersion::of(bytes)?;
+        if version < self.min_version {
+            bail!(
+                "certificate version {:?} is older than the minimum {:?}",
+                version,
+                self.min_version
+            );
+        }
+
+        let certificate = match version {
+            ZodaVersion::V1 => v1::decode_certificate(bytes)?,
+            ZodaVersion::V2 => {
+                let (certificate, params_digest) = v2::decode_certificate(bytes)?;
+                if params_digest != self.params_digest {
+                    bail!("certificate was made under other parameters");
+                }
+                certificate
+            }
+        };
+        certificate.verify()?;
+        Ok((certificate, version))
+    }
+}
+
+#[cfg(all(test, feature = "prover"))]
+mod tests {
+    use super::*;
+    use crate::params::ZodaParams;
+    use crate::tree::{DataSquare, Felt};
+
+    fn certificate() -> ZodaCertificate {
+        let cols = (0..4)
+            .map(|c| (0..4).map(|r| Felt::new(c * 4 + r + 1)).collect())
+            .collect();
+        let eds = DataSquare::new(cols).unwrap().extend().unwrap();
+        ZodaCertificate::new(&eds, &[(0, 1), (5, 2), (7, 7)]).unwrap()
+    }
+
+    #[test]
+    fn verifies_every_version() {
+        assert_eq!(ZodaVersion::CURRENT, ZodaVersion::V2);
+        let digest = ZodaParams::new(4).digest();
+        let verifier = VersionedVerifier::new(digest);
+        let certificate = certificate();
+
+        let v1 = v1::encode_certificate(&certificate);
+        assert_eq!(
+            verifier.verify(&v1).unwrap(),
+            (certificate.clone(), ZodaVersion::V1)
+        );
+        let v2 = encode_certificate(&certificate, &digest);
+        assert_eq!(
+            verifier.verify(&v2).unwrap(),
+            (certificate.clone(), ZodaVersion::V2)
+        );
+
+        assert!(verifier
+            .clone()
+            .with_min_version(ZodaVersion::V2)
+            .verify(&v1)
+            .is_err());
+        assert!(VersionedVerifier::new([0; 32]).verify(&v2).is_err());
+    }
+
+    #[test]
+    fn rejects_unknown_and_tampered() {
+        let verifier = VersionedVerifier::new(ZodaParams::new(4).digest());
+        let mut v1 = v1::encode_certificate(&certificate());
+
+        let mut unknown = v1.clone();
+        unknown[0] = 3;
+        assert!(verifier.verify(&unknown).is_err());
+        assert!(verifier.verify(&[]).is_err());
+
+        // first byte of the data root the samples open against
+        v1[1] ^= 1;
+        assert!(v1::decode_certificate(&v1).is_ok());
+        assert!(verifier.verify(&v1).is_err());
+    }
+}
@@ -0,0 +1,147 @@
+use anyhow::{bail, Result};
+
+use crate::bitmap::AvailabilityBitmap;
+use crate::decode::{skip_trailing, DecodeMode};
+
+// Message encoding:
+//   kind (1) | data root (32) | width (4, BE) | bitmap
+// The bitmap is exactly as long as the width requires and its padding bits
+// are zero, so every message has a single encoding.
+const WANT: u8 = 1;
+const HAVE: u8 = 2;
+
+// Cells of the square under `data_root` a peer asks for
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct WantList {
+    pub data_root: [u8; 32],
+    pub cells: AvailabilityBitmap,
+}
+
+// Cells of the square under `data_root` a peer can serve
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct HaveList {
+    pub data_root: [u8; 32],
+    pub cells: AvailabilityBitmap,
+}
+
+impl WantList {
+    pub fn new(data_root: [u8; 32], cells: AvailabilityBitmap) -> Self {
+        Self { data_root, cells }
+    }
+
+    // Everything not yet held
+    pub fn missing_from(have: &HaveList) -> Self {
+        Self::new(have.data_root, have.cells.complement())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.cells.count() == 0
+    }
+
+    // Adds the wants of another peer, e.g. to forward a single request
+    pub fn merge(&mut self, other: &WantList) -> Result<()> {
+        check_root(&self.data_root, &other.data_root)?;
+        self.cells.merge(&other.cells)
+    }
+
+    // Drops the cells that arrived or are already on their way
+    pub fn subtract(&mut self, received: &AvailabilityBitmap) -> Result<()> {
+        self.cells.subtract(received)
+    }
+
+    pub fn encode(&self) -> Vec<u8> {
+        encode(WANT, &self.data_root, &self.cells)
+    }
+
+    pub fn decode(bytes: &[u8]) -> Result<Self> {
+        Ok(Self::decode_with_mode(bytes, DecodeMode::Strict)?.0)
+    }
+
+    // Also returns the warnings for whatever a lenient decode skipped
+    pub fn decode_with_mode(bytes: &[u8], mode: DecodeMode) -> Result<(Self, Vec<String>)> {
+        let (data_root, cells, warnings) = decode(WANT, bytes, mode)?;
+        Ok((Self { data_root, cells }, warnings))
+    }
+}
+
+impl HaveList {
+    pub fn new(data_root: [u8; 32], cells: AvailabilityBitmap) -> Self {
+        Self { data_root, cells }
+    }
+
+    pub fn merge(&mut self, other: &HaveList) -> Result<()> {
+        check_root(&self.data_root, &other.data_root)?;
+        self.cells.merge(&other.cells)
+    }
+
+    pub fn subtract(&mut self, cells: &AvailabilityBitmap) -> Result<()> {
+        self.cells.subtract(cells)
+    }
+
+    // The cells to send in answer to `want`
+    pub fn serve(&self, want: &WantList) -> Result<AvailabilityBitmap> {
+        check_root(&self.data_root, &want.data_root)?;
+        let mut cells = want.cells.clone();
+        cells.intersect(&self.cells)?;
+        Ok(cells)
+    }
+
+    pub fn encode(&self) -> Vec<u8> {
+        encode(HAVE, &self.data_root, &self.cells)
+    }
+
+    pub fn decode(bytes: &[u8]) -> Result<Self> {
+        Ok(Self::decode_with_mode(bytes, DecodeMode::Strict)?.0)
+    }
+
+    // Also returns the warnings for whatever a lenient decode skipped
+    pub fn decode_with_mode(bytes: &[u8], mode: DecodeMode) -> Result<(Self, Vec<String>)> {
+        let (data_root, cells, warnings) = decode(HAVE, bytes, mode)?;
+        Ok((Self { data_root, cells }, warnings))
+    }
+}
+
+fn check_root(ours: &[u8; 32], theirs: &[u8; 32]) -> Result<()> {
+    if ours != theirs {
+        bail!("lists are for different data roots");
+    }
+    Ok(())
+}
+
+fn encode(kind: u8, data_root: &[u8; 32], cells: &AvailabilityBitmap) -> Vec<u8> {
+    let mut out = Vec::with_capacity(1 + 32 + 4 + cells.as_bytes().len());
+    out.push(kind);
+    out.extend_from_slice(data_root);
+    out.extend_from_slice(&(cells.width() as u32).to_be_bytes());
+    out.extend_from_slice(cells.as_bytes());
+    out
+}
+
+type Decoded = ([u8; 32], AvailabilityBitmap, Vec<String>);
+
+fn decode(kind: u8, bytes: &[u8], mode: DecodeMode) -> Result<Decoded> {
+    if bytes.len() < 1 + 32 + 4 {
+        bail!("unexpected end of input");
+    }
+    if bytes[0] != kind {
+        bail!("unexpected message kind {}", bytes[0]);
+    }
+    let data_root = bytes[1..33].try_into()?;
+    let width = u32::from_be_bytes(bytes[33..37].try_into()?) as usize;
+    if width < 2 || !width.is_power_of_two() {
+        bail!("extended width must be a power of two, got {}", width);
+    }
+    // a lenient decode reads the bitmap's length and skips what follows
+    let rest = &bytes[37..];
+    let len = match mode {
+        DecodeMode::Strict => rest.len(),
+        DecodeMode::Lenient => width
+            .checked_mul(width)
+            .map_or(rest.len(), |cells| cells.div_ceil(8).min(rest.len())),
+    };
+    let mut warnings = Vec::new();
+    skip_trailing(mode, rest.len() - len, &mut warnings)?;
+    // rejects the wrong length and set padding bits
+    let cells = AvailabilityBitmap::from_bytes(width, &rest[..len])?;
+    Ok((data_root, cells, warnings))
+}
@@ -0,0 +1,290 @@
+use anyhow::{bail, Result};
+
+use crate::codecs::BINIUS_RS_128;
+use crate::decode::skip_trailing;
+pub use crate::decode::DecodeMode;
+use crate::hashers::SHA256;
+use crate::params::Limits;
+use crate::tree::{ExtendedDataSquare, Felt, LeafStrategy, SHARE_SIZE};
+
+pub const MAGIC: &[u8; 4] = b"ZODA";
+pub const VERSION: u8 = 3;
+
+// Serialized square layout:
+//   magic (4) | version (1) | codec id len (1) | codec id
+//   | hasher id len (1) | hasher id | width (4, BE) | leaf strategy (1)
+//   | extensions len (2, BE) | extensions
+//   | dr (width / 2 felts) | cells (width * width felts, column-major)
+// Version 1 had no leaf strategy byte, its squares all use per-felt leaves.
+// Version 2 had no extensions. Felts are written as 16 big-endian bytes.
+//
+// From version 3 on, newer versions only add fields inside the extensions or
+// after the existing layout, so a lenient reader can skip what it doesn't
+// know and still read everything it does, see `DecodeMode`.
+//
+// Extensions are a sequence of tag (1) | len (2, BE) | value. Known tags:
+//   1: digest of the `Context` a partial square is bound to (32)
+const EXT_CONTEXT: u8 = 1;
+
+impl ExtendedDataSquare {
+    pub fn to_bytes(&self) -> Vec<u8> {
+        self.to_bytes_with_version(VERSION)
+    }
+
+    // For callers that pin a format, like `v1`. Only versions 2 and up are
+    // supported here. The context isn't written, a receiver binds the square
+    // to the one it expects with `with_context`
+    pub(crate) fn to_bytes_with_version(&self, version: u8) -> Vec<u8> {
+        let width = self.width();
+        let header = Header {
+            version,
+            codec_id: BINIUS_RS_128,
+            hasher_id: SHA256,
+            width,
+            leaf_strategy: self.leaf_strategy(),
+            context: None,
+        };
+        let mut out = Vec::with_capacity(header.len() + (width / 2 + width * width) * 16);
+
+        header.write(&mut out);
+        for elem in self.dr().iter().chain(self.cols().iter().flatten()) {
+            out.extend_from_slice(&elem.val().to_be_bytes());
+        }
+
+        out
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        Self::from_bytes_with_limits(bytes, &Limits::default())
+    }
+
+    // Rejects squares over `limits` before allocating any of their cells
+    pub fn from_bytes_with_limits(bytes: &[u8], limits: &Limits) -> Result<Self> {
+        Ok(Self::from_bytes_with_mode(bytes, limits, DecodeMode::Strict)?.0)
+    }
+
+    // Also returns the warnings for whatever a lenient decode skipped
+    pub fn from_bytes_with_mode(
+        bytes: &[u8],
+        limits: &Limits,
+        mode: DecodeMode,
+    ) -> Result<(Self, Vec<String>)> {
+        let mut reader = Reader::with_mode(bytes, mode);
+        let header = Header::read(&mut reader)?;
+        let width = header.width;
+        limits.check(width / 2, SHARE_SIZE)?;
+
+        let dr = reader.felts(width / 2)?;
+        let mut cols = Vec::with_capacity(width);
+        for _ in 0..width {
+            cols.push(reader.felts(width)?);
+        }
+        reader.finish()?;
+
+        let eds = Self::from_extended_cols_with(cols, dr, header.leaf_strategy)?;
+        Ok((eds, reader.into_warnings()))
+    }
+}
+
+pub(crate) struct Header<'a> {
+    pub(crate) version: u8,
+    pub(crate) codec_id: &'a str,
+    pub(crate) hasher_id: &'a str,
+    // extended width
+    pub(crate) width: usize,
+    pub(crate) leaf_strategy: LeafStrategy,
+    // the `EXT_CONTEXT` extension, only written from version 3 on
+    pub(crate) context: Option<[u8; 32]>,
+}
+
+impl<'a> Header<'a> {
+    // the largest a header can get, with both ids and the extensions at their
+    // maximum length
+    pub(crate) const MAX_LEN: usize = 4 + 1 + 1 + 255 + 1 + 255 + 4 + 1 + 2 + u16::MAX as usize;
+
+    pub(crate) fn len(&self) -> usize {
+        let strategy = usize::from(self.version > 1);
+        let extensions = if self.version > 2 {
+            2 + self.extensions().len()
+        } else {
+            0
+        };
+        4 + 1 + 1 + self.codec_id.len() + 1 + self.hasher_id.len() + 4 + strategy + extensions
+    }
+
+    pub(crate) fn write(&self, out: &mut Vec<u8>) {
+        out.extend_from_slice(MAGIC);
+        out.push(self.version);
+        out.push(self.codec_id.len() as u8);
+        out.extend_from_slice(self.codec_id.as_bytes());
+        out.push(self.hasher_id.len() as u8);
+        out.extend_from_slice(self.hasher_id.as_bytes());
+        out.extend_from_slice(&(self.width as u32).to_be_bytes());
+        if self.version > 1 {
+            out.push(self.leaf_strategy.id());
+        }
+        if self.version > 2 {
+            let extensions = self.extensions();
+            out.extend_from_slice(&(extensions.len() as u16).to_be_bytes());
+            out.extend_from_slice(&extensions);
+        }
+    }
+
+    fn extensions(&self) -> Vec<u8> {
+        let mut out = Vec::new();
+        if let Some(context) = &self.context {
+            out.push(EXT_CONTEXT);
+            out.extend_from_slice(&(context.len() as u16).to_be_bytes());
+            out.extend_from_slice(context);
+        }
+        out
+    }
+
+    pub(crate) fn read(reader: &mut Reader<'a>) -> Result<Self> {
+        if reader.take(4)? != MAGIC {
+            bail!("not a serialized square");
+        }
+        let version = reader.u8()?;
+        if version == 0 || (version > VERSION && reader.mode == DecodeMode::Strict) {
+            bail!("unsupported square version {}", version);
+        }
+        if version > VERSION {
+            reader.warn(format!(
+                "reading square version {} as version {}",
+                version, VERSION
+            ));
+        }
+
+        // felt squares are always extended with the binius code
+        let codec_id = reader.id()?;
+        if codec_id != BINIUS_RS_128 {
+            bail!("unsupported codec {}", codec_id);
+        }
+
+        let hasher_id = reader.id()?;
+        if hasher_id != SHA256 {
+            bail!("unsupported hasher {}", hasher_id);
+        }
+
+        let width = reader.u32()? as usize;
+        let leaf_strategy = match version {
+            1 => LeafStrategy::PerFelt,
+            _ => LeafStrategy::from_id(reader.u8()?)?,
+        };
+
+        let extensions = match version {
+            1 | 2 => &[][..],
+            _ => {
+                let len = u16::from_be_bytes(reader.take(2)?.try_into()?) as usize;
+                reader.take(len)?
+            }
+        };
+        let context = read_extensions(reader, extensions)?;
+
+        Ok(Self {
+            version,
+            codec_id,
+            hasher_id,
+            width,
+            leaf_strategy,
+            context,
+        })
+    }
+}
+
+// The context digest of the known extensions. Unknown tags, and anything
+// after an entry running past the end, are from a newer writer.
+fn read_extensions(reader: &mut Reader, mut extensions: &[u8]) -> Result<Option<[u8; 32]>> {
+    let mut context = None;
+    while !extensions.is_empty() {
+        let entry = extensions.get(..3).and_then(|head| {
+            let len = u16::from_be_bytes([head[1], head[2]]) as usize;
+            extensions.get(3..3 + len).map(|value| (head[0], value))
+        });
+        let Some((tag, value)) = entry else {
+            if reader.mode == DecodeMode::Strict {
+                bail!("{} bytes of malformed header extensions", extensions.len());
+            }
+            reader.warn(format!(
+                "skipped {} bytes of unknown header extensions",
+                extensions.len()
+            ));
+            break;
+        };
+        extensions = &extensions[3 + value.len()..];
+
+        match tag {
+            EXT_CONTEXT => context = Some(value.try_into()?),
+            _ if reader.mode == DecodeMode::Strict => {
+                bail!("unknown header extension {}", tag)
+            }
+            _ => reader.warn(format!("skipped unknown header extension {}", tag)),
+        }
+    }
+    Ok(context)
+}
+
+pub(crate) struct Reader<'a> {
+    bytes: &'a [u8],
+    mode: DecodeMode,
+    warnings: Vec<String>,
+}
+
+impl<'a> Reader<'a> {
+    pub(crate) fn new(bytes: &'a [u8]) -> Self {
+        Self::with_mode(bytes, DecodeMode::Strict)
+    }
+
+    pub(crate) fn with_mode(bytes: &'a [u8], mode: DecodeMode) -> Self {
+        Self {
+            bytes,
+            mode,
+            warnings: Vec::new(),
+        }
+    }
+
+    pub(crate) fn warn(&mut self, warning: String) {
+        self.warnings.push(warning);
+    }
+
+    pub(crate) fn into_warnings(self) -> Vec<String> {
+        self.warnings
+    }
+
+    pub(crate) fn take(&mut self, n: usize) -> Result<&'a [u8]> {
+        if self.bytes.len() < n {
+            bail!("unexpected end of input");
+        }
+        let (head, tail) = self.bytes.split_at(n);
+        self.bytes = tail;
+        Ok(head)
+    }
+
+    pub(crate) fn u8(&mut self) -> Result<u8> {
+        Ok(self.take(1)?[0])
+    }
+
+    pub(crate) fn u32(&mut self) -> Result<u32> {
+        Ok(u32::from_be_bytes(self.take(4)?.try_into()?))
+    }
+
+    // length-prefixed utf8 identifier
+    pub(crate) fn id(&mut self) -> Result<&'a str> {
+        let len = self.u8()? as usize;
+        Ok(std::str::from_utf8(self.take(len)?)?)
+    }
+
+    pub(crate) fn felt(&mut self) -> Result<Felt> {
+        Ok(Felt::new(u128::from_be_bytes(self.take(16)?.try_into()?)))
+    }
+
+    pub(crate) fn felts(&mut self, n: usize) -> Result<Vec<Felt>> {
+        (0..n).map(|_| self.felt()).collect()
+    }
+
+    // Trailing bytes are fields a newer writer appended
+    pub(crate) fn finish(&mut self) -> Result<()> {
+        let extra = std::mem::take(&mut self.bytes).len();
+        skip_trailing(self.mode, extra, &mut self.warnings)
+    }
+}
@@ -0,0 +1,62 @@
+// Flat certificate representation for zkVM guests (SP1, Risc0). The witness
+// types are plain arrays and integers that serialize trivially into a guest.
+// Guests build the crate without `std`, which leaves this module and `merkle`.
+use alloc::vec::Vec;
+
+use sha2::{Digest, Sha256};
+
+use crate::merkle::fold_path;
+
+// Longest proof path a witness can hold, enough for any square width we accept
+pub const MAX_PATH: usize = 32;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SampleWitness {
+    pub share: u128,
+    pub row: u32,
+    pub col: u32,
+    pub row_root: [u8; 32],
+    // cell -> row root, bottom-up
+    pub cell_path: [[u8; 32]; MAX_PATH],
+    pub cell_path_len: u8,
+    // row root -> data root, bottom-up
+    pub root_path: [[u8; 32]; MAX_PATH],
+    pub root_path_len: u8,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Witness {
+    pub data_root: [u8; 32],
+    // width of the extended square
+    pub width: u32,
+    pub samples: Vec<SampleWitness>,
+}
+
+impl Witness {
+    pub fn verify(&self) -> bool {
+        verify_witness(&self.data_root, self.width, &self.samples)
+    }
+}
+
+// Checks every sample against the data root. Takes borrowed parts so guests can
+// call it without building a `Witness`.
+pub fn verify_witness(data_root: &[u8; 32], width: u32, samples: &[SampleWitness]) -> bool {
+    let width = width as usize;
+    samples.iter().all(|sample| {
+        let (row, col) = (sample.row as usize, sample.col as usize);
+        if row >= width
+            || col >= width
+            || sample.cell_path_len as usize > MAX_PATH
+            || sample.root_path_len as usize > MAX_PATH
+        {
+            return false;
+        }
+
+        let leaf: [u8; 32] = Sha256::digest(sample.share.to_be_bytes()).into();
+        let cell_path = &sample.cell_path[..sample.cell_path_len as usize];
+        let root_path = &sample.root_path[..sample.root_path_len as usize];
+
+        fold_path(leaf, col, width, cell_path) == Some(sample.row_root)
+            && fold_path(sample.row_root, row, 2 * width, root_path) == Some(*data_root)
+    })
+}
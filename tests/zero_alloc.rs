@@ -0,0 +1,36 @@
+// Verification has to run without touching the heap, for zkVM guests and
+// embedded verifiers. Kept alone in its own binary so no other test
+// allocates while the count is taken.
+#![cfg(feature = "counting-alloc")]
+
+use zoda_rs::binius_field::BinaryField128b;
+use zoda_rs::certificate::ZodaCertificate;
+use zoda_rs::stats::{allocation_count, CountingAllocator};
+use zoda_rs::tree::{derive_dr_into, DataSquare, Felt};
+
+#[global_allocator]
+static ALLOC: CountingAllocator = CountingAllocator;
+
+#[test]
+fn verification_does_not_allocate() {
+    let cols: Vec<Vec<Felt>> = (0..8)
+        .map(|col| (0..8).map(|row| BinaryField128b::new(row * 8 + col + 1)).collect())
+        .collect();
+    let eds = DataSquare::new(cols).unwrap().extend().unwrap();
+    let data_root = eds.data_root().unwrap();
+    let x_root = eds.x_root().unwrap();
+    let proof = eds.prove_share(3, 12).unwrap();
+    let cell_proof = eds.prove_cell(5, 1).unwrap();
+    let coords: Vec<(usize, usize)> = (0..16).map(|i| (i, (i * 7) % 16)).collect();
+    let certificate = ZodaCertificate::new(&eds, &coords).unwrap();
+    let mut dr = [Felt::default(); 8];
+
+    let before = allocation_count().unwrap();
+    assert!(proof.verify(&data_root));
+    assert!(cell_proof.verify(&eds.cell(5, 1), &eds.row_roots()[5]));
+    derive_dr_into(&x_root, None, &mut dr);
+    certificate.verify().unwrap();
+    assert_eq!(allocation_count().unwrap(), before);
+
+    assert_eq!(dr, eds.dr());
+}